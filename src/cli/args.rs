@@ -1,7 +1,8 @@
 use clap::{Args, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 /// Power control arguments
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone)]
 pub struct PowerArgs {
     /// Power state (on/off)
     #[arg(value_enum)]
@@ -15,7 +16,7 @@ pub enum PowerState {
 }
 
 /// Sleep mode arguments
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone)]
 pub struct SleepArgs {
     /// Sleep duration in seconds
     #[arg(short, long)]
@@ -23,7 +24,7 @@ pub struct SleepArgs {
 }
 
 /// System subcommands
-#[derive(Subcommand, Debug)]
+#[derive(Subcommand, Debug, Clone)]
 pub enum SystemCommands {
     /// Reboot the device
     Reboot,
@@ -50,7 +51,25 @@ pub enum SystemCommands {
     },
 
     /// Get device statistics
-    Stats,
+    Stats {
+        /// Append a sample every --interval seconds to a CSV file until Ctrl+C
+        /// (default: stats.csv in the config directory)
+        #[arg(long, value_name = "FILE", conflicts_with = "history")]
+        record: Option<Option<PathBuf>>,
+
+        /// Seconds between recorded samples
+        #[arg(short, long, default_value = "60", requires = "record")]
+        interval: u64,
+
+        /// Summarize recorded samples with min/max/avg and sparklines
+        /// (default: stats.csv in the config directory)
+        #[arg(long, value_name = "FILE")]
+        history: Option<Option<PathBuf>>,
+
+        /// Time window for --history, e.g. 30m, 24h or 7d
+        #[arg(long, default_value = "24h", requires = "history")]
+        since: String,
+    },
 
     /// Save current configuration
     Save,
@@ -64,11 +83,14 @@ pub enum SystemCommands {
 }
 
 /// Info subcommands
-#[derive(Subcommand, Debug)]
+#[derive(Subcommand, Debug, Clone)]
 pub enum InfoCommands {
     /// Get device version
     Version,
 
+    /// Show firmware version and supported features
+    Capabilities,
+
     /// List available effects
     Effects,
 
@@ -91,7 +113,7 @@ pub enum ScreenFormat {
 }
 
 /// App management subcommands
-#[derive(Subcommand, Debug)]
+#[derive(Subcommand, Debug, Clone)]
 pub enum AppCommands {
     /// List all apps
     List,
@@ -123,10 +145,11 @@ pub enum AppCommands {
 }
 
 /// Notification arguments
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone)]
 pub struct NotifyArgs {
-    /// Notification text
-    pub text: String,
+    /// Notification text (available as `{{text}}` in templates)
+    #[arg(required_unless_present_any = ["file", "dismiss", "template"])]
+    pub text: Option<String>,
 
     /// Icon ID
     #[arg(short, long)]
@@ -171,10 +194,89 @@ pub struct NotifyArgs {
     /// Dismiss current notification
     #[arg(long)]
     pub dismiss: bool,
+
+    /// Render a named template from the config or the templates directory
+    #[arg(short = 'T', long, value_name = "NAME", conflicts_with = "file")]
+    pub template: Option<String>,
+
+    /// Template variable (repeatable); environment variables are available as `env.NAME`
+    #[arg(long = "var", value_name = "KEY=VALUE", requires = "template")]
+    pub vars: Vec<String>,
+
+    /// Read template variables as a JSON object from stdin
+    #[arg(long, requires = "template")]
+    pub stdin: bool,
+
+    /// Queue the notification if the device is unreachable (always on with
+    /// `preferences.offline_queue`)
+    #[arg(long)]
+    pub queue: bool,
+
+    /// Priority: low, normal, high or critical (critical ignores quiet hours)
+    #[arg(short = 'P', long, default_value = "normal")]
+    pub priority: awtrix3::models::Priority,
+
+    /// Wait until the notification has finished displaying or was dismissed
+    #[arg(long, conflicts_with = "dismiss")]
+    pub wait: bool,
+
+    /// Seconds to wait with --wait before giving up
+    #[arg(long, value_name = "SECS", default_value = "300", requires = "wait")]
+    pub wait_timeout: u32,
+}
+
+/// Notification history subcommands
+#[derive(Subcommand, Debug, Clone)]
+pub enum HistoryCommands {
+    /// List sent notifications, newest first
+    List {
+        /// Only notifications sent to this device
+        device: Option<String>,
+
+        /// Only notifications from the last window, e.g. 30m, 24h or 7d
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only notifications whose text contains this (case-insensitive)
+        #[arg(long)]
+        search: Option<String>,
+
+        /// Only notifications sent by this source (notify, resend, queue, serve)
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Only notifications that failed to deliver
+        #[arg(long)]
+        failed: bool,
+
+        /// Maximum number of entries to show
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Send a notification from the history again
+    Resend {
+        /// History entry ID
+        id: u64,
+
+        /// Device to send to instead of the original one
+        #[arg(long)]
+        to: Option<String>,
+    },
+}
+
+/// Offline queue subcommands
+#[derive(Subcommand, Debug, Clone)]
+pub enum QueueCommands {
+    /// Deliver notifications queued while devices were unreachable
+    Flush {
+        /// Only flush notifications for this device
+        device: Option<String>,
+    },
 }
 
 /// Custom app subcommands
-#[derive(Subcommand, Debug)]
+#[derive(Subcommand, Debug, Clone)]
 pub enum CustomCommands {
     /// Create or update a custom app
     Create {
@@ -223,7 +325,7 @@ pub enum CustomCommands {
 }
 
 /// Display control subcommands
-#[derive(Subcommand, Debug)]
+#[derive(Subcommand, Debug, Clone)]
 pub enum DisplayCommands {
     /// Set mood lighting
     Mood {
@@ -260,7 +362,7 @@ pub enum DisplayCommands {
 }
 
 /// Sound control subcommands
-#[derive(Subcommand, Debug)]
+#[derive(Subcommand, Debug, Clone)]
 pub enum SoundCommands {
     /// Play a sound
     Play {
@@ -286,7 +388,7 @@ pub enum SoundCommands {
 }
 
 /// Indicator arguments
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone)]
 pub struct IndicatorArgs {
     /// Indicator number (1-3) or 'all'
     pub indicator: String,
@@ -301,7 +403,7 @@ pub struct IndicatorArgs {
 }
 
 /// Settings subcommands
-#[derive(Subcommand, Debug)]
+#[derive(Subcommand, Debug, Clone)]
 pub enum SettingsCommands {
     /// Get current settings
     Get {
@@ -316,12 +418,43 @@ pub enum SettingsCommands {
 
         /// Setting value
         value: String,
+
+        /// Save the configuration on the device afterwards
+        #[arg(long)]
+        save: bool,
     },
 
     /// Import settings from file
     Import {
         /// Settings file (JSON)
         file: String,
+
+        /// Show what would change without applying
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Which settings from the file to send
+        #[arg(long, value_enum, default_value = "all")]
+        only: ImportScope,
+
+        /// Apply without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Save the configuration on the device afterwards
+        #[arg(long)]
+        save: bool,
+    },
+
+    /// Compare device settings with a file or another device
+    Diff {
+        /// Settings file (JSON) to compare against
+        #[arg(required_unless_present = "with")]
+        file: Option<String>,
+
+        /// Device name or host to compare against
+        #[arg(long, conflicts_with = "file")]
+        with: Option<String>,
     },
 
     /// Export settings to file
@@ -333,10 +466,161 @@ pub enum SettingsCommands {
 
     /// List all available settings
     List,
+
+    /// Copy settings from one configured device to others
+    Sync {
+        /// Source device name or host
+        #[arg(long)]
+        from: String,
+
+        /// Target device names or hosts (comma-separated or repeated)
+        #[arg(long, required = true, value_delimiter = ',')]
+        to: Vec<String>,
+
+        /// Only copy settings matching these key patterns (e.g. 'time_app.*')
+        #[arg(short, long, value_delimiter = ',')]
+        keys: Vec<String>,
+
+        /// Show what would change without applying
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the configuration on the target devices afterwards
+        #[arg(long)]
+        save: bool,
+    },
+
+    /// Named settings profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+}
+
+/// Settings profile subcommands
+#[derive(Subcommand, Debug, Clone)]
+pub enum ProfileCommands {
+    /// Save a profile from the device's current settings or a file
+    Save {
+        /// Profile name
+        name: String,
+
+        /// Settings file (JSON) to save instead of the device settings
+        #[arg(short, long)]
+        file: Option<String>,
+    },
+
+    /// Apply a profile to the device
+    Apply {
+        /// Profile name
+        name: String,
+    },
+
+    /// Restore the settings the device had before the last profile was applied
+    Revert,
+
+    /// List saved profiles
+    List,
+
+    /// Delete a profile
+    Delete {
+        /// Profile name
+        name: String,
+    },
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum ImportScope {
+    /// Send every setting in the file
+    All,
+    /// Send only settings that differ from the device
+    Changed,
+}
+
+/// Fleet plan/apply arguments
+#[derive(Args, Debug, Clone)]
+pub struct FleetArgs {
+    /// Fleet file (YAML or TOML)
+    pub file: String,
+
+    /// Only include these devices (comma-separated or repeated)
+    #[arg(long, value_delimiter = ',')]
+    pub only: Vec<String>,
+}
+
+/// Fleet apply arguments
+#[derive(Args, Debug, Clone)]
+pub struct ApplyArgs {
+    #[command(flatten)]
+    pub fleet: FleetArgs,
+
+    /// Apply without asking for confirmation
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Save the configuration on the devices after applying settings
+    #[arg(long)]
+    pub save: bool,
+}
+
+/// Daemon arguments
+#[derive(Args, Debug, Clone)]
+pub struct DaemonArgs {
+    /// Schedule check interval in seconds
+    #[arg(short, long, default_value = "30")]
+    pub interval: u64,
+}
+
+/// Exporter arguments
+#[derive(Args, Debug, Clone)]
+pub struct ExporterArgs {
+    /// Address to serve metrics on; use :9877 to listen on all interfaces
+    #[arg(short, long, default_value = "127.0.0.1:9877")]
+    pub listen: String,
+
+    /// Seconds between polls of each device
+    #[arg(short, long, default_value = "15")]
+    pub interval: u64,
+}
+
+/// Webhook server arguments
+#[derive(Args, Debug, Clone)]
+pub struct ServeArgs {
+    /// Address to accept webhooks on; use :9878 to listen on all interfaces
+    #[arg(short, long, default_value = "127.0.0.1:9878")]
+    pub listen: String,
+}
+
+/// Notification relay arguments
+#[derive(Args, Debug, Clone)]
+pub struct RelayArgs {
+    /// Address to accept notifications on, e.g. 127.0.0.1:9879
+    #[arg(short, long, default_value = "127.0.0.1:9879")]
+    pub listen: String,
+
+    /// Also accept newline-delimited JSON requests on this Unix socket
+    #[arg(long, value_name = "PATH")]
+    pub socket: Option<std::path::PathBuf>,
+
+    /// Seconds within which identical notifications to a device are dropped (0 to disable)
+    #[arg(long, default_value = "60")]
+    pub dedupe_window: u64,
+
+    /// Notifications accepted per sender (address or socket user) and minute (0 for no limit)
+    #[arg(long, default_value = "10")]
+    pub rate_limit: usize,
+
+    /// Notifications waiting per device before new ones are refused (0 for no limit)
+    #[arg(long, default_value = "100")]
+    pub max_queue: usize,
+
+    /// Seconds a notification without a duration is shown before the next one is sent
+    #[arg(long, default_value = "10")]
+    pub gap: u64,
 }
 
 /// Device management subcommands
-#[derive(Subcommand, Debug)]
+#[derive(Subcommand, Debug, Clone)]
 pub enum DeviceCommands {
     /// Discover devices on the network
     #[cfg(feature = "discovery")]
@@ -344,6 +628,22 @@ pub enum DeviceCommands {
         /// Discovery timeout in seconds
         #[arg(short, long, default_value = "5")]
         timeout: u64,
+
+        /// Also probe every host in a subnet, e.g. 192.168.1.0/24 (repeatable)
+        #[arg(long, value_name = "CIDR")]
+        scan: Vec<String>,
+
+        /// Maximum number of hosts probed at once when scanning
+        #[arg(long, default_value = "64")]
+        concurrency: usize,
+
+        /// Stop browsing once this many devices have been found
+        #[arg(long, value_name = "COUNT")]
+        expect: Option<usize>,
+
+        /// Choose devices to add to the config interactively
+        #[arg(long)]
+        add: bool,
     },
 
     /// Add a device to config
@@ -373,4 +673,22 @@ pub enum DeviceCommands {
         /// Device name or host
         device: Option<String>,
     },
+
+    /// Live health table of all configured devices
+    Watch {
+        /// Devices to watch (default: all configured devices)
+        devices: Vec<String>,
+
+        /// Seconds between polls
+        #[arg(short, long, default_value = "5")]
+        interval: u64,
+
+        /// Flag devices with less free heap than this
+        #[arg(long, value_name = "BYTES", default_value = "20000")]
+        low_heap: u32,
+
+        /// Poll once and exit
+        #[arg(long)]
+        once: bool,
+    },
 }
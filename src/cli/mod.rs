@@ -1,12 +1,15 @@
 use crate::config::Config;
-use anyhow::Result;
+use crate::output::{CommandOutput, OutputFormat};
+use anyhow::{Context, Result};
+use awtrix3::{AwtrixError, ErrorKind};
 use clap::{Parser, Subcommand};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 mod args;
 pub use args::*;
 
 /// AWTRIX3 CLI - Control your LED matrix display
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(name = "awtrix")]
 #[command(about = "A modern CLI for controlling AWTRIX3 LED matrix displays")]
 #[command(version)]
@@ -16,10 +19,14 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub device: Option<String>,
 
-    /// Output JSON for scripting
+    /// Output JSON for scripting (same as --output-format json)
     #[arg(short, long, global = true)]
     pub json: bool,
 
+    /// Output format (defaults to preferences.default_format from the config)
+    #[arg(long, global = true, value_enum)]
+    pub output_format: Option<OutputFormat>,
+
     /// Enable verbose logging
     #[arg(short, long, global = true)]
     pub verbose: bool,
@@ -32,7 +39,7 @@ pub struct Cli {
     pub command: Commands,
 }
 
-#[derive(Subcommand, Debug)]
+#[derive(Subcommand, Debug, Clone)]
 pub enum Commands {
     /// Power management
     Power(PowerArgs),
@@ -61,6 +68,18 @@ pub enum Commands {
     /// Send notifications
     Notify(NotifyArgs),
 
+    /// Sent notifications
+    History {
+        #[command(subcommand)]
+        command: HistoryCommands,
+    },
+
+    /// Notifications kept for unreachable devices
+    Queue {
+        #[command(subcommand)]
+        command: QueueCommands,
+    },
+
     /// Custom app management
     Custom {
         #[command(subcommand)]
@@ -94,6 +113,24 @@ pub enum Commands {
         command: DeviceCommands,
     },
 
+    /// Show changes needed to match a fleet file
+    Plan(FleetArgs),
+
+    /// Reconcile devices with a fleet file
+    Apply(ApplyArgs),
+
+    /// Run in the background, applying scheduled profiles
+    Daemon(DaemonArgs),
+
+    /// Serve device stats as Prometheus metrics
+    Exporter(ExporterArgs),
+
+    /// Receive webhooks and forward them as notifications
+    Serve(ServeArgs),
+
+    /// Accept notifications from local senders and show them one at a time by priority
+    Relay(RelayArgs),
+
     /// Generate shell completions
     Completions {
         /// Shell to generate completions for
@@ -102,64 +139,179 @@ pub enum Commands {
     },
 }
 
+impl Commands {
+    /// Whether running the command twice has the same effect as once, so it
+    /// can be repeated after a device moved
+    fn is_read_only(&self) -> bool {
+        match self {
+            Commands::Info { .. } => true,
+            Commands::System { command } => matches!(command, SystemCommands::Stats { .. }),
+            Commands::App { command } => matches!(command, AppCommands::List),
+            Commands::Settings { command } => matches!(
+                command,
+                SettingsCommands::Get { .. }
+                    | SettingsCommands::Diff { .. }
+                    | SettingsCommands::Export { .. }
+                    | SettingsCommands::List
+            ),
+            _ => false,
+        }
+    }
+}
+
 impl Cli {
-    pub async fn execute(self, config: Config) -> Result<()> {
-        // Get device host
-        let device_host = self.get_device_host(&config)?;
+    /// Resolve the output format: --json > --output-format > config preference
+    pub fn output_format(&self, config: &Config) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else if let Some(format) = self.output_format {
+            format
+        } else {
+            OutputFormat::from_name(&config.preferences.default_format)
+                .unwrap_or(OutputFormat::Table)
+        }
+    }
 
-        // Create client
-        let client = awtrix3::Client::new(&device_host)?;
+    pub async fn execute(self, mut config: Config) -> Result<CommandOutput> {
+        let used_device = AtomicBool::new(false);
+        let result = self.clone().run(config.clone(), &used_device).await;
+
+        // Follow a configured device that moved, then try once more if that
+        // cannot repeat something the device already did
+        match result {
+            Err(e)
+                if used_device.load(Ordering::Relaxed)
+                    && crate::output::error_kind(&e) == ErrorKind::Unreachable =>
+            {
+                let Ok(device) = self.get_device(&config) else {
+                    return Err(e);
+                };
+                match config.relocate_device(&device).await {
+                    Ok(Some(_)) if self.command.is_read_only() => {
+                        self.run(config, &used_device).await
+                    }
+                    // The request may have reached the device before failing
+                    Ok(Some(host)) => Err(e).with_context(|| {
+                        format!(
+                            "Device '{}' moved to {}; run the command again to use it",
+                            device, host
+                        )
+                    }),
+                    Ok(None) => Err(e),
+                    Err(locate) => {
+                        tracing::warn!("Could not look for device '{}': {:#}", device, locate);
+                        Err(e)
+                    }
+                }
+            }
+            result => result,
+        }
+    }
+
+    async fn run(self, config: Config, used_device: &AtomicBool) -> Result<CommandOutput> {
+        // Resolve the device lazily, since some commands only need the config
+        let device = self.get_device(&config);
+        let target = device.as_ref().ok().cloned();
+        let host = device.map(|device| config.resolve_host(&device));
+        let client = move || async move {
+            used_device.store(true, Ordering::Relaxed);
+            Ok::<_, anyhow::Error>(awtrix3::Client::new(host?)?)
+        };
 
         // Execute command
         match self.command {
-            Commands::Power(args) => crate::commands::power::execute(client, args).await,
-            Commands::Sleep(args) => crate::commands::power::sleep(client, args).await,
-            Commands::System { command } => crate::commands::system::execute(client, command).await,
-            Commands::Info { command } => crate::commands::info::execute(client, command).await,
-            Commands::App { command } => crate::commands::apps::execute(client, command).await,
-            Commands::Notify(args) => crate::commands::notify::execute(client, args).await,
-            Commands::Custom { command } => crate::commands::custom::execute(client, command).await,
+            Commands::Power(args) => crate::commands::power::execute(client().await?, args).await,
+            Commands::Sleep(args) => crate::commands::power::sleep(client().await?, args).await,
+            // History is read from the recording, so it does not need a device
+            Commands::System {
+                command:
+                    SystemCommands::Stats {
+                        history: Some(path),
+                        since,
+                        ..
+                    },
+            } => {
+                let path = match path {
+                    Some(path) => path,
+                    None => crate::commands::recording::default_path()?,
+                };
+                crate::commands::recording::history(&path, &since, &config)
+            }
+            Commands::System { command } => {
+                crate::commands::system::execute(client().await?, command).await
+            }
+            Commands::Info { command } => {
+                crate::commands::info::execute(client().await?, command).await
+            }
+            Commands::App { command } => {
+                crate::commands::apps::execute(client().await?, command).await
+            }
+            // History and the queue pick their own devices
+            Commands::History { command } => {
+                crate::commands::notify::history(command, &config).await
+            }
+            Commands::Queue { command } => crate::commands::notify::queue(command, &config).await,
+            Commands::Notify(args) => {
+                let target = target.unwrap_or_default();
+                crate::commands::notify::execute(client().await?, &target, args, &config).await
+            }
+            Commands::Custom { command } => {
+                crate::commands::custom::execute(client().await?, command).await
+            }
             Commands::Display { command } => {
-                crate::commands::display::execute(client, command).await
+                crate::commands::display::execute(client().await?, command).await
             }
-            Commands::Sound { command } => crate::commands::sound::execute(client, command).await,
-            Commands::Indicator(args) => crate::commands::indicators::execute(client, args).await,
+            Commands::Sound { command } => {
+                let target = target.unwrap_or_default();
+                crate::commands::sound::execute(client().await?, command, &target, &config).await
+            }
+            Commands::Indicator(args) => {
+                crate::commands::indicators::execute(client().await?, args).await
+            }
+            // Sync names its own devices, so it does not need a default device
+            Commands::Settings {
+                command: command @ SettingsCommands::Sync { .. },
+            } => crate::commands::settings::sync(command, &config).await,
             Commands::Settings { command } => {
-                crate::commands::settings::execute(client, command).await
+                let target = target.unwrap_or_default();
+                crate::commands::settings::execute(client().await?, command, &config, &target).await
             }
             Commands::Device { command } => crate::commands::device::execute(command, config).await,
+            Commands::Plan(args) => crate::commands::fleet::plan(args, config).await,
+            Commands::Apply(args) => crate::commands::fleet::apply(args, config).await,
+            Commands::Daemon(args) => crate::commands::daemon::execute(args, config).await,
+            Commands::Exporter(args) => crate::commands::exporter::execute(args, config).await,
+            Commands::Serve(args) => crate::commands::serve::execute(args, config).await,
+            Commands::Relay(args) => crate::commands::relay::execute(args, config, target).await,
             Commands::Completions { shell } => {
                 Self::generate_completions(shell);
-                Ok(())
+                Ok(CommandOutput::empty())
             }
         }
     }
 
-    fn get_device_host(&self, config: &Config) -> Result<String> {
+    /// Device name from config, or a direct host/IP
+    fn get_device(&self, config: &Config) -> Result<String> {
         // Priority: CLI arg > env var > config file
         if let Some(device) = &self.device {
-            // Check if it's a device name from config
-            if let Some(device_config) = config.devices.get(device) {
-                Ok(device_config.host.clone())
-            } else {
-                // Assume it's a direct host/IP
-                Ok(device.clone())
-            }
+            Ok(device.clone())
         } else if let Ok(device) = std::env::var("AWTRIX_DEVICE") {
             Ok(device)
         } else if let Some(default) = &config.default_device {
-            if let Some(device_config) = config.devices.get(default) {
-                Ok(device_config.host.clone())
+            if config.devices.contains_key(default) {
+                Ok(default.clone())
             } else {
-                Err(anyhow::anyhow!(
+                Err(AwtrixError::Config(format!(
                     "Default device '{}' not found in config",
                     default
                 ))
+                .into())
             }
         } else {
-            Err(anyhow::anyhow!(
-                "No device specified. Use --device, set AWTRIX_DEVICE env var, or configure a default device"
-            ))
+            Err(AwtrixError::Config(
+                "No device specified. Use --device, set AWTRIX_DEVICE env var, or configure a default device".to_string(),
+            )
+            .into())
         }
     }
 
@@ -0,0 +1,353 @@
+use crate::error::{AwtrixError, Result};
+use semver::Version;
+use std::collections::BTreeMap;
+use std::future::Future;
+
+/// Optional firmware features that can be checked before use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Feature {
+    Effects,
+    Transitions,
+    AppLoop,
+    Screen,
+    MoodLight,
+    Reorder,
+}
+
+impl Feature {
+    /// All known features
+    pub const ALL: [Feature; 6] = [
+        Feature::Effects,
+        Feature::Transitions,
+        Feature::AppLoop,
+        Feature::Screen,
+        Feature::MoodLight,
+        Feature::Reorder,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Feature::Effects => "effects",
+            Feature::Transitions => "transitions",
+            Feature::AppLoop => "app loop",
+            Feature::Screen => "screen",
+            Feature::MoodLight => "mood light",
+            Feature::Reorder => "app reorder",
+        }
+    }
+
+    /// Oldest firmware providing the feature
+    pub fn min_version(&self) -> Version {
+        match self {
+            Feature::Effects | Feature::Transitions | Feature::AppLoop => Version::new(0, 70, 0),
+            Feature::Screen | Feature::MoodLight => Version::new(0, 80, 0),
+            Feature::Reorder => Version::new(0, 90, 0),
+        }
+    }
+
+    /// Error for a device whose firmware `found` lacks the feature
+    fn unsupported(&self, found: &str) -> AwtrixError {
+        let required = self.min_version();
+        AwtrixError::Unsupported {
+            feature: self.to_string(),
+            required: format!("{}.{}", required.major, required.minor),
+            found: found.to_string(),
+        }
+    }
+
+    /// Endpoint that can be probed with GET to detect the feature
+    ///
+    /// Mood light and reorder only accept POST, so they cannot be probed
+    /// without changing the display.
+    fn probe(&self) -> Option<&'static str> {
+        match self {
+            Feature::Effects => Some("/api/effects"),
+            Feature::Transitions => Some("/api/transitions"),
+            Feature::AppLoop => Some("/api/loop"),
+            Feature::Screen => Some("/api/screen"),
+            Feature::MoodLight | Feature::Reorder => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Firmware version and feature support of a device
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    /// Version string as reported by the device
+    pub raw_version: String,
+
+    /// Parsed firmware version, if the version string could be parsed
+    pub version: Option<Version>,
+
+    /// Results of probing optional endpoints
+    pub probed: BTreeMap<Feature, bool>,
+}
+
+impl Capabilities {
+    /// Check whether the device supports a feature.
+    ///
+    /// A probe result decides if there is one; otherwise the firmware version
+    /// does. Unknown versions are assumed to support everything.
+    pub fn supports(&self, feature: Feature) -> bool {
+        match self.probed.get(&feature) {
+            Some(&probed) => probed,
+            None => supported_by(self.version.as_ref(), feature),
+        }
+    }
+
+    /// Fail with a clear error if the device does not support a feature
+    pub fn require(&self, feature: Feature) -> Result<()> {
+        if self.supports(feature) {
+            Ok(())
+        } else {
+            Err(feature.unsupported(&self.raw_version))
+        }
+    }
+}
+
+/// Whether a firmware version provides a feature; unknown versions are
+/// assumed to provide everything
+fn supported_by(version: Option<&Version>, feature: Feature) -> bool {
+    version.map_or(true, |version| *version >= feature.min_version())
+}
+
+/// Parse a firmware version like `0.96`, `v0.96.1` or `0.96-beta` leniently
+pub fn parse_firmware_version(raw: &str) -> Option<Version> {
+    let raw = raw.trim().trim_start_matches(['v', 'V']);
+    let numeric: String = raw
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+
+    let mut parts = numeric
+        .split('.')
+        .filter(|p| !p.is_empty())
+        .map(|p| p.parse::<u64>());
+
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+
+    Some(Version::new(major, minor, patch))
+}
+
+impl super::Client {
+    /// Detect firmware version and optional features.
+    ///
+    /// Probes every feature endpoint, so use it for reporting, not before
+    /// each call. The result is cached per client (and shared between its
+    /// clones).
+    pub async fn capabilities(&self) -> Result<Capabilities> {
+        if let Some(cached) = self.cached_capabilities() {
+            return Ok(cached);
+        }
+
+        let raw_version = self.firmware_version().await?;
+        let version = parse_firmware_version(&raw_version);
+
+        let mut probed = BTreeMap::new();
+        for feature in Feature::ALL {
+            if let Some(available) = self.probe(feature).await {
+                probed.insert(feature, available);
+            }
+        }
+
+        let capabilities = Capabilities {
+            raw_version,
+            version,
+            probed,
+        };
+
+        if let Ok(mut cache) = self.capabilities.lock() {
+            *cache = Some(capabilities.clone());
+        }
+
+        Ok(capabilities)
+    }
+
+    /// Probe a single feature's endpoint
+    ///
+    /// `None` if the feature cannot be probed or the probe was inconclusive.
+    pub async fn probe(&self, feature: Feature) -> Option<bool> {
+        match self.get(feature.probe()?).await {
+            Ok(_) => Some(true),
+            Err(AwtrixError::Api { code: 404, .. }) => Some(false),
+            // Anything else says nothing about support
+            Err(_) => None,
+        }
+    }
+
+    /// Fail early if the device is known not to support a feature.
+    ///
+    /// Uses detected capabilities if there are any, otherwise the firmware
+    /// version, which is looked up once per client. If neither is available
+    /// the call is allowed through.
+    pub async fn require(&self, feature: Feature) -> Result<()> {
+        if let Some(capabilities) = self.cached_capabilities() {
+            return capabilities.require(feature);
+        }
+        match self.firmware_version().await {
+            Ok(raw) if !supported_by(parse_firmware_version(&raw).as_ref(), feature) => {
+                Err(feature.unsupported(&raw))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Make a feature's request after checking the firmware supports it
+    ///
+    /// A device that answers 404 anyway, e.g. a build without the feature,
+    /// also fails with [`AwtrixError::Unsupported`].
+    pub(crate) async fn for_feature<T>(
+        &self,
+        feature: Feature,
+        request: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        self.require(feature).await?;
+        match request.await {
+            Err(AwtrixError::Api { code: 404, .. }) => {
+                let found = self
+                    .firmware_version()
+                    .await
+                    .unwrap_or_else(|_| "unknown".to_string());
+                Err(feature.unsupported(&found))
+            }
+            other => other,
+        }
+    }
+
+    /// Firmware version string, requested once and shared between clones
+    async fn firmware_version(&self) -> Result<String> {
+        if let Some(version) = self.firmware.lock().ok().and_then(|cache| cache.clone()) {
+            return Ok(version);
+        }
+
+        let version = self.get_version().await?.trim().to_string();
+        if let Ok(mut cache) = self.firmware.lock() {
+            *cache = Some(version.clone());
+        }
+        Ok(version)
+    }
+
+    fn cached_capabilities(&self) -> Option<Capabilities> {
+        self.capabilities
+            .lock()
+            .ok()
+            .and_then(|cache| cache.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_parse_firmware_version() {
+        assert_eq!(parse_firmware_version("0.96"), Some(Version::new(0, 96, 0)));
+        assert_eq!(
+            parse_firmware_version("v0.96.1\n"),
+            Some(Version::new(0, 96, 1))
+        );
+        assert_eq!(
+            parse_firmware_version("0.98-beta"),
+            Some(Version::new(0, 98, 0))
+        );
+        assert_eq!(parse_firmware_version("unknown"), None);
+    }
+
+    #[test]
+    fn test_require() {
+        let capabilities = Capabilities {
+            raw_version: "0.85".to_string(),
+            version: parse_firmware_version("0.85"),
+            probed: BTreeMap::from([(Feature::Screen, false)]),
+        };
+
+        assert!(capabilities.require(Feature::Effects).is_ok());
+        assert!(capabilities.require(Feature::MoodLight).is_ok());
+        assert!(capabilities.require(Feature::Screen).is_err());
+        let err = capabilities.require(Feature::Reorder).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "app reorder is not available on this device (requires firmware >= 0.90, found 0.85)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_old_firmware_fails_before_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/version"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("0.85"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = crate::Client::new(server.uri()).unwrap();
+        let apps = vec!["Time".to_string()];
+        assert!(matches!(
+            client.reorder_apps(&apps).await,
+            Err(AwtrixError::Unsupported { .. })
+        ));
+        // The version is looked up once and the reorder is never sent
+        client.reorder_apps(&apps).await.unwrap_err();
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_missing_endpoint_is_unsupported() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/version"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("0.96"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // New enough, but the endpoint answers 404
+        let client = crate::Client::new(server.uri()).unwrap();
+        let err = client.get_effects().await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "effects is not available on this device (requires firmware >= 0.70, found 0.96)"
+        );
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_are_cached() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/version"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("0.96"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/effects"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(["Fade"]))
+            .mount(&server)
+            .await;
+
+        let client = crate::Client::new(server.uri()).unwrap();
+        let capabilities = client.capabilities().await.unwrap();
+
+        assert_eq!(capabilities.version, Some(Version::new(0, 96, 0)));
+        assert!(capabilities.supports(Feature::Effects));
+        // Unmocked endpoints answer 404
+        assert!(!capabilities.supports(Feature::Screen));
+        // Not probed, so decided by the version
+        assert!(capabilities.supports(Feature::MoodLight));
+
+        // A clone shares the cache, so /version is only requested once
+        client.clone().capabilities().await.unwrap();
+    }
+}
@@ -1,3 +1,4 @@
+use super::Feature;
 use crate::error::{AwtrixError, Result};
 use crate::models::*;
 use reqwest::Response;
@@ -111,7 +112,9 @@ impl super::Client {
 
     /// Get available effects
     pub async fn get_effects(&self) -> Result<Vec<String>> {
-        let response = self.get("/api/effects").await?;
+        let response = self
+            .for_feature(Feature::Effects, self.get("/api/effects"))
+            .await?;
         let json = Self::get_json_value(response).await?;
 
         if let Some(effects) = json.as_array() {
@@ -127,7 +130,9 @@ impl super::Client {
 
     /// Get available transitions
     pub async fn get_transitions(&self) -> Result<Vec<String>> {
-        let response = self.get("/api/transitions").await?;
+        let response = self
+            .for_feature(Feature::Transitions, self.get("/api/transitions"))
+            .await?;
         let json = Self::get_json_value(response).await?;
 
         if let Some(transitions) = json.as_array() {
@@ -166,6 +171,13 @@ impl super::Client {
         Ok(())
     }
 
+    /// Reorder apps in the loop
+    pub async fn reorder_apps(&self, apps: &[String]) -> Result<()> {
+        self.for_feature(Feature::Reorder, self.post_json("/api/reorder", &apps))
+            .await?;
+        Ok(())
+    }
+
     /// Create or update custom app
     pub async fn create_custom_app(&self, name: &str, app: &CustomApp) -> Result<()> {
         let url = format!("/api/custom?name={}", name);
@@ -206,7 +218,11 @@ impl super::Client {
             payload.insert("kelvin".to_string(), serde_json::Value::Number(k.into()));
         }
 
-        self.post_json("/api/moodlight", &payload).await?;
+        self.for_feature(
+            Feature::MoodLight,
+            self.post_json("/api/moodlight", &payload),
+        )
+        .await?;
         Ok(())
     }
 
@@ -258,6 +274,35 @@ impl super::Client {
         Ok(())
     }
 
+    /// List file names in a directory of the device filesystem (e.g. `/ICONS`)
+    pub async fn list_files(&self, dir: &str) -> Result<Vec<String>> {
+        let response = self.get(&format!("/list?dir={}", dir)).await?;
+        let json = Self::get_json_value(response).await?;
+
+        Ok(json
+            .as_array()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|e| e.get("type").and_then(|t| t.as_str()) != Some("dir"))
+                    .filter_map(|e| e.get("name").and_then(|n| n.as_str()))
+                    .map(|name| name.rsplit('/').next().unwrap_or(name).to_string())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Upload a file to the device filesystem (e.g. `/ICONS/1234.gif`)
+    pub async fn upload_file(&self, path: &str, contents: Vec<u8>) -> Result<()> {
+        let url = self.build_url("/edit")?;
+        let part = reqwest::multipart::Part::bytes(contents).file_name(path.to_string());
+        let form = reqwest::multipart::Form::new().part("data", part);
+
+        let response = self.client.post(url).multipart(form).send().await?;
+        self.handle_response(response).await?;
+        Ok(())
+    }
+
     /// Reboot device
     pub async fn reboot(&self) -> Result<()> {
         self.post("/api/reboot").await?;
@@ -285,8 +330,6 @@ impl super::Client {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::models::*;
 
     #[tokio::test]
     async fn test_client_creation() {
@@ -300,4 +343,27 @@ mod tests {
         let url = client.build_url("/api/stats").unwrap();
         assert_eq!(url.as_str(), "http://192.168.1.100/api/stats");
     }
+
+    #[test]
+    fn test_url_building_with_prefix() {
+        let client = super::super::Client::new("https://proxy/awtrix/lobby").unwrap();
+        let url = client.build_url("/api/stats").unwrap();
+        assert_eq!(url.as_str(), "https://proxy/awtrix/lobby/api/stats");
+    }
+
+    #[tokio::test]
+    async fn test_requests_keep_path_prefix() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/awtrix/lobby/version"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("0.96"))
+            .mount(&server)
+            .await;
+
+        let client = super::super::Client::new(format!("{}/awtrix/lobby", server.uri())).unwrap();
+        assert_eq!(client.get_version().await.unwrap(), "0.96");
+    }
 }
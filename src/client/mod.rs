@@ -1,6 +1,15 @@
+pub mod address;
+pub mod capabilities;
 pub mod http;
+pub mod transaction;
+pub mod wait;
+
+pub use address::{DeviceAddress, IntoDeviceAddress};
+pub use capabilities::{Capabilities, Feature};
+pub use transaction::SettingsTransaction;
 
 use crate::error::{AwtrixError, Result};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use url::Url;
 // Models are re-exported from the main crate
@@ -20,52 +29,48 @@ static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
 /// AWTRIX3 client for interacting with the device
 #[derive(Debug, Clone)]
 pub struct Client {
-    base_url: Url,
+    address: DeviceAddress,
     client: reqwest::Client,
+    capabilities: Arc<Mutex<Option<Capabilities>>>,
+    /// Firmware version string, looked up once for feature checks
+    firmware: Arc<Mutex<Option<String>>>,
 }
 
 impl Client {
-    /// Create a new client with the device host
-    pub fn new<S: AsRef<str>>(host: S) -> Result<Self> {
-        let base_url =
-            if host.as_ref().starts_with("http://") || host.as_ref().starts_with("https://") {
-                Url::parse(host.as_ref())?
-            } else {
-                Url::parse(&format!("http://{}", host.as_ref()))?
-            };
+    /// Create a new client with the device address
+    pub fn new<A: IntoDeviceAddress>(address: A) -> Result<Self> {
+        Self::with_client(address, HTTP_CLIENT.clone())
+    }
 
+    /// Create a new client with a custom HTTP client
+    pub fn with_client<A: IntoDeviceAddress>(address: A, client: reqwest::Client) -> Result<Self> {
         Ok(Self {
-            base_url,
-            client: HTTP_CLIENT.clone(),
+            address: address.into_address()?,
+            client,
+            capabilities: Arc::default(),
+            firmware: Arc::default(),
         })
     }
 
-    /// Create a new client with a custom HTTP client
-    pub fn with_client<S: AsRef<str>>(host: S, client: reqwest::Client) -> Result<Self> {
-        let base_url =
-            if host.as_ref().starts_with("http://") || host.as_ref().starts_with("https://") {
-                Url::parse(host.as_ref())?
-            } else {
-                Url::parse(&format!("http://{}", host.as_ref()))?
-            };
-
-        Ok(Self { base_url, client })
+    /// Get the device address
+    pub fn address(&self) -> &DeviceAddress {
+        &self.address
     }
 
     /// Get the base URL
     pub fn base_url(&self) -> &Url {
-        &self.base_url
+        self.address.url()
     }
 
     /// Build a URL for an API endpoint
     fn build_url(&self, endpoint: &str) -> Result<Url> {
-        self.base_url.join(endpoint).map_err(|e| e.into())
+        self.address.join(endpoint)
     }
 }
 
 /// Builder for creating a customized client
 pub struct ClientBuilder {
-    host: Option<String>,
+    host: Option<Result<DeviceAddress>>,
     timeout: Duration,
     retry_count: u32,
     user_agent: String,
@@ -87,8 +92,9 @@ impl ClientBuilder {
         }
     }
 
-    pub fn host<S: Into<String>>(mut self, host: S) -> Self {
-        self.host = Some(host.into());
+    /// Device address; invalid addresses are reported by [`ClientBuilder::build`]
+    pub fn host<A: IntoDeviceAddress>(mut self, host: A) -> Self {
+        self.host = Some(host.into_address());
         self
     }
 
@@ -110,7 +116,7 @@ impl ClientBuilder {
     pub fn build(self) -> Result<Client> {
         let host = self
             .host
-            .ok_or_else(|| AwtrixError::Config("Host is required".to_string()))?;
+            .ok_or_else(|| AwtrixError::Config("Host is required".to_string()))??;
 
         let http_client = reqwest::Client::builder()
             .timeout(self.timeout)
@@ -0,0 +1,226 @@
+use crate::error::{AwtrixError, Result};
+use crate::models::Settings;
+
+/// All-or-nothing settings update.
+///
+/// Snapshots the current settings, applies each update in order, then re-reads
+/// the settings and verifies every updated field. If any step or the
+/// verification fails, the snapshot is restored.
+pub struct SettingsTransaction<'a> {
+    client: &'a super::Client,
+    updates: Vec<Settings>,
+    verify: bool,
+    save: bool,
+}
+
+impl super::Client {
+    /// Start a transactional settings update
+    pub fn settings_transaction(&self) -> SettingsTransaction<'_> {
+        SettingsTransaction {
+            client: self,
+            updates: Vec::new(),
+            verify: true,
+            save: false,
+        }
+    }
+}
+
+impl<'a> SettingsTransaction<'a> {
+    /// Add a settings update to apply
+    pub fn update(mut self, settings: Settings) -> Self {
+        self.updates.push(settings);
+        self
+    }
+
+    /// Re-read and verify the settings after applying (default: true)
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Persist the configuration with `save_config` after a successful apply
+    pub fn save(mut self, save: bool) -> Self {
+        self.save = save;
+        self
+    }
+
+    /// Apply the updates, returning the resulting settings
+    pub async fn commit(self) -> Result<Settings> {
+        let snapshot = self.client.get_settings().await?;
+
+        let mut expected = Settings::new();
+        for update in &self.updates {
+            if let Err(e) = self.client.update_settings(update).await {
+                return Err(self.rollback(&snapshot, e.to_string()).await);
+            }
+            expected.merge(update);
+        }
+
+        let result = if self.verify {
+            match self.client.get_settings().await {
+                Ok(actual) => {
+                    let mismatched = actual.diff(&expected);
+                    let mismatched: Vec<&str> = mismatched
+                        .iter()
+                        .filter(|change| change.desired.is_some())
+                        .map(|change| change.key.as_str())
+                        .collect();
+
+                    if !mismatched.is_empty() {
+                        let reason = format!("device did not accept: {}", mismatched.join(", "));
+                        return Err(self.rollback(&snapshot, reason).await);
+                    }
+                    actual
+                }
+                Err(e) => return Err(self.rollback(&snapshot, e.to_string()).await),
+            }
+        } else {
+            let mut result = snapshot.clone();
+            result.merge(&expected);
+            result
+        };
+
+        if self.save {
+            if let Err(e) = self.client.save_config().await {
+                return Err(self.rollback(&snapshot, e.to_string()).await);
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn rollback(&self, snapshot: &Settings, reason: String) -> AwtrixError {
+        match self.client.update_settings(snapshot).await {
+            Ok(()) => AwtrixError::Transaction {
+                reason,
+                rolled_back: true,
+            },
+            Err(e) => AwtrixError::Transaction {
+                reason: format!("{}; rollback failed: {}", reason, e),
+                rolled_back: false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn brightness(value: u8) -> Settings {
+        Settings {
+            brightness: Some(value),
+            ..Settings::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commit_verifies_and_saves() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/settings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(brightness(50)))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/settings"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/save"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = super::super::Client::new(server.uri()).unwrap();
+        let result = client
+            .settings_transaction()
+            .update(brightness(50))
+            .save(true)
+            .commit()
+            .await
+            .unwrap();
+
+        assert_eq!(result.brightness, Some(50));
+    }
+
+    #[tokio::test]
+    async fn test_rollback_on_verification_failure() {
+        let server = MockServer::start().await;
+        // The device keeps reporting the old value
+        Mock::given(method("GET"))
+            .and(path("/api/settings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(brightness(100)))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/settings"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = super::super::Client::new(server.uri()).unwrap();
+        let err = client
+            .settings_transaction()
+            .update(brightness(50))
+            .commit()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            AwtrixError::Transaction {
+                rolled_back: true,
+                ..
+            }
+        ));
+
+        // The second POST restores the snapshot
+        let requests = server.received_requests().await.unwrap();
+        let restored: Settings = requests
+            .iter()
+            .rev()
+            .find(|r| r.method.as_str() == "POST")
+            .map(|r| serde_json::from_slice(&r.body).unwrap())
+            .unwrap();
+        assert_eq!(restored.brightness, Some(100));
+    }
+
+    #[tokio::test]
+    async fn test_rollback_on_failed_update() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/settings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(brightness(100)))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/settings"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let client = super::super::Client::new(server.uri()).unwrap();
+        let err = client
+            .settings_transaction()
+            .update(brightness(50))
+            .commit()
+            .await
+            .unwrap_err();
+
+        // Restoring also fails against this device
+        assert!(matches!(
+            err,
+            AwtrixError::Transaction {
+                rolled_back: false,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::error::{AwtrixError, Result};
+use crate::models::Notification;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How often the device is polled while waiting
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long a notification gets to appear before it is assumed to be gone already
+const APPEAR_GRACE: Duration = Duration::from_secs(3);
+
+impl super::Client {
+    /// Send a notification and wait until it has finished displaying
+    ///
+    /// See [`wait_for_notification`](Self::wait_for_notification).
+    pub async fn notify_and_wait(
+        &self,
+        notification: Notification,
+        timeout: Duration,
+    ) -> Result<bool> {
+        let before = self.get_stats().await?.current_app;
+        self.notify(notification).await?;
+        self.wait_for_notification(before, timeout).await
+    }
+
+    /// Wait until the device stops showing notifications
+    ///
+    /// `before` is the app the stats reported (`app`) before the notification
+    /// was sent. The notification is taken to be showing once the stats report
+    /// another app, and to be done once they report something else again, so
+    /// the name the firmware gives notifications does not matter. Stacked
+    /// notifications are waited for as well, and held ones until they are
+    /// dismissed.
+    ///
+    /// Returns whether a notification was seen at all. If the app does not
+    /// change within a few seconds, waiting ends with `false`: the notification
+    /// may have finished between polls, so callers cannot be sure it was
+    /// displayed. An app loop switching apps on its own in the meantime is
+    /// mistaken for a notification.
+    ///
+    /// Fails with [`AwtrixError::Timeout`] if a notification is still showing
+    /// after `timeout`.
+    pub async fn wait_for_notification(
+        &self,
+        before: Option<String>,
+        timeout: Duration,
+    ) -> Result<bool> {
+        tokio::time::timeout(timeout, self.poll_notification(before))
+            .await
+            .map_err(|_| AwtrixError::Timeout {
+                what: "the notification to finish".to_string(),
+                seconds: timeout.as_secs(),
+            })?
+    }
+
+    async fn poll_notification(&self, before: Option<String>) -> Result<bool> {
+        let start = Instant::now();
+        // What the stats report while the notification is showing
+        let mut shown: Option<Option<String>> = None;
+        loop {
+            let app = self.get_stats().await?.current_app;
+            match &shown {
+                None if app != before => shown = Some(app),
+                None if start.elapsed() >= APPEAR_GRACE => return Ok(false),
+                Some(notification) if app != *notification => return Ok(true),
+                _ => {}
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn stats(server: &MockServer, app: &str, times: Option<u64>) {
+        let mock = Mock::given(method("GET"))
+            .and(path("/api/stats"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "app": app })));
+        match times {
+            Some(times) => mock.up_to_n_times(times).mount(server).await,
+            None => mock.mount(server).await,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_waits_until_notification_ends() {
+        let server = MockServer::start().await;
+        stats(&server, "Notification", Some(2)).await;
+        stats(&server, "Time", None).await;
+
+        let client = super::super::Client::new(server.uri()).unwrap();
+        let shown = client
+            .wait_for_notification(Some("Time".to_string()), Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(shown);
+
+        let polls = server.received_requests().await.unwrap().len();
+        assert_eq!(polls, 3);
+    }
+
+    #[tokio::test]
+    async fn test_notification_name_does_not_matter() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/notify"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // The loop moves on to the next app once the notification is gone
+        stats(&server, "Time", Some(1)).await;
+        stats(&server, "notify", Some(2)).await;
+        stats(&server, "Date", None).await;
+
+        let client = super::super::Client::new(server.uri()).unwrap();
+        let shown = client
+            .notify_and_wait(
+                Notification::builder().text("hi").build(),
+                Duration::from_secs(10),
+            )
+            .await
+            .unwrap();
+        assert!(shown);
+    }
+
+    #[tokio::test]
+    async fn test_reports_notification_never_seen() {
+        let server = MockServer::start().await;
+        stats(&server, "Time", None).await;
+
+        let client = super::super::Client::new(server.uri()).unwrap();
+        let shown = client
+            .wait_for_notification(Some("Time".to_string()), Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(!shown);
+    }
+
+    #[tokio::test]
+    async fn test_times_out_while_held() {
+        let server = MockServer::start().await;
+        stats(&server, "Notification", None).await;
+
+        let client = super::super::Client::new(server.uri()).unwrap();
+        let err = client
+            .wait_for_notification(Some("Time".to_string()), Duration::from_secs(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AwtrixError::Timeout { seconds: 1, .. }));
+    }
+}
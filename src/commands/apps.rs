@@ -1,57 +1,59 @@
 use crate::cli::AppCommands;
+use crate::output::CommandOutput;
 use anyhow::Result;
 
-pub async fn execute(client: awtrix3::Client, command: AppCommands) -> Result<()> {
-    match command {
+pub async fn execute(client: awtrix3::Client, command: AppCommands) -> Result<CommandOutput> {
+    let output = match command {
         AppCommands::List => {
             let loop_info = client.get_apps().await?;
-            println!("App Loop Status:");
-            if let Some(current) = &loop_info.current {
-                println!("  Current App: {}", current);
-            } else {
-                println!("  Current App: None");
-            }
+            let mut lines = vec!["App Loop Status:".to_string()];
+            lines.push(format!(
+                "  Current App: {}",
+                loop_info.current.as_deref().unwrap_or("None")
+            ));
 
             if !loop_info.apps.is_empty() {
-                println!("\nAvailable Apps:");
-                for app in loop_info.apps {
+                lines.push("\nAvailable Apps:".to_string());
+                for app in &loop_info.apps {
                     let status = if app.enabled.unwrap_or(true) {
                         "enabled"
                     } else {
                         "disabled"
                     };
-                    println!("  - {} ({})", app.name, status);
+                    lines.push(format!("  - {} ({})", app.name, status));
                 }
             } else {
-                println!("\nNo apps in loop");
+                lines.push("\nNo apps in loop".to_string());
             }
+
+            CommandOutput::data(&loop_info)?.text(lines.join("\n"))
         }
         AppCommands::Next => {
             client.next_app().await?;
-            println!("Switched to next app");
+            CommandOutput::message("Switched to next app")
         }
         AppCommands::Previous => {
             client.previous_app().await?;
-            println!("Switched to previous app");
+            CommandOutput::message("Switched to previous app")
         }
         AppCommands::Switch { name } => {
             client.switch_app(&name).await?;
-            println!("Switched to app: {}", name);
+            CommandOutput::message(format!("Switched to app: {}", name)).field("app", name)
         }
         AppCommands::Reorder { apps } => {
             // Split the comma-separated list
-            let app_list: Vec<&str> = apps.split(',').map(|s| s.trim()).collect();
+            let app_list: Vec<String> = apps.split(',').map(|s| s.trim().to_string()).collect();
 
-            // TODO: Implement reorder API call
-            // For now, just show what would be reordered
-            println!("Reordering apps to: {:?}", app_list);
-            println!("(Reorder functionality not yet implemented in API client)");
+            client.reorder_apps(&app_list).await?;
+            CommandOutput::message(format!("Apps reordered: {}", app_list.join(", ")))
+                .field("apps", app_list)
         }
         AppCommands::Update { file: _file } => {
             // TODO: Implement app configuration update
-            println!("App configuration update not yet implemented");
+            CommandOutput::message("App configuration update not yet implemented")
+                .field("status", "unsupported")
         }
-    }
+    };
 
-    Ok(())
+    Ok(output)
 }
@@ -1,12 +1,13 @@
 use crate::cli::CustomCommands;
+use crate::output::CommandOutput;
 // use crate::utils::parse_color;
 use anyhow::Result;
 use awtrix3::CustomApp;
 use std::fs;
 use std::time::Duration;
 
-pub async fn execute(client: awtrix3::Client, command: CustomCommands) -> Result<()> {
-    match command {
+pub async fn execute(client: awtrix3::Client, command: CustomCommands) -> Result<CommandOutput> {
+    let output = match command {
         CustomCommands::Create {
             name,
             text,
@@ -29,19 +30,18 @@ pub async fn execute(client: awtrix3::Client, command: CustomCommands) -> Result
 
             // Create the app
             client.create_custom_app(&name, &app).await?;
-            println!("Custom app '{}' created", name);
+            CommandOutput::message(format!("Custom app '{}' created", name)).field("app", name)
         }
         CustomCommands::Delete { name } => {
             client.delete_custom_app(&name).await?;
-            println!("Custom app '{}' deleted", name);
-        }
-        CustomCommands::List => {
-            println!("Custom app listing not yet implemented");
-            println!("Note: AWTRIX3 API does not provide a direct endpoint to list custom apps.");
-            println!(
-                "Custom apps are managed through the web interface or by knowing their names."
-            );
+            CommandOutput::message(format!("Custom app '{}' deleted", name)).field("app", name)
         }
+        CustomCommands::List => CommandOutput::message(
+            "Custom app listing not yet implemented\n\
+             Note: AWTRIX3 API does not provide a direct endpoint to list custom apps.\n\
+             Custom apps are managed through the web interface or by knowing their names.",
+        )
+        .field("status", "unsupported"),
         CustomCommands::Watch {
             name,
             file,
@@ -52,11 +52,13 @@ pub async fn execute(client: awtrix3::Client, command: CustomCommands) -> Result
                 return Err(anyhow::anyhow!("Configuration file not found: {}", file));
             }
 
-            println!(
-                "Watching file '{}' for app '{}' (interval: {}s)",
-                file, name, interval
-            );
-            println!("Press Ctrl+C to stop watching...");
+            if crate::output::is_human() {
+                println!(
+                    "Watching file '{}' for app '{}' (interval: {}s)",
+                    file, name, interval
+                );
+                println!("Press Ctrl+C to stop watching...");
+            }
 
             // TODO: Implement file watching
             // This would require:
@@ -70,7 +72,10 @@ pub async fn execute(client: awtrix3::Client, command: CustomCommands) -> Result
                 match fs::read_to_string(&file) {
                     Ok(content) => match serde_json::from_str::<CustomApp>(&content) {
                         Ok(app) => match client.create_custom_app(&name, &app).await {
-                            Ok(_) => println!("Updated app '{}' from file", name),
+                            Ok(_) if crate::output::is_human() => {
+                                println!("Updated app '{}' from file", name)
+                            }
+                            Ok(_) => {}
                             Err(e) => eprintln!("Failed to update app: {}", e),
                         },
                         Err(e) => eprintln!("Failed to parse JSON: {}", e),
@@ -85,7 +90,7 @@ pub async fn execute(client: awtrix3::Client, command: CustomCommands) -> Result
                 // and handle Ctrl+C gracefully
             }
         }
-    }
+    };
 
-    Ok(())
+    Ok(output)
 }
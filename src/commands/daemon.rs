@@ -0,0 +1,145 @@
+use crate::cli::DaemonArgs;
+use crate::config::{Config, OfflineQueue, ProfileStore};
+use crate::output::CommandOutput;
+use anyhow::Result;
+use awtrix3::AwtrixError;
+use chrono::Local;
+use std::time::Duration;
+
+pub async fn execute(args: DaemonArgs, config: Config) -> Result<CommandOutput> {
+    if args.interval == 0 {
+        return Err(
+            AwtrixError::Validation("Interval must be greater than 0 seconds".to_string()).into(),
+        );
+    }
+
+    // Validate the schedule up front rather than failing at 22:00
+    for entry in &config.schedule {
+        entry.time()?;
+    }
+    for quiet in &config.quiet_hours {
+        quiet.validate()?;
+    }
+
+    let store = ProfileStore::open()?;
+
+    if crate::output::is_human() {
+        println!(
+            "Daemon started with {} scheduled profile switch(es) (interval: {}s)",
+            config.schedule.len(),
+            args.interval
+        );
+        for entry in &config.schedule {
+            println!("  {} → {}", entry.at, entry.profile);
+        }
+        println!("Queued notifications are delivered when their devices are back");
+        println!("Press Ctrl+C to stop...");
+    }
+
+    let mut last = Local::now().naive_local();
+    let mut ticker = tokio::time::interval(Duration::from_secs(args.interval));
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => {
+                return Ok(CommandOutput::message("Daemon stopped"));
+            }
+        }
+
+        let now = Local::now().naive_local();
+        run_schedule(&config, &store, last, now).await;
+        last = now;
+
+        flush_queue(&config).await;
+    }
+}
+
+/// Deliver notifications queued for devices that are reachable again
+async fn flush_queue(config: &Config) {
+    let queue = match OfflineQueue::open() {
+        Ok(queue) => queue,
+        Err(e) => {
+            tracing::error!("Cannot open the notification queue: {:#}", e);
+            return;
+        }
+    };
+
+    match crate::commands::notify::flush_queue(config, &queue, None).await {
+        Ok(report) => {
+            for flush in report.devices {
+                if flush.sent > 0 {
+                    tracing::info!(
+                        "Delivered {} queued notification(s) to {}",
+                        flush.sent,
+                        flush.device
+                    );
+                }
+                if let Some(error) = flush.error {
+                    tracing::debug!(
+                        "{} queued notification(s) still waiting for {}: {}",
+                        flush.waiting,
+                        flush.device,
+                        error
+                    );
+                }
+            }
+            if report.expired > 0 {
+                tracing::warn!("Dropped {} expired queued notification(s)", report.expired);
+            }
+        }
+        Err(e) => tracing::error!("Failed to flush the notification queue: {:#}", e),
+    }
+}
+
+async fn run_schedule(
+    config: &Config,
+    store: &ProfileStore,
+    last: chrono::NaiveDateTime,
+    now: chrono::NaiveDateTime,
+) {
+    for entry in &config.schedule {
+        if !entry.is_due(last, now).unwrap_or(false) {
+            continue;
+        }
+
+        let devices = if entry.devices.is_empty() {
+            config.default_device.iter().cloned().collect()
+        } else {
+            entry.devices.clone()
+        };
+
+        if devices.is_empty() {
+            tracing::warn!(
+                "Schedule entry at {} has no devices and no default device is configured",
+                entry.at
+            );
+            continue;
+        }
+
+        for device in devices {
+            let result = match awtrix3::Client::new(config.resolve_host(&device)) {
+                Ok(client) => {
+                    crate::commands::settings::apply_profile(
+                        &client,
+                        store,
+                        &entry.profile,
+                        &config.snapshot_key(&device),
+                    )
+                    .await
+                }
+                Err(e) => Err(e.into()),
+            };
+
+            match result {
+                Ok(()) => tracing::info!("Applied profile '{}' to {}", entry.profile, device),
+                Err(e) => tracing::error!(
+                    "Failed to apply profile '{}' to {}: {}",
+                    entry.profile,
+                    device,
+                    e
+                ),
+            }
+        }
+    }
+}
@@ -1,46 +1,110 @@
 use crate::cli::*;
 use crate::config::{self, Config, DeviceConfig};
-use anyhow::Result;
+use crate::output::CommandOutput;
+#[cfg(feature = "discovery")]
+use crate::utils::discovery::DiscoveredDevice;
+use anyhow::{Context, Result};
+use awtrix3::AwtrixError;
+#[cfg(feature = "discovery")]
+use dialoguer::{Input, MultiSelect, Select};
+use serde_json::{json, Value};
+#[cfg(feature = "discovery")]
+use std::io::IsTerminal;
+#[cfg(feature = "discovery")]
 use std::time::Duration;
 
-pub async fn execute(command: DeviceCommands, mut config: Config) -> Result<()> {
-    match command {
+pub async fn execute(command: DeviceCommands, mut config: Config) -> Result<CommandOutput> {
+    let output = match command {
         #[cfg(feature = "discovery")]
-        DeviceCommands::Discover { timeout } => {
-            println!("Discovering AWTRIX3 devices on network...");
-            println!("Timeout: {} seconds", timeout);
+        DeviceCommands::Discover {
+            timeout,
+            scan,
+            concurrency,
+            expect,
+            add,
+        } => {
+            // Check before the slow part, since the prompts need a terminal
+            if add && !std::io::stdin().is_terminal() {
+                return Err(AwtrixError::Validation(
+                    "--add needs an interactive terminal".to_string(),
+                )
+                .into());
+            }
+
+            if crate::output::is_human() {
+                println!("Discovering AWTRIX3 devices on network...");
+                println!("Timeout: {} seconds", timeout);
+                for subnet in &scan {
+                    println!("Scanning subnet: {}", subnet);
+                }
+            }
 
             let devices =
-                crate::utils::discovery::discover_devices(Duration::from_secs(timeout)).await?;
+                discover(Duration::from_secs(timeout), &scan, concurrency, expect).await?;
+
+            let mut lines = Vec::new();
+            let mut found = Vec::new();
 
             if devices.is_empty() {
-                println!("No AWTRIX3 devices found on the network.");
-                println!("Make sure devices are powered on and connected to the same network.");
+                lines.push("No AWTRIX3 devices found on the network.".to_string());
+                lines.push(
+                    "Make sure devices are powered on and connected to the same network."
+                        .to_string(),
+                );
             } else {
-                println!("Found {} device(s):", devices.len());
-                for device in devices {
-                    println!("  📱 {} ({}:{})", device.name, device.host, device.port);
+                lines.push(format!("Found {} device(s):", devices.len()));
+                for device in &devices {
+                    lines.push(format!(
+                        "  📱 {} ({}:{})",
+                        device.name, device.host, device.port
+                    ));
+                    if let Some(id) = &device.id {
+                        lines.push(format!("     ID: {}", id));
+                    }
 
                     // Test connectivity
-                    if let Ok(client) = awtrix3::Client::new(&device.host) {
-                        match client.get_version().await {
-                            Ok(version) => println!("     ✅ Online - Version: {}", version.trim()),
-                            Err(_) => println!("     ❌ Cannot connect to API"),
+                    let version = match awtrix3::Client::new(device.address()) {
+                        Ok(client) => match client.get_version().await {
+                            Ok(version) => {
+                                lines.push(format!("     ✅ Online - Version: {}", version.trim()));
+                                Some(version.trim().to_string())
+                            }
+                            Err(_) => {
+                                lines.push("     ❌ Cannot connect to API".to_string());
+                                None
+                            }
+                        },
+                        Err(_) => {
+                            lines.push("     ❌ Invalid host".to_string());
+                            None
                         }
-                    } else {
-                        println!("     ❌ Invalid host");
-                    }
+                    };
+
+                    found.push(json!({
+                        "name": device.name,
+                        "id": device.id,
+                        "host": device.host,
+                        "port": device.port,
+                        "txt": device.txt_records,
+                        "online": version.is_some(),
+                        "version": version,
+                    }));
                 }
-                println!("");
-                println!("To add a device, use: awtrix device add <name> <host>");
+                lines.push(String::new());
+                lines.push(
+                    "To add devices, use: awtrix device discover --add (or device add <name> <host>)"
+                        .to_string(),
+                );
             }
-        }
 
-        #[cfg(not(feature = "discovery"))]
-        DeviceCommands::Discover { .. } => {
-            println!("❌ Device discovery is not available.");
-            println!("This binary was compiled without discovery support.");
-            println!("To enable discovery, rebuild with: cargo build --features discovery");
+            if add && !devices.is_empty() {
+                if crate::output::is_human() {
+                    println!("{}", lines[..lines.len() - 2].join("\n"));
+                }
+                onboard(&devices, &mut config).await?
+            } else {
+                CommandOutput::data(&json!({ "devices": found }))?.text(lines.join("\n"))
+            }
         }
 
         DeviceCommands::Add {
@@ -49,176 +113,440 @@ pub async fn execute(command: DeviceCommands, mut config: Config) -> Result<()>
             default,
         } => {
             // Test connectivity first
-            println!("Testing connection to {}...", host);
+            if crate::output::is_human() {
+                println!("Testing connection to {}...", host);
+            }
 
-            match awtrix3::Client::new(&host) {
-                Ok(client) => {
-                    match client.get_version().await {
-                        Ok(version) => {
-                            println!("✅ Connected successfully - Version: {}", version.trim());
+            let client =
+                awtrix3::Client::new(&host).with_context(|| format!("Invalid host '{}'", host))?;
+            let version = client
+                .get_version()
+                .await
+                .with_context(|| format!("Cannot connect to AWTRIX3 API at {}", host))?;
 
-                            // Add device to config
-                            let device_config = DeviceConfig {
-                                host: host.clone(),
-                                name: format!("AWTRIX3 {}", name),
-                                timeout: 30,
-                                retries: 3,
-                            };
+            let mut lines = vec![format!(
+                "✅ Connected successfully - Version: {}",
+                version.trim()
+            )];
 
-                            config.devices.insert(name.clone(), device_config);
+            // Remember the device ID so it can be found again if its address changes
+            let uid = client.get_stats().await.ok().and_then(|stats| stats.uid);
 
-                            if default {
-                                config.default_device = Some(name.clone());
-                                println!("Set '{}' as default device", name);
-                            }
+            // Add device to config
+            let device_config = DeviceConfig {
+                host: client.address().clone(),
+                uid,
+                name: format!("AWTRIX3 {}", name),
+                timeout: 30,
+                retries: 3,
+            };
 
-                            // Save config
-                            config::save_config(&config)?;
-                            println!("Device '{}' added successfully", name);
-                        }
-                        Err(e) => {
-                            return Err(anyhow::anyhow!(
-                                "Cannot connect to AWTRIX3 API at {}: {}",
-                                host,
-                                e
-                            ));
-                        }
-                    }
-                }
-                Err(e) => {
-                    return Err(anyhow::anyhow!("Invalid host '{}': {}", host, e));
-                }
+            config.devices.insert(name.clone(), device_config);
+
+            if default {
+                config.default_device = Some(name.clone());
+                lines.push(format!("Set '{}' as default device", name));
             }
+
+            // Save config
+            config::save_config(&config)?;
+            lines.push(format!("Device '{}' added successfully", name));
+
+            CommandOutput::message(lines.join("\n"))
+                .field("device", &name)
+                .field("host", client.address().to_string())
+                .field("version", version.trim())
+                .field("default", config.default_device.as_ref() == Some(&name))
         }
 
         DeviceCommands::Remove { name } => {
-            if config.devices.contains_key(&name) {
-                config.devices.remove(&name);
-
-                // Handle default device removal
-                if config.default_device.as_ref() == Some(&name) {
-                    if config.devices.is_empty() {
-                        config.default_device = None;
-                        println!("No devices remaining - cleared default device");
-                    } else {
-                        // Set first remaining device as default
-                        let new_default = config.devices.keys().next().unwrap().clone();
-                        config.default_device = Some(new_default.clone());
-                        println!("Set '{}' as new default device", new_default);
-                    }
-                }
-
-                // Save config
-                config::save_config(&config)?;
-                println!("Device '{}' removed successfully", name);
-            } else {
-                return Err(anyhow::anyhow!(
+            if config.devices.remove(&name).is_none() {
+                return Err(AwtrixError::Config(format!(
                     "Device '{}' not found in configuration",
                     name
-                ));
+                ))
+                .into());
+            }
+
+            let mut lines = Vec::new();
+
+            // Handle default device removal
+            if config.default_device.as_ref() == Some(&name) {
+                if config.devices.is_empty() {
+                    config.default_device = None;
+                    lines.push("No devices remaining - cleared default device".to_string());
+                } else {
+                    // Set first remaining device as default
+                    let new_default = config.devices.keys().next().unwrap().clone();
+                    config.default_device = Some(new_default.clone());
+                    lines.push(format!("Set '{}' as new default device", new_default));
+                }
             }
+
+            // Save config
+            config::save_config(&config)?;
+            lines.push(format!("Device '{}' removed successfully", name));
+
+            CommandOutput::message(lines.join("\n"))
+                .field("device", name)
+                .field("default", &config.default_device)
         }
 
         DeviceCommands::List => {
             if config.devices.is_empty() {
-                println!("No devices configured.");
-                println!("Use 'awtrix device discover' to find devices or 'awtrix device add' to add manually.");
-                return Ok(());
+                return Ok(CommandOutput::data(&json!({ "devices": [] }))?.text(
+                    "No devices configured.\n\
+                     Use 'awtrix device discover' to find devices or 'awtrix device add' to add manually.",
+                ));
             }
 
-            println!("Configured AWTRIX3 Devices:");
-            println!("");
+            let mut lines = vec!["Configured AWTRIX3 Devices:".to_string(), String::new()];
+            let mut devices = Vec::new();
 
             for (name, device) in &config.devices {
                 let is_default = config.default_device.as_ref() == Some(name);
                 let default_marker = if is_default { " (default)" } else { "" };
 
-                println!("📱 {} - {}{}", name, device.host, default_marker);
-                println!("   Name: {}", device.name);
+                lines.push(format!("📱 {} - {}{}", name, device.host, default_marker));
+                lines.push(format!("   Name: {}", device.name));
 
                 // Test connectivity
-                print!("   Status: ");
-                match awtrix3::Client::new(&device.host) {
+                let version = match awtrix3::Client::new(&device.host) {
                     Ok(client) => match client.get_version().await {
-                        Ok(version) => println!("✅ Online - Version: {}", version.trim()),
-                        Err(_) => println!("❌ Offline or unreachable"),
+                        Ok(version) => {
+                            lines.push(format!(
+                                "   Status: ✅ Online - Version: {}",
+                                version.trim()
+                            ));
+                            Some(version.trim().to_string())
+                        }
+                        Err(_) => {
+                            lines.push("   Status: ❌ Offline or unreachable".to_string());
+                            None
+                        }
                     },
-                    Err(_) => println!("❌ Invalid configuration"),
-                }
+                    Err(_) => {
+                        lines.push("   Status: ❌ Invalid configuration".to_string());
+                        None
+                    }
+                };
 
-                println!("");
+                lines.push(String::new());
+                devices.push(json!({
+                    "name": name,
+                    "host": device.host.to_string(),
+                    "description": device.name,
+                    "default": is_default,
+                    "online": version.is_some(),
+                    "version": version,
+                }));
             }
+
+            CommandOutput::data(&json!({ "devices": devices }))?.text(lines.join("\n"))
         }
 
         DeviceCommands::Test { device } => {
             let device_config = if let Some(device_name) = device {
                 // Test specific device
                 config.devices.get(&device_name).ok_or_else(|| {
-                    anyhow::anyhow!("Device '{}' not found in configuration", device_name)
+                    AwtrixError::Config(format!(
+                        "Device '{}' not found in configuration",
+                        device_name
+                    ))
                 })?
             } else {
                 // Test default device
-                let default_name = config
-                    .default_device
-                    .as_ref()
-                    .ok_or_else(|| anyhow::anyhow!("No default device configured"))?;
+                let default_name = config.default_device.as_ref().ok_or_else(|| {
+                    AwtrixError::Config("No default device configured".to_string())
+                })?;
 
                 config.devices.get(default_name).ok_or_else(|| {
-                    anyhow::anyhow!(
+                    AwtrixError::Config(format!(
                         "Default device '{}' not found in configuration",
                         default_name
-                    )
+                    ))
                 })?
             };
 
-            println!("Testing device: {}", device_config.name);
-            println!("Host: {}", device_config.host);
-            println!("");
+            let (lines, result) = test_device(
+                &device_config.name,
+                &device_config.host.to_unredacted_string(),
+            )
+            .await;
+            CommandOutput::data(&result)?.text(lines.join("\n"))
+        }
 
-            let start = std::time::Instant::now();
+        DeviceCommands::Watch {
+            devices,
+            interval,
+            low_heap,
+            once,
+        } => crate::commands::watch::execute(devices, interval, low_heap, once, &config).await?,
+    };
 
-            match awtrix3::Client::new(&device_config.host) {
-                Ok(client) => {
-                    println!("✅ Client created successfully");
+    Ok(output)
+}
 
-                    // Test API endpoints
-                    match client.get_version().await {
-                        Ok(version) => {
-                            let response_time = start.elapsed();
-                            println!(
-                                "✅ Version API: {} ({}ms)",
-                                version.trim(),
-                                response_time.as_millis()
-                            );
-                        }
-                        Err(e) => {
-                            println!("❌ Version API failed: {}", e);
-                            return Ok(());
-                        }
-                    }
+/// Let the user pick discovered devices, name and test them, then save them all
+#[cfg(feature = "discovery")]
+async fn onboard(devices: &[DiscoveredDevice], config: &mut Config) -> Result<CommandOutput> {
+    let configured = |device: &DiscoveredDevice| {
+        config
+            .devices
+            .iter()
+            .find(|(_, known)| {
+                known.host.to_string() == device.address() || known.host.host() == device.host
+            })
+            .map(|(name, _)| name.clone())
+    };
 
-                    // Test stats
-                    let stats_start = std::time::Instant::now();
-                    match client.get_stats().await {
-                        Ok(_) => {
-                            let response_time = stats_start.elapsed();
-                            println!("✅ Stats API: OK ({}ms)", response_time.as_millis());
-                        }
-                        Err(e) => {
-                            println!("⚠️  Stats API: {}", e);
-                        }
-                    }
+    let items: Vec<String> = devices
+        .iter()
+        .map(|device| match configured(device) {
+            Some(name) => format!(
+                "{} ({}) - already configured as '{}'",
+                device.name,
+                device.address(),
+                name
+            ),
+            None => format!("{} ({})", device.name, device.address()),
+        })
+        .collect();
+    let defaults: Vec<bool> = devices
+        .iter()
+        .map(|device| configured(device).is_none())
+        .collect();
+
+    let selected = MultiSelect::new()
+        .with_prompt("Select devices to add (space to toggle, enter to confirm)")
+        .items(&items)
+        .defaults(&defaults)
+        .interact()?;
+
+    if selected.is_empty() {
+        return Ok(
+            CommandOutput::message("No devices selected").field("added", Vec::<String>::new())
+        );
+    }
+
+    let mut added: Vec<String> = Vec::new();
+    let mut failed = Vec::new();
+
+    for index in selected {
+        let device = &devices[index];
+
+        // Suggest a name that is not taken yet
+        let base = device.suggested_name();
+        let mut suggestion = base.clone();
+        let mut n = 2;
+        while config.devices.contains_key(&suggestion) {
+            suggestion = format!("{}_{}", base, n);
+            n += 1;
+        }
 
-                    println!("");
-                    println!("✅ Device test completed successfully");
-                    println!("Total response time: {}ms", start.elapsed().as_millis());
+        let name: String = Input::new()
+            .with_prompt(format!("Name for {} ({})", device.name, device.address()))
+            .default(suggestion)
+            .validate_with(|input: &String| {
+                if input.trim().is_empty() {
+                    Err("Name cannot be empty")
+                } else if config.devices.contains_key(input.trim()) {
+                    Err("A device with this name already exists")
+                } else {
+                    Ok(())
+                }
+            })
+            .interact_text()?;
+        let name = name.trim().to_string();
+
+        let (lines, result) = test_device(&device.name, &device.address()).await;
+        if crate::output::is_human() {
+            println!("{}\n", lines.join("\n"));
+        }
+
+        if result["ok"] != json!(true) {
+            failed.push(name);
+            continue;
+        }
+
+        config.devices.insert(
+            name.clone(),
+            DeviceConfig {
+                host: device.address().parse()?,
+                uid: device.id.clone(),
+                name: format!("AWTRIX3 {}", name),
+                timeout: 30,
+                retries: 3,
+            },
+        );
+        added.push(name);
+    }
+
+    if added.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No devices added; connectivity test failed for: {}",
+            failed.join(", ")
+        ));
+    }
+
+    // Offer to change the default device
+    let keep = match &config.default_device {
+        Some(current) => format!("Keep current default ({})", current),
+        None => "No default device".to_string(),
+    };
+    let mut choices = vec![keep];
+    choices.extend(
+        added
+            .iter()
+            .map(|name| format!("Make '{}' the default", name)),
+    );
+    let choice = Select::new()
+        .with_prompt("Default device")
+        .items(&choices)
+        .default(if config.default_device.is_none() {
+            1
+        } else {
+            0
+        })
+        .interact()?;
+    if choice > 0 {
+        config.default_device = Some(added[choice - 1].clone());
+    }
+
+    config::save_config(config)?;
+
+    let mut lines = vec![format!(
+        "Added {} device(s): {}",
+        added.len(),
+        added.join(", ")
+    )];
+    if !failed.is_empty() {
+        lines.push(format!(
+            "Skipped {} device(s) that failed the connectivity test: {}",
+            failed.len(),
+            failed.join(", ")
+        ));
+    }
+    if let Some(default) = &config.default_device {
+        lines.push(format!("Default device: {}", default));
+    }
+
+    Ok(CommandOutput::message(lines.join("\n"))
+        .field("added", &added)
+        .field("failed", &failed)
+        .field("default", &config.default_device))
+}
+
+/// Check the version and stats endpoints, returning report lines and a JSON result
+async fn test_device(description: &str, host: &str) -> (Vec<String>, Value) {
+    let shown = awtrix3::client::address::redact(host);
+    let mut lines = vec![
+        format!("Testing device: {}", description),
+        format!("Host: {}", shown),
+        String::new(),
+    ];
+    let mut result = json!({
+        "device": description,
+        "host": shown,
+        "ok": false,
+    });
+
+    let start = std::time::Instant::now();
+
+    match awtrix3::Client::new(host) {
+        Ok(client) => {
+            lines.push("✅ Client created successfully".to_string());
+
+            // Test API endpoints
+            match client.get_version().await {
+                Ok(version) => {
+                    let response_time = start.elapsed();
+                    lines.push(format!(
+                        "✅ Version API: {} ({}ms)",
+                        version.trim(),
+                        response_time.as_millis()
+                    ));
+                    result["version"] = json!(version.trim());
+                    result["version_ms"] = json!(response_time.as_millis() as u64);
+                }
+                Err(e) => {
+                    lines.push(format!("❌ Version API failed: {}", e));
+                    result["error"] = json!(e.to_string());
+                    return (lines, result);
+                }
+            }
+
+            // Test stats
+            let stats_start = std::time::Instant::now();
+            match client.get_stats().await {
+                Ok(_) => {
+                    let response_time = stats_start.elapsed();
+                    lines.push(format!(
+                        "✅ Stats API: OK ({}ms)",
+                        response_time.as_millis()
+                    ));
+                    result["stats_ms"] = json!(response_time.as_millis() as u64);
                 }
                 Err(e) => {
-                    println!("❌ Failed to create client: {}", e);
+                    lines.push(format!("⚠️  Stats API: {}", e));
+                    result["stats_error"] = json!(e.to_string());
                 }
             }
+
+            lines.push(String::new());
+            lines.push("✅ Device test completed successfully".to_string());
+            lines.push(format!(
+                "Total response time: {}ms",
+                start.elapsed().as_millis()
+            ));
+            result["ok"] = json!(true);
+            result["total_ms"] = json!(start.elapsed().as_millis() as u64);
+        }
+        Err(e) => {
+            lines.push(format!("❌ Failed to create client: {}", e));
+            result["error"] = json!(e.to_string());
+        }
+    }
+
+    (lines, result)
+}
+
+/// Find devices with mDNS and by scanning subnets, merging the results
+#[cfg(feature = "discovery")]
+async fn discover(
+    timeout: Duration,
+    subnets: &[String],
+    concurrency: usize,
+    expected: Option<usize>,
+) -> Result<Vec<crate::utils::discovery::DiscoveredDevice>> {
+    use crate::utils::discovery;
+
+    // Probes answer well within the mDNS browse window
+    let probe_timeout = timeout.min(Duration::from_secs(2));
+
+    let scans = async {
+        let mut found = Vec::new();
+        for subnet in subnets {
+            found.extend(discovery::scan_subnet(subnet, probe_timeout, concurrency).await?);
         }
+        Ok::<_, anyhow::Error>(found)
+    };
+    let (mdns, scanned) = tokio::join!(discovery::discover_devices(timeout, expected), scans);
+
+    let mut devices = match mdns {
+        Ok(devices) => devices,
+        // Multicast is often blocked where scanning is needed, so keep going
+        Err(e) if !subnets.is_empty() => {
+            tracing::warn!("mDNS discovery failed: {}", e);
+            Vec::new()
+        }
+        Err(e) => return Err(e),
+    };
+
+    // A device found both ways is listed once, matched by its ID
+    for device in scanned? {
+        discovery::merge_device(&mut devices, device);
     }
 
-    Ok(())
+    Ok(devices)
 }
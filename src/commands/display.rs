@@ -1,9 +1,11 @@
 use crate::cli::*;
+use crate::output::CommandOutput;
 // use crate::utils::parse_color;
 use anyhow::Result;
+use awtrix3::AwtrixError;
 
-pub async fn execute(client: awtrix3::Client, command: DisplayCommands) -> Result<()> {
-    match command {
+pub async fn execute(client: awtrix3::Client, command: DisplayCommands) -> Result<CommandOutput> {
+    let output = match command {
         DisplayCommands::Mood {
             color,
             kelvin,
@@ -11,10 +13,11 @@ pub async fn execute(client: awtrix3::Client, command: DisplayCommands) -> Resul
         } => {
             // Validate kelvin range if provided
             if let Some(k) = kelvin {
-                if k < 2000 || k > 6500 {
-                    return Err(anyhow::anyhow!(
-                        "Color temperature must be between 2000K and 6500K"
-                    ));
+                if !(2000..=6500).contains(&k) {
+                    return Err(AwtrixError::Validation(
+                        "Color temperature must be between 2000K and 6500K".to_string(),
+                    )
+                    .into());
                 }
             }
 
@@ -33,33 +36,37 @@ pub async fn execute(client: awtrix3::Client, command: DisplayCommands) -> Resul
                 .await?;
 
             // Provide feedback
-            match (parsed_color, kelvin, brightness) {
-                (Some(c), None, Some(b)) => println!(
+            let message = match (parsed_color, kelvin, brightness) {
+                (Some(c), None, Some(b)) => format!(
                     "Mood light set to color {} with brightness {}",
                     c.to_hex(),
                     b
                 ),
                 (None, Some(k), Some(b)) => {
-                    println!("Mood light set to {}K with brightness {}", k, b)
+                    format!("Mood light set to {}K with brightness {}", k, b)
                 }
-                (Some(c), None, None) => println!("Mood light set to color {}", c.to_hex()),
-                (None, Some(k), None) => println!("Mood light set to {}K", k),
-                (None, None, Some(b)) => println!("Mood light brightness set to {}", b),
-                _ => println!("Mood light updated"),
-            }
+                (Some(c), None, None) => format!("Mood light set to color {}", c.to_hex()),
+                (None, Some(k), None) => format!("Mood light set to {}K", k),
+                (None, None, Some(b)) => format!("Mood light brightness set to {}", b),
+                _ => "Mood light updated".to_string(),
+            };
+            CommandOutput::message(message)
+                .field("color", parsed_color.map(|c| c.to_hex()))
+                .field("kelvin", kelvin)
+                .field("brightness", brightness)
         }
         DisplayCommands::Screen { fps, fullscreen } => {
             // Validate FPS range
             if fps > 60 {
-                return Err(anyhow::anyhow!("FPS must be 60 or less"));
+                return Err(AwtrixError::Validation("FPS must be 60 or less".to_string()).into());
             }
 
-            println!("Screen viewing not yet implemented");
-            println!(
-                "Would show screen at {} FPS{}",
+            let output = CommandOutput::message(format!(
+                "Screen viewing not yet implemented\nWould show screen at {} FPS{}",
                 fps,
                 if fullscreen { " (fullscreen)" } else { "" }
-            );
+            ))
+            .field("status", "unsupported");
 
             // TODO: Implement live screen viewing
             // This would require:
@@ -67,15 +74,18 @@ pub async fn execute(client: awtrix3::Client, command: DisplayCommands) -> Resul
             // 2. Terminal rendering of the matrix data
             // 3. FPS timing control
             // 4. Keyboard interrupt handling
+            output
         }
         DisplayCommands::Stream { interval } => {
-            println!("Screen streaming not yet implemented");
-            println!("Would stream screen updates every {}ms", interval);
-
             // TODO: Implement screen streaming
             // Similar to screen viewing but continuous
+            CommandOutput::message(format!(
+                "Screen streaming not yet implemented\nWould stream screen updates every {}ms",
+                interval
+            ))
+            .field("status", "unsupported")
         }
-    }
+    };
 
-    Ok(())
+    Ok(output)
 }
@@ -0,0 +1,218 @@
+use crate::cli::ExporterArgs;
+use crate::commands::watch::{self, Sample};
+use crate::config::Config;
+use crate::output::CommandOutput;
+use crate::server::{self, Response};
+use anyhow::{Context, Result};
+use awtrix3::utils::decimal;
+use awtrix3::AwtrixError;
+use std::fmt::Write;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+
+/// Gauges exported per device: name, help text and value from a stats sample
+type Gauge = (&'static str, &'static str, fn(&Sample) -> Option<f64>);
+
+const GAUGES: &[Gauge] = &[
+    (
+        "awtrix_up",
+        "Whether the last stats request succeeded",
+        |s| Some(if s.stats.is_ok() { 1.0 } else { 0.0 }),
+    ),
+    (
+        "awtrix_request_duration_seconds",
+        "Duration of the last stats request",
+        |s| Some(s.latency.as_secs_f64()),
+    ),
+    ("awtrix_wifi_signal_dbm", "WiFi signal strength", |s| {
+        stats(s)?.wifi_signal.map(f64::from)
+    }),
+    ("awtrix_ram_free_bytes", "Free heap memory", |s| {
+        stats(s)?.ram.map(f64::from)
+    }),
+    (
+        "awtrix_temperature_celsius",
+        "Temperature sensor reading",
+        |s| stats(s)?.temperature.map(decimal),
+    ),
+    ("awtrix_humidity_percent", "Humidity sensor reading", |s| {
+        stats(s)?.humidity.map(decimal)
+    }),
+    ("awtrix_lux", "Light sensor reading", |s| {
+        stats(s)?.lux.map(decimal)
+    }),
+    ("awtrix_battery_percent", "Battery level", |s| {
+        stats(s)?.battery.map(f64::from)
+    }),
+    (
+        "awtrix_uptime_seconds",
+        "Time since the device started",
+        |s| stats(s)?.uptime.map(|uptime| uptime as f64),
+    ),
+    ("awtrix_brightness", "Current matrix brightness", |s| {
+        stats(s)?.brightness.map(f64::from)
+    }),
+    (
+        "awtrix_matrix_on",
+        "Whether the matrix is switched on",
+        |s| stats(s)?.matrix.map(|on| if on { 1.0 } else { 0.0 }),
+    ),
+];
+
+fn stats(sample: &Sample) -> Option<&awtrix3::models::response::Stats> {
+    sample.stats.as_ref().ok()
+}
+
+pub async fn execute(args: ExporterArgs, config: Config) -> Result<CommandOutput> {
+    if args.interval == 0 {
+        return Err(
+            AwtrixError::Validation("Interval must be greater than 0 seconds".to_string()).into(),
+        );
+    }
+
+    let address = server::parse_listen(&args.listen)?;
+    let devices = watch::select_devices(&config, Vec::new())?;
+    let listener = TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Cannot listen on {}", address))?;
+
+    if crate::output::is_human() {
+        println!(
+            "Exporting stats of {} device(s) on http://{}/metrics (interval: {}s)",
+            devices.len(),
+            address,
+            args.interval
+        );
+        println!("Press Ctrl+C to stop...");
+    }
+
+    // Devices are polled in the background, so scrapes never wait on them
+    let metrics = Arc::new(RwLock::new(String::new()));
+    let poller = tokio::spawn(poll_forever(
+        config,
+        devices,
+        Duration::from_secs(args.interval),
+        metrics.clone(),
+    ));
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    let metrics = metrics.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = respond(stream, &metrics).await {
+                            tracing::debug!("Metrics request failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to accept connection: {}", e),
+            },
+            _ = tokio::signal::ctrl_c() => {
+                poller.abort();
+                return Ok(CommandOutput::message("Exporter stopped"));
+            }
+        }
+    }
+}
+
+async fn poll_forever(
+    config: Config,
+    devices: Vec<String>,
+    interval: Duration,
+    metrics: Arc<RwLock<String>>,
+) {
+    let timeout = interval.min(Duration::from_secs(10));
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+        let samples = watch::poll(&config, &devices, timeout).await;
+        *metrics.write().unwrap_or_else(|e| e.into_inner()) = render(&samples);
+    }
+}
+
+/// Answer a single HTTP request with the current metrics
+async fn respond(mut stream: TcpStream, metrics: &RwLock<String>) -> std::io::Result<()> {
+    let request = server::read_request(&mut stream).await?;
+
+    let response = match request.path.as_str() {
+        "/metrics" => Response::new(
+            200,
+            "text/plain; version=0.0.4",
+            metrics.read().unwrap_or_else(|e| e.into_inner()).clone(),
+        ),
+        "/" => Response::new(200, "text/html", "<a href=\"/metrics\">Metrics</a>\n"),
+        _ => Response::not_found(),
+    };
+
+    server::write_response(&mut stream, response).await
+}
+
+/// Prometheus text format for a set of samples
+fn render(samples: &[Sample]) -> String {
+    let mut out = String::new();
+
+    for (name, help, value) in GAUGES {
+        let values: Vec<(&str, f64)> = samples
+            .iter()
+            .filter_map(|sample| Some((sample.device.as_str(), value(sample)?)))
+            .collect();
+        if values.is_empty() {
+            continue;
+        }
+
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        for (device, value) in values {
+            let _ = writeln!(out, "{}{{device=\"{}\"}} {}", name, escape(device), value);
+        }
+    }
+
+    out
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use awtrix3::models::response::Stats;
+
+    #[test]
+    fn test_render() {
+        let samples = vec![
+            Sample {
+                device: "lobby".to_string(),
+                stats: Ok(Stats {
+                    temperature: Some(21.7),
+                    matrix: Some(true),
+                    ..Stats::default()
+                }),
+                latency: Duration::from_millis(250),
+            },
+            Sample {
+                device: "say \"hi\"".to_string(),
+                stats: Err(AwtrixError::Validation("down".to_string())),
+                latency: Duration::from_secs(1),
+            },
+        ];
+
+        let text = render(&samples);
+        assert!(text.contains("# TYPE awtrix_up gauge\n"));
+        assert!(text.contains("awtrix_up{device=\"lobby\"} 1\n"));
+        assert!(text.contains("awtrix_up{device=\"say \\\"hi\\\"\"} 0\n"));
+        assert!(text.contains("awtrix_request_duration_seconds{device=\"lobby\"} 0.25\n"));
+        assert!(text.contains("awtrix_temperature_celsius{device=\"lobby\"} 21.7\n"));
+        assert!(text.contains("awtrix_matrix_on{device=\"lobby\"} 1\n"));
+        // Unreported sensors are left out rather than exported as zero
+        assert!(!text.contains("awtrix_humidity_percent"));
+    }
+}
@@ -0,0 +1,243 @@
+use crate::cli::{ApplyArgs, FleetArgs};
+use crate::commands::settings::format_value;
+use crate::config::fleet::{Action, DevicePlan};
+use crate::config::{Config, Fleet};
+use crate::output::CommandOutput;
+use anyhow::Result;
+use awtrix3::client::address::redact;
+use awtrix3::AwtrixError;
+use colored::Colorize;
+use dialoguer::Confirm;
+use serde_json::{json, Value};
+use std::io::IsTerminal;
+
+pub async fn plan(args: FleetArgs, config: Config) -> Result<CommandOutput> {
+    let plans = load_plans(&args, &config).await?;
+    let (text, _) = display_plans(&plans);
+    Ok(CommandOutput::data(&json!({ "plans": plans_data(&plans) }))?.text(text))
+}
+
+pub async fn apply(args: ApplyArgs, config: Config) -> Result<CommandOutput> {
+    let plans = load_plans(&args.fleet, &config).await?;
+    let (text, changes) = display_plans(&plans);
+
+    if plans.iter().all(|plan| plan.actions.is_empty()) {
+        return Ok(
+            CommandOutput::data(&json!({ "plans": plans_data(&plans), "applied": [] }))?.text(text),
+        );
+    }
+
+    if crate::output::is_human() {
+        println!("{}", text);
+    }
+
+    // Actions that are always applied are not worth a prompt on their own
+    if changes > 0 && !args.yes {
+        if !std::io::stdin().is_terminal() {
+            return Err(AwtrixError::Validation(
+                "Cannot ask for confirmation without a terminal; use --yes to apply non-interactively"
+                    .to_string(),
+            )
+            .into());
+        }
+        let confirmed = Confirm::new()
+            .with_prompt("Apply these changes?")
+            .default(false)
+            .interact()?;
+
+        if !confirmed {
+            return Ok(CommandOutput::message("Apply cancelled").field("cancelled", true));
+        }
+    }
+
+    let mut applied = Vec::new();
+    let mut failed = Vec::new();
+
+    for plan in plans.iter().filter(|plan| !plan.actions.is_empty()) {
+        match plan.apply(args.save).await {
+            Ok(()) => applied.push(plan.device.as_str()),
+            Err(e) => {
+                eprintln!("{} {}: {}", "Failed:".red().bold(), plan.device, e);
+                failed.push(plan.device.as_str());
+            }
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(CommandOutput::message(
+            applied
+                .iter()
+                .map(|device| format!("✅ {} reconciled", device))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+        .field("applied", &applied))
+    } else {
+        Err(anyhow::anyhow!(
+            "Apply failed for {} device(s): {}",
+            failed.len(),
+            failed.join(", ")
+        ))
+    }
+}
+
+async fn load_plans(args: &FleetArgs, config: &Config) -> Result<Vec<DevicePlan>> {
+    let fleet = Fleet::load(&args.file)?;
+    let plans = fleet.plan(config, &args.only).await?;
+
+    if plans.is_empty() {
+        return Err(anyhow::anyhow!("No devices in {} to plan", args.file));
+    }
+
+    Ok(plans)
+}
+
+/// Render the plan and return it with the total number of changes
+///
+/// Actions that are always applied are listed but not counted as changes.
+fn display_plans(plans: &[DevicePlan]) -> (String, usize) {
+    let mut lines = Vec::new();
+    let mut total = 0;
+
+    for plan in plans {
+        if plan.changes() == 0 {
+            lines.push(format!(
+                "📱 {} ({}): {}",
+                plan.device,
+                redact(&plan.host),
+                "up to date".green()
+            ));
+        } else {
+            lines.push(format!("📱 {} ({}):", plan.device, redact(&plan.host)));
+        }
+
+        for action in &plan.actions {
+            display_action(action, &mut lines);
+        }
+        total += plan.changes();
+    }
+
+    lines.push(String::new());
+    lines.push(format!(
+        "Plan: {} change(s) across {} device(s)",
+        total,
+        plans.iter().filter(|plan| plan.changes() > 0).count()
+    ));
+
+    (lines.join("\n"), total)
+}
+
+fn display_action(action: &Action, lines: &mut Vec<String>) {
+    let start = lines.len();
+    let (changed, added) = if action.always_applied() {
+        ("=".dimmed(), "=".dimmed())
+    } else {
+        ("~".yellow().bold(), "+".green().bold())
+    };
+
+    match action {
+        Action::UpdateSettings { changes, .. } => {
+            lines.push(format!("  {} settings", changed));
+            for change in changes {
+                let current = change
+                    .current
+                    .as_ref()
+                    .map(format_value)
+                    .unwrap_or_else(|| "(unset)".to_string());
+                let desired = change
+                    .desired
+                    .as_ref()
+                    .map(format_value)
+                    .unwrap_or_default();
+                lines.push(format!(
+                    "      {}: {} → {}",
+                    change.key,
+                    current.red(),
+                    desired.green()
+                ));
+            }
+        }
+        Action::ReorderApps { current, desired } => lines.push(format!(
+            "  {} app loop: {} → {}",
+            changed,
+            current.join(", ").red(),
+            desired.join(", ").green()
+        )),
+        Action::PutCustomApp { name, exists, .. } => {
+            if *exists {
+                lines.push(format!("  {} custom app '{}'", changed, name));
+            } else {
+                lines.push(format!("  {} custom app '{}'", added, name));
+            }
+        }
+        Action::SetIndicator { indicator, color } => lines.push(format!(
+            "  {} indicator {} → {}",
+            changed,
+            indicator,
+            color
+                .map(|c| c.to_hex())
+                .unwrap_or_else(|| "off".to_string())
+        )),
+        Action::UploadFile { remote, local } => lines.push(format!(
+            "  {} upload {} from {}",
+            added,
+            remote,
+            local.display()
+        )),
+    }
+
+    if action.always_applied() {
+        if let Some(line) = lines.get_mut(start) {
+            line.push_str(&format!(" {}", "(always applied)".dimmed()));
+        }
+    }
+}
+
+/// Structured form of the plan for non-table output
+fn plans_data(plans: &[DevicePlan]) -> Vec<Value> {
+    plans
+        .iter()
+        .map(|plan| {
+            let actions: Vec<Value> = plan
+                .actions
+                .iter()
+                .map(|action| {
+                    let mut data = match action {
+                        Action::UpdateSettings { changes, .. } => json!({
+                            "action": "update_settings",
+                            "changes": changes,
+                        }),
+                        Action::ReorderApps { current, desired } => json!({
+                            "action": "reorder_apps",
+                            "current": current,
+                            "desired": desired,
+                        }),
+                        Action::PutCustomApp { name, exists, .. } => json!({
+                            "action": "put_custom_app",
+                            "name": name,
+                            "exists": exists,
+                        }),
+                        Action::SetIndicator { indicator, color } => json!({
+                            "action": "set_indicator",
+                            "indicator": indicator,
+                            "color": color.map(|c| c.to_hex()),
+                        }),
+                        Action::UploadFile { remote, local } => json!({
+                            "action": "upload_file",
+                            "remote": remote,
+                            "local": local,
+                        }),
+                    };
+                    data["always_applied"] = json!(action.always_applied());
+                    data
+                })
+                .collect();
+
+            json!({
+                "device": plan.device,
+                "host": redact(&plan.host),
+                "actions": actions,
+            })
+        })
+        .collect()
+}
@@ -1,20 +1,21 @@
 use crate::cli::*;
+use crate::output::CommandOutput;
 // use crate::utils::parse_color;
 use anyhow::Result;
+use awtrix3::AwtrixError;
 
-pub async fn execute(client: awtrix3::Client, args: IndicatorArgs) -> Result<()> {
+pub async fn execute(client: awtrix3::Client, args: IndicatorArgs) -> Result<CommandOutput> {
     // Parse which indicators to control
     let indicators = if args.indicator == "all" {
         vec![1, 2, 3]
     } else {
         // Parse single indicator number
-        let num: u8 = args
-            .indicator
-            .parse()
-            .map_err(|_| anyhow::anyhow!("Invalid indicator number. Use 1-3 or 'all'"))?;
+        let num: u8 = args.indicator.parse().map_err(|_| {
+            AwtrixError::Validation("Invalid indicator number. Use 1-3 or 'all'".to_string())
+        })?;
 
-        if num < 1 || num > 3 {
-            return Err(anyhow::anyhow!("Indicator number must be 1-3"));
+        if !(1..=3).contains(&num) {
+            return Err(AwtrixError::Validation("Indicator number must be 1-3".to_string()).into());
         }
 
         vec![num]
@@ -26,19 +27,28 @@ pub async fn execute(client: awtrix3::Client, args: IndicatorArgs) -> Result<()>
     } else if let Some(color_str) = args.color {
         Some(awtrix3::Color::from_hex(&color_str)?)
     } else {
-        return Err(anyhow::anyhow!("Must specify either --color or --off"));
+        return Err(
+            AwtrixError::Validation("Must specify either --color or --off".to_string()).into(),
+        );
     };
 
     // Apply to each indicator
-    for indicator in indicators {
-        client.set_indicator(indicator, color.clone()).await?;
+    let mut lines = Vec::new();
+    for &indicator in &indicators {
+        client.set_indicator(indicator, color).await?;
 
         if args.off {
-            println!("Indicator {} turned OFF", indicator);
+            lines.push(format!("Indicator {} turned OFF", indicator));
         } else if let Some(c) = &color {
-            println!("Indicator {} set to color {}", indicator, c.to_hex());
+            lines.push(format!(
+                "Indicator {} set to color {}",
+                indicator,
+                c.to_hex()
+            ));
         }
     }
 
-    Ok(())
+    Ok(CommandOutput::message(lines.join("\n"))
+        .field("indicators", indicators)
+        .field("color", color.map(|c| c.to_hex())))
 }
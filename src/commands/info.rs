@@ -1,37 +1,79 @@
 use crate::cli::*;
+use crate::output::CommandOutput;
 use anyhow::Result;
 
-pub async fn execute(client: awtrix3::Client, command: InfoCommands) -> Result<()> {
-    match command {
+pub async fn execute(client: awtrix3::Client, command: InfoCommands) -> Result<CommandOutput> {
+    let output = match command {
         InfoCommands::Version => {
             let version = client.get_version().await?;
-            println!("AWTRIX3 Firmware Version: {}", version);
+            CommandOutput::message(format!("AWTRIX3 Firmware Version: {}", version))
+                .field("version", version)
+        }
+        InfoCommands::Capabilities => {
+            let capabilities = client.capabilities().await?;
+            let mut lines = vec![format!(
+                "AWTRIX3 Firmware Version: {}",
+                capabilities.raw_version
+            )];
+            lines.push("Features:".to_string());
+
+            let mut features = Vec::new();
+            for feature in awtrix3::client::Feature::ALL {
+                let supported = capabilities.supports(feature);
+                let probed = capabilities.probed.contains_key(&feature);
+                lines.push(format!(
+                    "  {} {}{}",
+                    if supported { "✅" } else { "❌" },
+                    feature,
+                    if probed {
+                        String::new()
+                    } else {
+                        format!(" (firmware >= {})", feature.min_version())
+                    }
+                ));
+                features.push(serde_json::json!({
+                    "feature": feature.as_str(),
+                    "supported": supported,
+                    "probed": probed,
+                    "min_version": feature.min_version().to_string(),
+                }));
+            }
+
+            CommandOutput::data(&serde_json::json!({
+                "version": capabilities.raw_version,
+                "features": features,
+            }))?
+            .text(lines.join("\n"))
         }
         InfoCommands::Effects => {
             let effects = client.get_effects().await?;
-            println!("Available Effects:");
-            for (i, effect) in effects.iter().enumerate() {
-                println!("  {}. {}", i + 1, effect);
-            }
+            CommandOutput::data(&serde_json::json!({ "effects": effects }))?
+                .text(numbered_list("Available Effects:", &effects))
         }
         InfoCommands::Transitions => {
             let transitions = client.get_transitions().await?;
-            println!("Available Transitions:");
-            for (i, transition) in transitions.iter().enumerate() {
-                println!("  {}. {}", i + 1, transition);
-            }
+            CommandOutput::data(&serde_json::json!({ "transitions": transitions }))?
+                .text(numbered_list("Available Transitions:", &transitions))
         }
         InfoCommands::Screen { format } => match format {
             ScreenFormat::Text => {
-                println!("Screen information (text format not yet implemented)");
+                CommandOutput::message("Screen information (text format not yet implemented)")
             }
             ScreenFormat::Json => {
-                println!("Screen information (JSON format not yet implemented)");
+                CommandOutput::message("Screen information (JSON format not yet implemented)")
             }
             ScreenFormat::Raw => {
-                println!("Screen information (raw format not yet implemented)");
+                CommandOutput::message("Screen information (raw format not yet implemented)")
             }
         },
+    };
+    Ok(output)
+}
+
+fn numbered_list(title: &str, items: &[String]) -> String {
+    let mut lines = vec![title.to_string()];
+    for (i, item) in items.iter().enumerate() {
+        lines.push(format!("  {}. {}", i + 1, item));
     }
-    Ok(())
+    lines.join("\n")
 }
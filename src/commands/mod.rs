@@ -1,11 +1,18 @@
 pub mod apps;
 pub mod custom;
+pub mod daemon;
 pub mod device;
 pub mod display;
+pub mod exporter;
+pub mod fleet;
 pub mod indicators;
 pub mod info;
 pub mod notify;
 pub mod power;
+pub mod recording;
+pub mod relay;
+pub mod serve;
 pub mod settings;
 pub mod sound;
 pub mod system;
+pub mod watch;
@@ -1,79 +1,768 @@
-use crate::cli::NotifyArgs;
+use crate::cli::{HistoryCommands, NotifyArgs, QueueCommands};
+use crate::config::{
+    Config, HistoryEntry, NotificationLog, OfflineQueue, QueuedNotification, QuietDecision,
+};
+use crate::output::CommandOutput;
 // use crate::utils::parse_color;
 use anyhow::Result;
+use awtrix3::models::Priority;
+use awtrix3::utils::template;
 use awtrix3::Notification;
+use awtrix3::{AwtrixError, ErrorKind};
+use chrono::{Duration as TimeDelta, Local, NaiveDateTime, SecondsFormat};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
 use std::fs;
+use std::io::Read;
+use std::time::{Duration, Instant};
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Longest text shown in the history table
+const TEXT_WIDTH: usize = 40;
+
+/// How long a queued notification's device gets to answer during a flush
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub async fn execute(
+    client: awtrix3::Client,
+    device: &str,
+    args: NotifyArgs,
+    config: &Config,
+) -> Result<CommandOutput> {
+    let sender = Sender {
+        client: &client,
+        device,
+        source: "notify",
+        history_limit: config.preferences.history_limit,
+    };
 
-pub async fn execute(client: awtrix3::Client, args: NotifyArgs) -> Result<()> {
     if args.dismiss {
         // Dismiss current notification
         client.dismiss_notification().await?;
-        println!("Notification dismissed");
-    } else if let Some(file_path) = args.file {
+        return Ok(CommandOutput::message("Notification dismissed"));
+    }
+
+    let queue = args.queue || config.preferences.offline_queue;
+    let priority = args.priority;
+    let wait = args
+        .wait
+        .then(|| Duration::from_secs(args.wait_timeout.into()));
+    let (notification, message) = if let Some(file_path) = args.file.clone() {
         // Load notification from JSON file
         let content = fs::read_to_string(file_path)?;
         let notification: Notification = serde_json::from_str(&content)?;
-
-        client.notify(notification).await?;
-        println!("Notification sent from file");
+        (notification, "Notification sent from file".to_string())
+    } else if let Some(name) = args.template.clone() {
+        let data = template_data(&args)?;
+        let notification = template::render_notification(&config.template(&name)?, &data)?;
+        let notification = apply_args(notification, args)?;
+        let message = format!(
+            "Notification sent from template '{}': {}",
+            name,
+            notification.text.as_deref().unwrap_or("(no text)")
+        );
+        (notification, message)
     } else {
         // Build notification from CLI arguments
-        let notification = build_notification_from_args(args)?;
-        let text_preview = notification.text.clone();
-
-        client.notify(notification).await?;
-        println!(
+        let text = args.text.clone().unwrap_or_default();
+        let notification = apply_args(Notification::builder().text(text).build(), args)?;
+        let message = format!(
             "Notification sent: {}",
-            text_preview.as_deref().unwrap_or("(no text)")
+            notification.text.as_deref().unwrap_or("(no text)")
         );
+        (notification, message)
+    };
+
+    let requested = serde_json::to_value(&notification)?;
+    let notification = match quiet_gate(config, device, notification, priority)? {
+        Gate::Send(notification) => notification,
+        Gate::Blocked(until) => {
+            return Ok(CommandOutput::message(format!(
+                "Quiet hours on {} until {}; notification not sent",
+                device,
+                until.format("%H:%M")
+            ))
+            .field("blocked", true))
+        }
+        Gate::Deferred(queued) => {
+            return Ok(CommandOutput::message(format!(
+                "Quiet hours on {}; notification deferred as #{} until {}",
+                device,
+                queued.id,
+                queued
+                    .not_before
+                    .unwrap_or(queued.queued_at)
+                    .format("%H:%M")
+            ))
+            .field("queued", queued.id))
+        }
+    };
+
+    let message = if serde_json::to_value(&notification)? != requested {
+        format!("{} (silenced for quiet hours)", message)
+    } else {
+        message
+    };
+
+    // The app shown before sending, to tell when the notification takes over
+    let before = match wait {
+        Some(_) => client.get_stats().await.map(|stats| stats.current_app),
+        None => Ok(None),
+    };
+
+    match sender.send(&notification, priority).await {
+        Ok(()) => {
+            let output = CommandOutput::message(message).field("notification", notification);
+            match wait {
+                Some(timeout) => {
+                    let started = Instant::now();
+                    let shown = client.wait_for_notification(before?, timeout).await?;
+                    let output = output
+                        .field("waited", started.elapsed().as_secs())
+                        .field("shown", shown);
+                    if !shown {
+                        tracing::warn!(
+                            "The notification was not seen on {}; it may have finished before it could be checked",
+                            device
+                        );
+                    }
+                    Ok(output)
+                }
+                None => Ok(output),
+            }
+        }
+        // Keep it for when the device is back
+        Err(e) if queue && e.kind() == ErrorKind::Unreachable => {
+            let expiry = TimeDelta::seconds(config.preferences.queue_expiry as i64);
+            let queued = OfflineQueue::open()?.push(device, &notification, priority, expiry)?;
+            Ok(CommandOutput::message(format!(
+                "{} is unreachable; notification queued as #{} until {}",
+                device,
+                queued.id,
+                queued.expires_at.format("%Y-%m-%d %H:%M")
+            ))
+            .field("queued", queued.id)
+            .field("notification", notification))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// A notification after the device's quiet hours were applied
+pub enum Gate {
+    /// Send it now, possibly silenced
+    Send(Notification),
+    /// Dropped; quiet hours last until then
+    Blocked(NaiveDateTime),
+    /// Put in the offline queue until quiet hours end
+    Deferred(QueuedNotification),
+}
+
+/// Apply a device's quiet hours, deferring the notification if they say so
+pub fn quiet_gate(
+    config: &Config,
+    device: &str,
+    notification: Notification,
+    priority: Priority,
+) -> Result<Gate> {
+    let now = Local::now().naive_local();
+    Ok(
+        match config.quiet_decision(device, notification.clone(), priority, now)? {
+            QuietDecision::Send(notification) => Gate::Send(notification),
+            QuietDecision::Block { until } => Gate::Blocked(until),
+            QuietDecision::Defer { until } => {
+                let until = until
+                    .and_local_timezone(Local)
+                    .earliest()
+                    .unwrap_or_else(Local::now)
+                    .fixed_offset();
+                let expiry = TimeDelta::seconds(config.preferences.queue_expiry as i64);
+                Gate::Deferred(OfflineQueue::open()?.defer(
+                    device,
+                    &notification,
+                    priority,
+                    until,
+                    expiry,
+                )?)
+            }
+        },
+    )
+}
+
+/// Sends notifications and records them in the history
+pub struct Sender<'a> {
+    pub client: &'a awtrix3::Client,
+    pub device: &'a str,
+    pub source: &'a str,
+    pub history_limit: usize,
+}
+
+impl Sender<'_> {
+    pub async fn send(
+        &self,
+        notification: &Notification,
+        priority: Priority,
+    ) -> awtrix3::Result<()> {
+        let result = self.client.notify(notification.clone()).await;
+
+        let error = result.as_ref().err().map(ToString::to_string);
+        let recorded = NotificationLog::open(self.history_limit)
+            .and_then(|log| log.record(self.device, self.source, notification, priority, error));
+        if let Err(e) = recorded {
+            tracing::warn!("Could not record notification history: {:#}", e);
+        }
+
+        result
+    }
+}
+
+/// Keep notifications for an unreachable device until it is back
+pub fn enqueue(config: &Config, device: &str, notifications: &[(Notification, Priority)]) {
+    let expiry = TimeDelta::seconds(config.preferences.queue_expiry as i64);
+    let queued = OfflineQueue::open().and_then(|queue| {
+        notifications
+            .iter()
+            .try_for_each(|(notification, priority)| {
+                queue
+                    .push(device, notification, *priority, expiry)
+                    .map(drop)
+            })
+    });
+    match queued {
+        Ok(()) => tracing::info!(
+            "Queued {} notification(s) for {}",
+            notifications.len(),
+            device
+        ),
+        Err(e) => tracing::warn!("Could not queue notifications for {}: {:#}", device, e),
+    }
+}
+
+/// `history list` and `history resend`
+pub async fn history(command: HistoryCommands, config: &Config) -> Result<CommandOutput> {
+    let log = NotificationLog::open(config.preferences.history_limit)?;
+
+    match command {
+        HistoryCommands::List {
+            device,
+            since,
+            search,
+            source,
+            failed,
+            limit,
+        } => {
+            let since = match since {
+                Some(window) => Some(crate::commands::recording::window_start(&window)?),
+                None => None,
+            };
+            let search = search.map(|search| search.to_lowercase());
+
+            let entries: Vec<HistoryEntry> = log
+                .entries()?
+                .into_iter()
+                .rev()
+                .filter(|entry| {
+                    device
+                        .as_ref()
+                        .map_or(true, |device| &entry.device == device)
+                })
+                .filter(|entry| {
+                    source
+                        .as_ref()
+                        .map_or(true, |source| &entry.source == source)
+                })
+                .filter(|entry| since.map_or(true, |since| entry.timestamp >= since))
+                .filter(|entry| !failed || entry.error.is_some())
+                .filter(|entry| {
+                    search.as_ref().map_or(true, |search| {
+                        entry
+                            .notification
+                            .text
+                            .as_deref()
+                            .is_some_and(|text| text.to_lowercase().contains(search))
+                    })
+                })
+                .take(limit)
+                .collect();
+
+            let text = if entries.is_empty() {
+                "No notifications recorded".to_string()
+            } else {
+                history_table(&entries)
+            };
+            Ok(CommandOutput::data(&json!({ "entries": entries }))?.text(text))
+        }
+
+        HistoryCommands::Resend { id, to } => {
+            let entry = log.get(id)?;
+            let device = to.unwrap_or(entry.device);
+            let client = awtrix3::Client::new(config.resolve_host(&device))?;
+
+            let notification =
+                match quiet_gate(config, &device, entry.notification, entry.priority)? {
+                    Gate::Send(notification) => notification,
+                    Gate::Blocked(until) => {
+                        return Ok(CommandOutput::message(format!(
+                            "Quiet hours on {} until {}; notification not resent",
+                            device,
+                            until.format("%H:%M")
+                        )))
+                    }
+                    Gate::Deferred(queued) => {
+                        return Ok(CommandOutput::message(format!(
+                            "Quiet hours on {}; resend deferred as #{}",
+                            device, queued.id
+                        )))
+                    }
+                };
+
+            Sender {
+                client: &client,
+                device: &device,
+                source: "resend",
+                history_limit: config.preferences.history_limit,
+            }
+            .send(&notification, entry.priority)
+            .await?;
+
+            Ok(CommandOutput::message(format!(
+                "Resent #{} to {}: {}",
+                id,
+                device,
+                notification.text.as_deref().unwrap_or("(no text)")
+            ))
+            .field("device", &device)
+            .field("notification", notification))
+        }
+    }
+}
+
+/// `queue flush`
+pub async fn queue(command: QueueCommands, config: &Config) -> Result<CommandOutput> {
+    match command {
+        QueueCommands::Flush { device } => {
+            let report = flush_queue(config, &OfflineQueue::open()?, device.as_deref()).await?;
+
+            let mut lines: Vec<String> = report
+                .devices
+                .iter()
+                .map(|flush| {
+                    let mut line = format!("{}: delivered {}", flush.device, flush.sent);
+                    if flush.superseded > 0 {
+                        line.push_str(&format!(", {} superseded", flush.superseded));
+                    }
+                    if flush.waiting > 0 {
+                        line.push_str(&format!(", {} still queued", flush.waiting));
+                    }
+                    if let Some(error) = &flush.error {
+                        line.push_str(&format!(" ({})", error));
+                    }
+                    line
+                })
+                .collect();
+            if report.expired > 0 {
+                lines.push(format!(
+                    "Dropped {} expired notification(s)",
+                    report.expired
+                ));
+            }
+            if lines.is_empty() {
+                lines.push("No queued notifications".to_string());
+            }
+
+            Ok(CommandOutput::data(&report)?.text(lines.join("\n")))
+        }
     }
+}
+
+/// Result of flushing the offline queue
+#[derive(Debug, Serialize)]
+pub struct FlushReport {
+    pub devices: Vec<DeviceFlush>,
+    /// Notifications dropped because they expired
+    pub expired: usize,
+}
+
+/// Result of flushing one device's queued notifications
+#[derive(Debug, Serialize)]
+pub struct DeviceFlush {
+    pub device: String,
+    pub sent: usize,
+    /// Dropped because a later non-stacking notification replaces them
+    pub superseded: usize,
+    /// Still queued, because delivery failed or they are deferred
+    pub waiting: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Deliver queued notifications in order, for one device or all of them
+///
+/// A device's notifications stop at the first failure so the order is kept;
+/// deferred ones and those held back by quiet hours are skipped and stay queued.
+/// A notification with `stack: false` replaces whatever the device shows, so
+/// the ones queued before it are dropped once it has been delivered.
+pub async fn flush_queue(
+    config: &Config,
+    queue: &OfflineQueue,
+    device: Option<&str>,
+) -> Result<FlushReport> {
+    let mut by_device: Vec<(String, Vec<QueuedNotification>)> = Vec::new();
+    for entry in queue.pending()? {
+        if device.is_some_and(|device| device != entry.device) {
+            continue;
+        }
+        match by_device.iter_mut().find(|(name, _)| *name == entry.device) {
+            Some((_, entries)) => entries.push(entry),
+            None => by_device.push((entry.device.clone(), vec![entry])),
+        }
+    }
+
+    let now = Local::now().naive_local();
+    let mut delivered = Vec::new();
+    let mut devices = Vec::new();
+    for (name, all) in by_device {
+        let mut flush = DeviceFlush {
+            device: name.clone(),
+            sent: 0,
+            superseded: 0,
+            waiting: all.len(),
+            error: None,
+        };
+
+        // Deferred notifications wait without holding back the others
+        let entries: Vec<&QueuedNotification> = all.iter().filter(|entry| entry.is_due()).collect();
+        if entries.is_empty() {
+            if let Some(not_before) = all.iter().filter_map(|entry| entry.not_before).min() {
+                flush.error = Some(format!(
+                    "deferred until {}",
+                    not_before.with_timezone(&Local).format("%H:%M")
+                ));
+            }
+            devices.push(flush);
+            continue;
+        }
+
+        // A `stack: false` notification replaces the ones queued before it,
+        // except those that must not be lost
+        let start = entries
+            .iter()
+            .rposition(|entry| entry.notification.stack == Some(false))
+            .unwrap_or(0);
+        let (superseded, kept): (Vec<&QueuedNotification>, Vec<_>) = entries[..start]
+            .iter()
+            .partition(|entry| entry.can_be_superseded());
+        let to_send: Vec<&QueuedNotification> = kept
+            .into_iter()
+            .chain(entries[start..].iter().copied())
+            .collect();
+
+        let client = awtrix3::ClientBuilder::new()
+            .host(config.resolve_host(&name))
+            .timeout(FLUSH_TIMEOUT)
+            .build();
+        let client = match client {
+            Ok(client) => client,
+            Err(e) => {
+                flush.error = Some(e.to_string());
+                devices.push(flush);
+                continue;
+            }
+        };
+        let sender = Sender {
+            client: &client,
+            device: &name,
+            source: "queue",
+            history_limit: config.preferences.history_limit,
+        };
+
+        for entry in to_send {
+            // Entries held back by quiet hours stay queued; more important
+            // ones after them still go out
+            let notification = match config.quiet_decision(
+                &name,
+                entry.notification.clone(),
+                entry.priority,
+                now,
+            )? {
+                QuietDecision::Send(notification) => notification,
+                QuietDecision::Block { until } | QuietDecision::Defer { until } => {
+                    flush.error = Some(format!("quiet hours until {}", until.format("%H:%M")));
+                    continue;
+                }
+            };
+            if let Err(e) = sender.send(&notification, entry.priority).await {
+                flush.error = Some(e.to_string());
+                break;
+            }
+            if entry.id == entries[start].id {
+                delivered.extend(superseded.iter().map(|entry| entry.id));
+                flush.superseded = superseded.len();
+            }
+            delivered.push(entry.id);
+            flush.sent += 1;
+        }
+        flush.waiting = all.len() - flush.sent - flush.superseded;
+        devices.push(flush);
+    }
+
+    let expired = queue.remove(&delivered)?;
+    Ok(FlushReport { devices, expired })
+}
+
+fn history_table(entries: &[HistoryEntry]) -> String {
+    let mut builder = Builder::default();
+    builder.push_record(["ID", "Time", "Device", "Source", "Text", "Status"]);
+
+    for entry in entries {
+        let text = entry.notification.text.as_deref().unwrap_or_default();
+        let text = if text.chars().count() > TEXT_WIDTH {
+            format!("{}…", text.chars().take(TEXT_WIDTH - 1).collect::<String>())
+        } else {
+            text.to_string()
+        };
+
+        builder.push_record([
+            entry.id.to_string(),
+            entry
+                .timestamp
+                .with_timezone(&Local)
+                .to_rfc3339_opts(SecondsFormat::Secs, false),
+            entry.device.clone(),
+            entry.source.clone(),
+            text,
+            match &entry.error {
+                Some(error) => format!("failed: {}", error),
+                None => "sent".to_string(),
+            },
+        ]);
+    }
+
+    builder.build().with(Style::rounded()).to_string()
+}
 
-    Ok(())
+/// Variables a template is rendered with
+///
+/// Environment variables are under `env`; JSON from stdin and then `--var`
+/// values are layered on top, and the positional text is `text`.
+fn template_data(args: &NotifyArgs) -> Result<Value> {
+    let mut data = Map::new();
+    data.insert(
+        "env".to_string(),
+        Value::Object(
+            std::env::vars()
+                .map(|(key, value)| (key, Value::String(value)))
+                .collect(),
+        ),
+    );
+
+    if args.stdin {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        match serde_json::from_str(&input) {
+            Ok(Value::Object(vars)) => data.extend(vars),
+            Ok(_) => {
+                return Err(AwtrixError::Validation(
+                    "Template variables on stdin must be a JSON object".to_string(),
+                )
+                .into())
+            }
+            Err(e) => {
+                return Err(AwtrixError::Validation(format!("Invalid JSON on stdin: {}", e)).into())
+            }
+        }
+    }
+
+    for var in &args.vars {
+        let (key, value) = parse_var(var)?;
+        data.insert(key, value);
+    }
+
+    if let Some(text) = &args.text {
+        data.insert("text".to_string(), Value::String(text.clone()));
+    }
+    Ok(Value::Object(data))
 }
 
-fn build_notification_from_args(args: NotifyArgs) -> Result<Notification> {
-    let mut builder = Notification::builder();
+/// Parse `KEY=VALUE`; whole numbers and `true`/`false` keep their type so
+/// they can fill numeric and boolean fields
+fn parse_var(var: &str) -> Result<(String, Value)> {
+    let Some((key, value)) = var.split_once('=').filter(|(key, _)| !key.is_empty()) else {
+        return Err(AwtrixError::Validation(format!(
+            "Invalid variable '{}', expected KEY=VALUE",
+            var
+        ))
+        .into());
+    };
 
-    // Set text
-    builder = builder.text(args.text);
+    let typed = match value {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => match value.parse::<i64>() {
+            // Leave values like `007` as text
+            Ok(number) if number.to_string() == value => Value::from(number),
+            _ => Value::String(value.to_string()),
+        },
+    };
+    Ok((key.to_string(), typed))
+}
 
-    // Set icon if provided
+/// Apply the notification options given on the command line
+fn apply_args(mut notification: Notification, args: NotifyArgs) -> Result<Notification> {
     if let Some(icon) = args.icon {
-        builder = builder.icon(icon);
+        notification.icon = Some(icon);
     }
 
-    // Set color if provided
     if let Some(color_str) = args.color {
-        let color = awtrix3::Color::from_hex(&color_str)?;
-        builder = builder.color(color);
+        notification.color = Some(awtrix3::Color::from_hex(&color_str)?);
     }
 
-    // Set duration if provided
     if let Some(duration) = args.duration {
-        builder = builder.duration(duration);
+        notification.duration = Some(duration);
     }
 
-    // Set sound if provided
     if let Some(sound) = args.sound {
-        builder = builder.sound(sound);
+        notification.sound = Some(sound);
     }
 
-    // Set progress if provided (validate 0-100)
+    // Validate progress 0-100
     if let Some(progress) = args.progress {
         if progress > 100 {
-            return Err(anyhow::anyhow!("Progress must be between 0 and 100"));
+            return Err(
+                AwtrixError::Validation("Progress must be between 0 and 100".to_string()).into(),
+            );
         }
-        builder = builder.progress(progress);
+        notification.progress = Some(progress);
     }
 
     // Set flags
     if args.hold {
-        builder = builder.hold(true);
+        notification.hold = Some(true);
     }
 
     if args.wakeup {
-        builder = builder.wakeup(true);
+        notification.wakeup = Some(true);
+    }
+
+    if args.stack {
+        notification.stack = Some(true);
     }
 
-    Ok(builder.build())
+    if args.no_scroll {
+        notification.no_scroll = Some(true);
+    }
+
+    Ok(notification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_flush_queue() {
+        let device = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/notify"))
+            .and(body_json(json!({ "text": "replace", "stack": false })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&device)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/notify"))
+            .and(body_json(json!({ "text": "after" })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&device)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/notify"))
+            .and(body_json(json!({ "text": "urgent" })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&device)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/notify"))
+            .and(body_json(json!({ "text": "held", "hold": true })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&device)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let queue = OfflineQueue::new(dir.path().join("queue.jsonl"));
+        let hour = TimeDelta::hours(1);
+        let text = |text: &str| Notification::builder().text(text).build();
+        let replace = Notification {
+            stack: Some(false),
+            ..text("replace")
+        };
+        let held = Notification {
+            hold: Some(true),
+            ..text("held")
+        };
+        let normal = Priority::Normal;
+        let tomorrow = Local::now().fixed_offset() + TimeDelta::days(1);
+        // Deferred entries do not hold back the ones after them
+        queue
+            .defer(&device.uri(), &text("later"), normal, tomorrow, hour)
+            .unwrap();
+        queue
+            .push(&device.uri(), &text("before"), normal, hour)
+            .unwrap();
+        queue.push(&device.uri(), &held, normal, hour).unwrap();
+        queue
+            .push(&device.uri(), &text("urgent"), Priority::Critical, hour)
+            .unwrap();
+        queue
+            .push("127.0.0.1:9", &text("offline"), normal, hour)
+            .unwrap();
+        queue.push(&device.uri(), &replace, normal, hour).unwrap();
+        queue
+            .push(&device.uri(), &text("after"), normal, hour)
+            .unwrap();
+
+        let mut config = Config::default();
+        config.preferences.history_limit = 0;
+        let report = flush_queue(&config, &queue, None).await.unwrap();
+
+        assert_eq!(report.devices.len(), 2);
+        assert_eq!(report.devices[0].sent, 4);
+        assert_eq!(report.devices[0].superseded, 1);
+        assert_eq!(report.devices[0].waiting, 1);
+        assert_eq!(report.devices[1].waiting, 1);
+        assert!(report.devices[1].error.is_some());
+
+        let pending: Vec<_> = queue
+            .pending()
+            .unwrap()
+            .into_iter()
+            .filter_map(|entry| entry.notification.text)
+            .collect();
+        assert_eq!(pending, vec!["later", "offline"]);
+    }
+
+    #[test]
+    fn test_parse_var() {
+        assert_eq!(parse_var("icon=42").unwrap(), ("icon".into(), json!(42)));
+        assert_eq!(
+            parse_var("hold=true").unwrap(),
+            ("hold".into(), json!(true))
+        );
+        assert_eq!(
+            parse_var("version=1.10").unwrap(),
+            ("version".into(), json!("1.10"))
+        );
+        assert_eq!(parse_var("id=007").unwrap(), ("id".into(), json!("007")));
+        assert_eq!(parse_var("msg=a=b").unwrap(), ("msg".into(), json!("a=b")));
+        assert!(parse_var("novalue").is_err());
+        assert!(parse_var("=x").is_err());
+    }
 }
@@ -1,7 +1,9 @@
 use crate::cli::{PowerArgs, PowerState, SleepArgs};
+use crate::output::CommandOutput;
 use anyhow::Result;
+use awtrix3::AwtrixError;
 
-pub async fn execute(client: awtrix3::Client, args: PowerArgs) -> Result<()> {
+pub async fn execute(client: awtrix3::Client, args: PowerArgs) -> Result<CommandOutput> {
     let power_on = match args.state {
         PowerState::On => true,
         PowerState::Off => false,
@@ -11,27 +13,28 @@ pub async fn execute(client: awtrix3::Client, args: PowerArgs) -> Result<()> {
     client.set_power(power_on).await?;
 
     // Provide user feedback
-    println!(
+    Ok(CommandOutput::message(format!(
         "Power state set to: {}",
         if power_on { "ON" } else { "OFF" }
-    );
-
-    Ok(())
+    ))
+    .field("power", power_on))
 }
 
-pub async fn sleep(client: awtrix3::Client, args: SleepArgs) -> Result<()> {
+pub async fn sleep(client: awtrix3::Client, args: SleepArgs) -> Result<CommandOutput> {
     // Validate duration is positive
     if args.duration == 0 {
-        return Err(anyhow::anyhow!(
-            "Sleep duration must be greater than 0 seconds"
-        ));
+        return Err(AwtrixError::Validation(
+            "Sleep duration must be greater than 0 seconds".to_string(),
+        )
+        .into());
     }
 
     // Call the API
     client.set_sleep(args.duration).await?;
 
     // Provide user feedback
-    println!("Sleep mode set for {} seconds", args.duration);
-
-    Ok(())
+    Ok(
+        CommandOutput::message(format!("Sleep mode set for {} seconds", args.duration))
+            .field("sleep", args.duration),
+    )
 }
@@ -0,0 +1,460 @@
+use crate::config::{self, Config};
+use crate::output::CommandOutput;
+use anyhow::{Context, Result};
+use awtrix3::models::response::Stats;
+use awtrix3::utils::decimal;
+use awtrix3::AwtrixError;
+use chrono::{DateTime, Duration as TimeDelta, FixedOffset, Local, SecondsFormat};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const HEADER: &str = "timestamp,device,temperature,humidity,lux,rssi,heap,battery,uptime";
+
+/// Sensors summarized by `--history`, with their units
+const METRICS: &[(&str, &str)] = &[
+    ("temperature", "°C"),
+    ("humidity", "%"),
+    ("lux", "lx"),
+    ("rssi", "dBm"),
+];
+
+const SPARK: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const SPARK_WIDTH: usize = 40;
+
+/// Default recording file in the config directory
+pub fn default_path() -> Result<PathBuf> {
+    Ok(config::get_config_dir()?.join("stats.csv"))
+}
+
+/// One recorded stats sample
+#[derive(Debug, Clone, PartialEq)]
+struct Record {
+    timestamp: DateTime<FixedOffset>,
+    device: String,
+    values: Vec<Option<f64>>,
+}
+
+impl Record {
+    fn new(timestamp: DateTime<FixedOffset>, device: String, stats: &Stats) -> Self {
+        let values = vec![
+            stats.temperature.map(decimal),
+            stats.humidity.map(decimal),
+            stats.lux.map(decimal),
+            stats.wifi_signal.map(f64::from),
+            stats.ram.map(f64::from),
+            stats.battery.map(f64::from),
+            stats.uptime.map(|uptime| uptime as f64),
+        ];
+        Self {
+            timestamp,
+            device,
+            values,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        let mut line = format!(
+            "{},{}",
+            self.timestamp.to_rfc3339_opts(SecondsFormat::Secs, false),
+            escape(&self.device)
+        );
+        for value in &self.values {
+            line.push(',');
+            if let Some(value) = value {
+                line.push_str(&value.to_string());
+            }
+        }
+        line
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = split(line).into_iter();
+        let timestamp = DateTime::parse_from_rfc3339(&fields.next()?).ok()?;
+        let device = fields.next()?;
+        let values = fields.map(|field| field.parse().ok()).collect();
+        Some(Self {
+            timestamp,
+            device,
+            values,
+        })
+    }
+
+    /// Value of a column after `device`, by header name
+    fn value(&self, column: &str) -> Option<f64> {
+        let index = HEADER.split(',').skip(2).position(|name| name == column)?;
+        self.values.get(index).copied().flatten()
+    }
+}
+
+/// Quote a CSV field if needed
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Split a CSV line, honoring quoted fields
+fn split(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// Poll stats every `interval` seconds and append them to `path` until Ctrl+C
+pub async fn record(
+    client: awtrix3::Client,
+    path: PathBuf,
+    interval: u64,
+) -> Result<CommandOutput> {
+    if interval == 0 {
+        return Err(
+            AwtrixError::Validation("Interval must be greater than 0 seconds".to_string()).into(),
+        );
+    }
+
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let is_new = fs::metadata(&path).map_or(true, |meta| meta.len() == 0);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Cannot open {}", path.display()))?;
+    if is_new {
+        writeln!(file, "{}", HEADER)?;
+    }
+
+    let device = client.address().to_string();
+    if crate::output::is_human() {
+        println!(
+            "Recording stats of {} to {} every {}s",
+            device,
+            path.display(),
+            interval
+        );
+        println!("Press Ctrl+C to stop...");
+    }
+
+    let mut recorded = 0;
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+
+        // A device that is briefly offline should not end the recording
+        match client.get_stats().await {
+            Ok(stats) => {
+                let record = Record::new(Local::now().fixed_offset(), device.clone(), &stats);
+                writeln!(file, "{}", record.to_line())?;
+                file.flush()?;
+                recorded += 1;
+                tracing::debug!("Recorded sample {}", recorded);
+            }
+            Err(e) => tracing::warn!("Failed to get stats from {}: {}", device, e),
+        }
+    }
+
+    Ok(CommandOutput::message(format!(
+        "Recorded {} sample(s) to {}",
+        recorded,
+        path.display()
+    ))
+    .field("samples", recorded)
+    .field("file", path.display().to_string()))
+}
+
+/// Summary of one sensor over the window
+#[derive(Debug, Serialize)]
+struct MetricSummary {
+    min: f64,
+    max: f64,
+    avg: f64,
+    sparkline: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DeviceHistory {
+    device: String,
+    samples: usize,
+    from: String,
+    to: String,
+    metrics: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Summarize samples recorded in `path` within the `since` window
+pub fn history(path: &Path, since: &str, config: &Config) -> Result<CommandOutput> {
+    let cutoff = window_start(since)?.fixed_offset();
+    let file = File::open(path).with_context(|| {
+        format!(
+            "Cannot open {}; record samples first with 'system stats --record'",
+            path.display()
+        )
+    })?;
+
+    let mut records: Vec<Record> = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.starts_with("timestamp,") || line.trim().is_empty() {
+            continue;
+        }
+        match Record::parse(&line) {
+            Some(record) if record.timestamp >= cutoff => records.push(record),
+            Some(_) => {}
+            None => tracing::debug!("Skipping malformed line: {}", line),
+        }
+    }
+    records.sort_by_key(|record| record.timestamp);
+
+    // Group by device, in order of first appearance
+    let mut devices: Vec<String> = Vec::new();
+    for record in &records {
+        if !devices.contains(&record.device) {
+            devices.push(record.device.clone());
+        }
+    }
+
+    let mut summaries = Vec::new();
+    let mut text = String::new();
+    for device in devices {
+        let samples: Vec<&Record> = records.iter().filter(|r| r.device == device).collect();
+        let label = device_label(&device, config);
+        let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+            continue;
+        };
+
+        let _ = writeln!(
+            text,
+            "{} - {} sample(s), {} to {}",
+            label,
+            samples.len(),
+            first.timestamp.format("%Y-%m-%d %H:%M"),
+            last.timestamp.format("%Y-%m-%d %H:%M"),
+        );
+
+        let mut metrics = serde_json::Map::new();
+        for (metric, unit) in METRICS {
+            let values: Vec<f64> = samples.iter().filter_map(|r| r.value(metric)).collect();
+            let Some(summary) = summarize(&values) else {
+                continue;
+            };
+            let _ = writeln!(
+                text,
+                "  {:<12} min {:>7.1}  max {:>7.1}  avg {:>7.1} {:<4} {}",
+                metric, summary.min, summary.max, summary.avg, unit, summary.sparkline
+            );
+            metrics.insert(metric.to_string(), serde_json::to_value(&summary)?);
+        }
+
+        summaries.push(DeviceHistory {
+            device: label,
+            samples: samples.len(),
+            from: first.timestamp.to_rfc3339(),
+            to: last.timestamp.to_rfc3339(),
+            metrics,
+        });
+    }
+
+    if summaries.is_empty() {
+        text = format!("No samples recorded in the last {}", since);
+    }
+
+    Ok(
+        CommandOutput::data(&serde_json::json!({ "devices": summaries }))?
+            .text(text.trim_end().to_string()),
+    )
+}
+
+/// Configured device name for a recorded host, or the host itself
+fn device_label(device: &str, config: &Config) -> String {
+    config
+        .devices
+        .iter()
+        .find(|(_, known)| known.host.to_string() == device)
+        .map(|(name, _)| name.clone())
+        .unwrap_or_else(|| device.to_string())
+}
+
+fn summarize(values: &[f64]) -> Option<MetricSummary> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let avg = values.iter().sum::<f64>() / values.len() as f64;
+
+    Some(MetricSummary {
+        min,
+        max,
+        avg: (avg * 100.0).round() / 100.0,
+        sparkline: sparkline(values, SPARK_WIDTH),
+    })
+}
+
+/// Sparkline of at most `width` characters, averaging values into buckets
+fn sparkline(values: &[f64], width: usize) -> String {
+    let buckets: Vec<f64> = if values.len() <= width {
+        values.to_vec()
+    } else {
+        (0..width)
+            .map(|i| {
+                let bucket = &values[i * values.len() / width..(i + 1) * values.len() / width];
+                bucket.iter().sum::<f64>() / bucket.len() as f64
+            })
+            .collect()
+    };
+
+    let min = buckets.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = buckets.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+
+    buckets
+        .iter()
+        .map(|value| {
+            if range == 0.0 {
+                SPARK[SPARK.len() / 2]
+            } else {
+                let level = ((value - min) / range * (SPARK.len() - 1) as f64).round();
+                SPARK[level as usize]
+            }
+        })
+        .collect()
+}
+
+/// Parse a time window like `90m`, `24h` or `7d`
+pub fn parse_window(window: &str) -> Result<TimeDelta> {
+    let invalid = || {
+        AwtrixError::Validation(format!(
+            "Invalid time window '{}', expected e.g. 30m, 24h or 7d",
+            window
+        ))
+    };
+
+    let split = window.char_indices().last().map_or(0, |(index, _)| index);
+    let (amount, unit) = window.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    if amount <= 0 {
+        return Err(invalid().into());
+    }
+
+    let delta = match unit {
+        "m" => TimeDelta::try_minutes(amount),
+        "h" => TimeDelta::try_hours(amount),
+        "d" => TimeDelta::try_days(amount),
+        _ => None,
+    };
+    Ok(delta.ok_or_else(invalid)?)
+}
+
+/// Start of a time window like `24h` that ends now
+pub fn window_start(window: &str) -> Result<DateTime<Local>> {
+    Local::now()
+        .checked_sub_signed(parse_window(window)?)
+        .ok_or_else(|| {
+            AwtrixError::Validation(format!("Time window '{}' is too long", window)).into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_round_trip() {
+        let stats = Stats {
+            temperature: Some(21.7),
+            wifi_signal: Some(-61),
+            uptime: Some(3600),
+            ..Stats::default()
+        };
+        let timestamp = DateTime::parse_from_rfc3339("2026-10-18T12:00:00+02:00").unwrap();
+        let record = Record::new(timestamp, "https://proxy/a,b".to_string(), &stats);
+
+        let line = record.to_line();
+        assert_eq!(
+            line,
+            "2026-10-18T12:00:00+02:00,\"https://proxy/a,b\",21.7,,,-61,,,3600"
+        );
+
+        let parsed = Record::parse(&line).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.value("temperature"), Some(21.7));
+        assert_eq!(parsed.value("rssi"), Some(-61.0));
+        assert_eq!(parsed.value("humidity"), None);
+    }
+
+    #[test]
+    fn test_history_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.csv");
+        let now = Local::now().fixed_offset();
+        let old = now - TimeDelta::days(3);
+        fs::write(
+            &path,
+            format!(
+                "{}\n{},lobby,30,,,,,,\n{},lobby,20,,,,,,\n{},lobby,22,,,,,,\n",
+                HEADER,
+                old.to_rfc3339(),
+                (now - TimeDelta::hours(1)).to_rfc3339(),
+                now.to_rfc3339()
+            ),
+        )
+        .unwrap();
+
+        let output = history(&path, "24h", &Config::default()).unwrap();
+        let json = output.render(crate::output::OutputFormat::Json).unwrap();
+        let data: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let lobby = &data["devices"][0];
+        assert_eq!(lobby["samples"], 2);
+        assert_eq!(lobby["metrics"]["temperature"]["min"], 20.0);
+        assert_eq!(lobby["metrics"]["temperature"]["max"], 22.0);
+        assert_eq!(lobby["metrics"]["temperature"]["avg"], 21.0);
+        assert!(lobby["metrics"].get("rssi").is_none());
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(
+            sparkline(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0], 40),
+            "▁▂▃▄▅▆▇█"
+        );
+        assert_eq!(sparkline(&[5.0, 5.0], 40), "▅▅");
+        // Long series are averaged down to the width
+        assert_eq!(sparkline(&[0.0, 0.0, 10.0, 10.0], 2), "▁█");
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("90m").unwrap(), TimeDelta::minutes(90));
+        assert_eq!(parse_window("7d").unwrap(), TimeDelta::days(7));
+        assert!(parse_window("soon").is_err());
+        assert!(parse_window("").is_err());
+        assert!(parse_window("-5h").is_err());
+        assert!(parse_window("0m").is_err());
+        assert!(parse_window("999999999999d").is_err());
+        assert!(window_start("100000000d").is_err());
+        assert!(window_start("24h").unwrap() < Local::now());
+    }
+}
@@ -0,0 +1,820 @@
+use crate::cli::RelayArgs;
+use crate::commands::notify::{quiet_gate, Gate};
+use crate::config::Config;
+use crate::output::CommandOutput;
+use crate::server::{self, Request, Response};
+use anyhow::{Context, Result};
+use awtrix3::models::Priority;
+use awtrix3::{AwtrixError, ErrorKind, Notification};
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+
+/// Window the per-sender rate limit is counted over
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Run the relay; `default_device` receives notifications that name no device
+pub async fn execute(
+    args: RelayArgs,
+    config: Config,
+    default_device: Option<String>,
+) -> Result<CommandOutput> {
+    if args.gap == 0 {
+        return Err(
+            AwtrixError::Validation("Gap must be greater than 0 seconds".to_string()).into(),
+        );
+    }
+
+    for quiet in &config.quiet_hours {
+        quiet.validate()?;
+    }
+
+    let address = server::parse_listen(&args.listen)?;
+    let listener = TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Cannot listen on {}", address))?;
+
+    let relay = Arc::new(Relay::new(
+        config,
+        default_device,
+        Duration::from_secs(args.dedupe_window),
+        args.rate_limit,
+        args.max_queue,
+        Duration::from_secs(args.gap),
+    ));
+
+    if let Some(path) = &args.socket {
+        #[cfg(unix)]
+        tokio::spawn(socket::serve(socket::bind(path)?, relay.clone()));
+        #[cfg(not(unix))]
+        return Err(AwtrixError::Validation(format!(
+            "Cannot listen on {}: Unix sockets are not supported on this platform",
+            path.display()
+        ))
+        .into());
+    }
+
+    if crate::output::is_human() {
+        println!("Relaying notifications from http://{}/notify", address);
+        if let Some(path) = &args.socket {
+            println!("  and from the Unix socket {}", path.display());
+        }
+        println!(
+            "  dedupe window {}s, rate limit {}/min per sender, {} queued per device, {}s per notification",
+            args.dedupe_window, args.rate_limit, args.max_queue, args.gap
+        );
+        println!("Press Ctrl+C to stop...");
+    }
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((mut stream, peer)) => {
+                    let relay = relay.clone();
+                    tokio::spawn(async move {
+                        let response = match server::read_request(&mut stream).await {
+                            Ok(request) => handle(&relay, request, &peer.ip().to_string()),
+                            Err(e) => Response::read_failed(&e),
+                        };
+                        if let Err(e) = server::write_response(&mut stream, response).await {
+                            tracing::debug!("Relay response failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to accept connection: {}", e),
+            },
+            _ = tokio::signal::ctrl_c() => {
+                #[cfg(unix)]
+                if let Some(path) = &args.socket {
+                    let _ = std::fs::remove_file(path);
+                }
+                return Ok(CommandOutput::message("Relay stopped"));
+            }
+        }
+    }
+}
+
+/// A notification waiting for its turn on a device
+#[derive(Debug)]
+struct Pending {
+    priority: Priority,
+    /// Arrival order, so equal priorities are shown first come, first served
+    seq: u64,
+    notification: Notification,
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
+
+/// What happened to a submitted notification
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    /// Waiting in the device's queue at this position (1 is next)
+    Queued { position: usize },
+    /// Critical; sent right away, interrupting the display
+    Immediate,
+    /// The same notification was accepted for the device within the window
+    Duplicate,
+    /// The sender sent too many notifications; retry after this many seconds
+    RateLimited { retry_after: u64 },
+    /// The device's queue is full
+    QueueFull,
+}
+
+#[derive(Default)]
+struct Queues {
+    pending: HashMap<String, BinaryHeap<Pending>>,
+    /// When each device and payload was last accepted
+    seen: HashMap<String, Instant>,
+    /// When each sender's recent notifications were accepted
+    senders: HashMap<String, VecDeque<Instant>>,
+    seq: u64,
+}
+
+/// Accepts notifications from many senders and shows them one at a time
+struct Relay {
+    config: Config,
+    default_device: Option<String>,
+    dedupe_window: Duration,
+    /// Notifications per sender and minute; 0 for no limit
+    rate_limit: usize,
+    /// Notifications waiting per device; 0 for no limit
+    max_queue: usize,
+    /// Display time for notifications without a `duration`
+    gap: Duration,
+    queues: Mutex<Queues>,
+    /// Wakes the dispatcher of each device
+    dispatchers: Mutex<HashMap<String, Arc<Notify>>>,
+}
+
+impl Relay {
+    fn new(
+        config: Config,
+        default_device: Option<String>,
+        dedupe_window: Duration,
+        rate_limit: usize,
+        max_queue: usize,
+        gap: Duration,
+    ) -> Self {
+        Self {
+            config,
+            default_device,
+            dedupe_window,
+            rate_limit,
+            max_queue,
+            gap,
+            queues: Mutex::default(),
+            dispatchers: Mutex::default(),
+        }
+    }
+
+    /// Dedupe, rate-limit and queue a notification
+    ///
+    /// `sender` identifies the connection (peer address or socket user) and is
+    /// what the rate limit counts, since a sender can name any source.
+    fn submit(
+        &self,
+        device: &str,
+        sender: &str,
+        priority: Priority,
+        mut notification: Notification,
+        now: Instant,
+    ) -> Outcome {
+        let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+
+        let window = self.dedupe_window;
+        queues
+            .seen
+            .retain(|_, accepted| now.duration_since(*accepted) < window);
+        let key = format!(
+            "{}\n{}",
+            device,
+            serde_json::to_string(&notification).unwrap_or_default()
+        );
+        if !window.is_zero() && queues.seen.contains_key(&key) {
+            return Outcome::Duplicate;
+        }
+
+        let queued = queues.pending.get(device).map_or(0, BinaryHeap::len);
+        if priority < Priority::Critical && self.max_queue > 0 && queued >= self.max_queue {
+            return Outcome::QueueFull;
+        }
+
+        // Forget senders with nothing in the window, so they do not pile up
+        queues.senders.retain(|_, recent| {
+            while recent
+                .front()
+                .is_some_and(|accepted| now.duration_since(*accepted) >= RATE_WINDOW)
+            {
+                recent.pop_front();
+            }
+            !recent.is_empty()
+        });
+        let recent = queues.senders.entry(sender.to_string()).or_default();
+        if priority < Priority::Critical && self.rate_limit > 0 && recent.len() >= self.rate_limit {
+            let oldest = recent.front().copied().unwrap_or(now);
+            let retry_after = RATE_WINDOW.saturating_sub(now.duration_since(oldest));
+            return Outcome::RateLimited {
+                retry_after: retry_after.as_secs().max(1),
+            };
+        }
+        recent.push_back(now);
+        if !window.is_zero() {
+            queues.seen.insert(key, now);
+        }
+
+        if priority == Priority::Critical {
+            return Outcome::Immediate;
+        }
+
+        // Queue on the device rather than cutting off what it shows
+        notification.stack = Some(true);
+        queues.seq += 1;
+        let pending = Pending {
+            priority,
+            seq: queues.seq,
+            notification,
+        };
+        let queue = queues.pending.entry(device.to_string()).or_default();
+        let position = queue.iter().filter(|other| **other > pending).count() + 1;
+        queue.push(pending);
+        Outcome::Queued { position }
+    }
+
+    /// Next notification for a device, highest priority first
+    fn next(&self, device: &str) -> Option<(Notification, Priority)> {
+        let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+        queues
+            .pending
+            .get_mut(device)?
+            .pop()
+            .map(|pending| (pending.notification, pending.priority))
+    }
+
+    /// Start the device's dispatcher if needed and tell it there is work
+    fn wake(self: &Arc<Self>, device: &str) {
+        let mut dispatchers = self.dispatchers.lock().unwrap_or_else(|e| e.into_inner());
+        let wake = dispatchers.entry(device.to_string()).or_insert_with(|| {
+            let wake = Arc::new(Notify::new());
+            tokio::spawn(dispatch(self.clone(), device.to_string(), wake.clone()));
+            wake
+        });
+        wake.notify_one();
+    }
+
+    /// Send one notification, applying quiet hours and queueing it offline
+    /// if that is enabled; returns whether it was shown
+    async fn deliver(&self, device: &str, notification: Notification, priority: Priority) -> bool {
+        let notification = match quiet_gate(&self.config, device, notification, priority) {
+            Ok(Gate::Send(notification)) => notification,
+            Ok(Gate::Blocked(until)) => {
+                tracing::info!(
+                    "Quiet hours on {} until {}; notification dropped",
+                    device,
+                    until.format("%H:%M")
+                );
+                return false;
+            }
+            Ok(Gate::Deferred(queued)) => {
+                tracing::info!(
+                    "Quiet hours on {}; notification deferred as #{}",
+                    device,
+                    queued.id
+                );
+                return false;
+            }
+            Err(e) => {
+                tracing::error!("Failed to apply quiet hours for {}: {:#}", device, e);
+                return false;
+            }
+        };
+        let notification = &notification;
+
+        let client = match awtrix3::Client::new(self.config.resolve_host(device)) {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::error!("Failed to notify {}: {}", device, e);
+                return false;
+            }
+        };
+
+        let sender = crate::commands::notify::Sender {
+            client: &client,
+            device,
+            source: "relay",
+            history_limit: self.config.preferences.history_limit,
+        };
+        match sender.send(notification, priority).await {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to notify {}: {}", device, e);
+                if self.config.preferences.offline_queue && e.kind() == ErrorKind::Unreachable {
+                    crate::commands::notify::enqueue(
+                        &self.config,
+                        device,
+                        &[(notification.clone(), priority)],
+                    );
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Show a device's queued notifications one at a time, giving each its display time
+async fn dispatch(relay: Arc<Relay>, device: String, wake: Arc<Notify>) {
+    loop {
+        let Some((notification, priority)) = relay.next(&device) else {
+            wake.notified().await;
+            continue;
+        };
+
+        let duration = notification.duration;
+        if relay.deliver(&device, notification, priority).await {
+            let shown = duration.map_or(relay.gap, |seconds| Duration::from_secs(seconds.into()));
+            tokio::time::sleep(shown).await;
+        }
+    }
+}
+
+/// Submit a notification and describe the outcome as a status and JSON body
+///
+/// `source` is the label the sender gave itself, only used in logs.
+fn accept(
+    relay: &Arc<Relay>,
+    device: Option<String>,
+    sender: &str,
+    source: &str,
+    priority: Priority,
+    notification: Notification,
+) -> (u16, Value) {
+    let Some(device) = device.or_else(|| relay.default_device.clone()) else {
+        return (
+            400,
+            json!({ "error": "No device given and no default device configured" }),
+        );
+    };
+
+    tracing::debug!(
+        "Notification from {} ({}) for {} at {} priority",
+        source,
+        sender,
+        device,
+        priority
+    );
+    match relay.submit(
+        &device,
+        sender,
+        priority,
+        notification.clone(),
+        Instant::now(),
+    ) {
+        Outcome::Queued { position } => {
+            relay.wake(&device);
+            (
+                202,
+                json!({ "accepted": true, "device": device, "priority": priority, "position": position }),
+            )
+        }
+        Outcome::Immediate => {
+            let relay = relay.clone();
+            let critical = Notification {
+                stack: Some(false),
+                ..notification
+            };
+            let target = device.clone();
+            tokio::spawn(async move { relay.deliver(&target, critical, priority).await });
+            (
+                202,
+                json!({ "accepted": true, "device": device, "priority": priority, "position": 0 }),
+            )
+        }
+        Outcome::Duplicate => (
+            200,
+            json!({ "accepted": false, "duplicate": true, "device": device }),
+        ),
+        Outcome::RateLimited { retry_after } => (
+            429,
+            json!({
+                "error": format!("Rate limit of {} per minute exceeded for {}", relay.rate_limit, sender),
+                "retry_after": retry_after,
+            }),
+        ),
+        Outcome::QueueFull => (
+            503,
+            json!({
+                "error": format!("{} notifications are already waiting for '{}'", relay.max_queue, device),
+            }),
+        ),
+    }
+}
+
+/// `POST /notify?device=&priority=&source=` with a notification as the body
+fn handle(relay: &Arc<Relay>, request: Request, peer: &str) -> Response {
+    if request.path != "/notify" && request.path != "/api/notify" {
+        return Response::not_found();
+    }
+    if request.method != "POST" {
+        return Response::json(405, &json!({ "error": "Use POST" }));
+    }
+
+    let notification: Notification = match serde_json::from_slice(&request.body) {
+        Ok(notification) => notification,
+        Err(e) => {
+            return Response::json(
+                400,
+                &json!({ "error": format!("Invalid notification: {}", e) }),
+            )
+        }
+    };
+    let priority = match request.query.get("priority").map(|p| p.parse::<Priority>()) {
+        Some(Ok(priority)) => priority,
+        Some(Err(e)) => return Response::json(400, &json!({ "error": e.to_string() })),
+        None => Priority::default(),
+    };
+    let source = request
+        .query
+        .get("source")
+        .map(String::as_str)
+        .or_else(|| request.header("x-awtrix-source"))
+        .unwrap_or(peer)
+        .to_string();
+
+    let device = request.query.get("device").cloned();
+    let (status, body) = accept(relay, device, peer, &source, priority, notification);
+    Response::json(status, &body)
+}
+
+#[cfg(unix)]
+mod socket {
+    use super::*;
+    use serde::Deserialize;
+    use std::os::unix::fs::FileTypeExt;
+    use std::path::Path;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{UnixListener, UnixStream};
+
+    /// One line of JSON sent to the Unix socket
+    #[derive(Debug, Deserialize)]
+    struct SocketRequest {
+        device: Option<String>,
+        #[serde(default)]
+        priority: Priority,
+        source: Option<String>,
+        notification: Notification,
+    }
+
+    /// Listen on a socket path, replacing a stale socket from an earlier run
+    ///
+    /// Only a socket nobody listens on any more is removed; other files and a
+    /// socket of a running relay are left alone.
+    pub fn bind(path: &Path) -> Result<UnixListener> {
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                    return Err(AwtrixError::Validation(format!(
+                        "Cannot listen on {}: another process is listening on it",
+                        path.display()
+                    ))
+                    .into());
+                }
+                std::fs::remove_file(path)
+                    .with_context(|| format!("Cannot replace {}", path.display()))?;
+            }
+            Ok(_) => {
+                return Err(AwtrixError::Validation(format!(
+                    "Cannot listen on {}: it exists and is not a socket",
+                    path.display()
+                ))
+                .into())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Cannot listen on {}", path.display()))
+            }
+        }
+        UnixListener::bind(path).with_context(|| format!("Cannot listen on {}", path.display()))
+    }
+
+    pub async fn serve(listener: UnixListener, relay: Arc<Relay>) {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(connection(stream, relay.clone()));
+                }
+                Err(e) => tracing::warn!("Failed to accept socket connection: {}", e),
+            }
+        }
+    }
+
+    /// Answer each line of JSON with one line of JSON
+    ///
+    /// Lines are limited to [`server::MAX_LINE`] and the connection is closed
+    /// after [`server::IO_TIMEOUT`] without a complete line.
+    async fn connection(stream: UnixStream, relay: Arc<Relay>) {
+        // Rate-limited per user; the `source` of a request is only a label
+        let sender = match stream.peer_cred() {
+            Ok(cred) => format!("uid {}", cred.uid()),
+            Err(_) => "socket".to_string(),
+        };
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+            let mut limited = (&mut reader).take(server::MAX_LINE as u64);
+            let read = match tokio::time::timeout(server::IO_TIMEOUT, limited.read_line(&mut line))
+                .await
+            {
+                Ok(Ok(read)) if read > 0 => read,
+                _ => break,
+            };
+            if read == server::MAX_LINE && !line.ends_with('\n') {
+                let body = json!({ "error": "Request line too long" });
+                let reply = format!("{}\n", body);
+                let _ =
+                    tokio::time::timeout(server::IO_TIMEOUT, writer.write_all(reply.as_bytes()))
+                        .await;
+                break;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (_, body) = match serde_json::from_str::<SocketRequest>(&line) {
+                Ok(request) => accept(
+                    &relay,
+                    request.device,
+                    &sender,
+                    request.source.as_deref().unwrap_or("socket"),
+                    request.priority,
+                    request.notification,
+                ),
+                Err(e) => (400, json!({ "error": format!("Invalid request: {}", e) })),
+            };
+            let reply = format!("{}\n", body);
+            let write = writer.write_all(reply.as_bytes());
+            if !matches!(
+                tokio::time::timeout(server::IO_TIMEOUT, write).await,
+                Ok(Ok(()))
+            ) {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relay(dedupe_window: u64, rate_limit: usize) -> Relay {
+        Relay::new(
+            Config::default(),
+            None,
+            Duration::from_secs(dedupe_window),
+            rate_limit,
+            0,
+            Duration::from_secs(5),
+        )
+    }
+
+    fn text(text: &str) -> Notification {
+        Notification::builder().text(text).build()
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let relay = relay(60, 0);
+        let now = Instant::now();
+
+        relay.submit("lobby", "a", Priority::Low, text("low"), now);
+        relay.submit("lobby", "a", Priority::Normal, text("first"), now);
+        let high = relay.submit("lobby", "a", Priority::High, text("high"), now);
+        relay.submit("lobby", "a", Priority::Normal, text("second"), now);
+        assert_eq!(high, Outcome::Queued { position: 1 });
+
+        let order: Vec<String> = std::iter::from_fn(|| relay.next("lobby"))
+            .filter_map(|(notification, _)| notification.text)
+            .collect();
+        assert_eq!(order, vec!["high", "first", "second", "low"]);
+        assert!(relay.next("kitchen").is_none());
+    }
+
+    #[test]
+    fn test_dedupe_window() {
+        let relay = relay(60, 0);
+        let now = Instant::now();
+
+        assert!(matches!(
+            relay.submit("lobby", "a", Priority::Normal, text("hi"), now),
+            Outcome::Queued { .. }
+        ));
+        assert_eq!(
+            relay.submit("lobby", "b", Priority::High, text("hi"), now),
+            Outcome::Duplicate
+        );
+        // Other devices and later messages are not duplicates
+        assert!(matches!(
+            relay.submit("kitchen", "a", Priority::Normal, text("hi"), now),
+            Outcome::Queued { .. }
+        ));
+        assert!(matches!(
+            relay.submit(
+                "lobby",
+                "a",
+                Priority::Normal,
+                text("hi"),
+                now + Duration::from_secs(60)
+            ),
+            Outcome::Queued { .. }
+        ));
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let relay = relay(0, 2);
+        let now = Instant::now();
+
+        relay.submit("lobby", "cron", Priority::Normal, text("1"), now);
+        relay.submit("lobby", "cron", Priority::Normal, text("2"), now);
+        assert_eq!(
+            relay.submit(
+                "lobby",
+                "cron",
+                Priority::Normal,
+                text("3"),
+                now + Duration::from_secs(20)
+            ),
+            Outcome::RateLimited { retry_after: 40 }
+        );
+        assert_eq!(
+            relay.submit("lobby", "cron", Priority::Critical, text("4"), now),
+            Outcome::Immediate
+        );
+        assert!(matches!(
+            relay.submit("lobby", "backup", Priority::Normal, text("5"), now),
+            Outcome::Queued { .. }
+        ));
+        assert!(matches!(
+            relay.submit(
+                "lobby",
+                "cron",
+                Priority::Normal,
+                text("6"),
+                now + RATE_WINDOW
+            ),
+            Outcome::Queued { .. }
+        ));
+        // Senders with nothing left in the window are forgotten
+        let queues = relay.queues.lock().unwrap();
+        assert_eq!(queues.senders.keys().collect::<Vec<_>>(), ["cron"]);
+    }
+
+    #[test]
+    fn test_queue_limit() {
+        let relay = Relay {
+            max_queue: 2,
+            ..relay(0, 0)
+        };
+        let now = Instant::now();
+
+        relay.submit("lobby", "a", Priority::Normal, text("1"), now);
+        relay.submit("lobby", "a", Priority::Normal, text("2"), now);
+        assert_eq!(
+            relay.submit("lobby", "b", Priority::High, text("3"), now),
+            Outcome::QueueFull
+        );
+        assert_eq!(
+            relay.submit("lobby", "b", Priority::Critical, text("4"), now),
+            Outcome::Immediate
+        );
+        relay.next("lobby");
+        assert!(matches!(
+            relay.submit("lobby", "b", Priority::Normal, text("5"), now),
+            Outcome::Queued { position: 2 }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_source_does_not_bypass_rate_limit() {
+        let relay = Arc::new(Relay {
+            default_device: Some("127.0.0.1:9".to_string()),
+            ..relay(0, 1)
+        });
+        let request = |source: &str| Request {
+            method: "POST".to_string(),
+            path: "/notify".to_string(),
+            query: HashMap::from([("source".to_string(), source.to_string())]),
+            headers: HashMap::new(),
+            body: br#"{"text": "hi"}"#.to_vec(),
+        };
+
+        assert_eq!(handle(&relay, request("a"), "10.0.0.7").status, 202);
+        assert_eq!(handle(&relay, request("b"), "10.0.0.7").status, 429);
+        assert_eq!(handle(&relay, request("b"), "10.0.0.8").status, 202);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_socket_bind_keeps_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "keep me").unwrap();
+        assert!(socket::bind(&file).is_err());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep me");
+
+        // A live socket is not taken over, a stale one is replaced
+        let path = dir.path().join("relay.sock");
+        let listener = socket::bind(&path).unwrap();
+        assert!(socket::bind(&path).is_err());
+        drop(listener);
+        assert!(socket::bind(&path).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_socket_rejects_long_lines() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("relay.sock");
+        let listener = socket::bind(&path).unwrap();
+        tokio::spawn(socket::serve(listener, Arc::new(relay(60, 0))));
+
+        let stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        writer
+            .write_all(&vec![b'x'; server::MAX_LINE + 1])
+            .await
+            .unwrap();
+
+        let mut lines = BufReader::new(reader).lines();
+        let reply = lines.next_line().await.unwrap().unwrap();
+        assert!(reply.contains("too long"), "{}", reply);
+        // The connection is closed afterwards, possibly with unread input
+        assert!(!matches!(lines.next_line().await, Ok(Some(_))));
+    }
+
+    #[test]
+    fn test_handle_rejects_bad_requests() {
+        let relay = Arc::new(relay(60, 0));
+        let request = |path: &str, body: &str, query: &[(&str, &str)]| Request {
+            method: "POST".to_string(),
+            path: path.to_string(),
+            query: query
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            headers: HashMap::new(),
+            body: body.as_bytes().to_vec(),
+        };
+
+        assert_eq!(
+            handle(&relay, request("/nope", "{}", &[]), "peer").status,
+            404
+        );
+        assert_eq!(
+            handle(&relay, request("/notify", "nope", &[]), "peer").status,
+            400
+        );
+        assert_eq!(
+            handle(
+                &relay,
+                request("/notify", "{}", &[("priority", "urgent")]),
+                "peer"
+            )
+            .status,
+            400
+        );
+        // No device and no default device
+        assert_eq!(
+            handle(&relay, request("/notify", "{}", &[]), "peer").status,
+            400
+        );
+    }
+}
@@ -0,0 +1,499 @@
+use crate::cli::ServeArgs;
+use crate::commands::notify::{quiet_gate, Gate};
+use crate::config::{Config, NotificationLog, WebhookRoute};
+use crate::output::CommandOutput;
+use crate::server::{self, Request, Response};
+use anyhow::{Context, Result};
+use awtrix3::models::Priority;
+use awtrix3::utils::alertmanager::{self, FiringCritical};
+use awtrix3::utils::webhook::{self, WebhookFormat};
+use awtrix3::{AwtrixError, Color, ErrorKind, Notification};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+pub async fn execute(args: ServeArgs, config: Config) -> Result<CommandOutput> {
+    if config.webhooks.is_empty() {
+        return Err(AwtrixError::Config(
+            "No webhook routes configured. Add [[webhooks]] entries to the config".to_string(),
+        )
+        .into());
+    }
+    for route in &config.webhooks {
+        if !route.path.starts_with('/') {
+            return Err(AwtrixError::Config(format!(
+                "Webhook path '{}' must start with '/'",
+                route.path
+            ))
+            .into());
+        }
+        if route
+            .indicator
+            .is_some_and(|indicator| !(1..=3).contains(&indicator))
+        {
+            return Err(AwtrixError::Config(format!(
+                "Webhook '{}': indicator must be 1, 2 or 3",
+                route.path
+            ))
+            .into());
+        }
+    }
+
+    for quiet in &config.quiet_hours {
+        quiet.validate()?;
+    }
+
+    let address = server::parse_listen(&args.listen)?;
+    let listener = TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Cannot listen on {}", address))?;
+
+    if crate::output::is_human() {
+        println!("Serving webhooks on http://{}", address);
+        for route in &config.webhooks {
+            println!(
+                "  POST {} ({:?}) → {}",
+                route.path,
+                route.format,
+                describe_devices(route, &config)
+            );
+        }
+        println!("Press Ctrl+C to stop...");
+    }
+
+    let config = Arc::new(config);
+    let state = Arc::new(ServeState {
+        history: Some(NotificationLog::open(config.preferences.history_limit)?),
+        ..ServeState::default()
+    });
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((mut stream, _)) => {
+                    let config = config.clone();
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        let response = match server::read_request(&mut stream).await {
+                            Ok(request) => handle(request, &config, &state).await,
+                            Err(e) => Response::read_failed(&e),
+                        };
+                        if let Err(e) = server::write_response(&mut stream, response).await {
+                            tracing::debug!("Webhook response failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to accept connection: {}", e),
+            },
+            _ = tokio::signal::ctrl_c() => {
+                return Ok(CommandOutput::message("Webhook server stopped"));
+            }
+        }
+    }
+}
+
+fn describe_devices(route: &WebhookRoute, config: &Config) -> String {
+    if route.devices.is_empty() {
+        config
+            .default_device
+            .clone()
+            .unwrap_or_else(|| "(no default device)".to_string())
+    } else {
+        route.devices.join(", ")
+    }
+}
+
+/// State kept between webhook requests
+#[derive(Default)]
+struct ServeState {
+    /// Firing critical alerts per Alertmanager route path
+    firing: Mutex<HashMap<String, FiringCritical>>,
+    /// Where sent notifications are recorded
+    history: Option<NotificationLog>,
+}
+
+/// Turn a webhook request into notifications on the route's devices
+async fn handle(request: Request, config: &Config, state: &ServeState) -> Response {
+    let Some(route) = config
+        .webhooks
+        .iter()
+        .find(|route| route.path == request.path)
+    else {
+        return Response::not_found();
+    };
+
+    if request.method != "POST" {
+        return Response::json(405, &json!({ "error": "Use POST" }));
+    }
+
+    if let Some(token) = &route.token {
+        let given = request
+            .header("x-awtrix-token")
+            .or_else(|| request.header("x-gitlab-token"))
+            .or_else(|| request.query.get("token").map(String::as_str));
+        if !given.is_some_and(|given| server::secret_eq(given, token)) {
+            return Response::json(401, &json!({ "error": "Invalid or missing token" }));
+        }
+    }
+
+    let payload: Value = match serde_json::from_slice(&request.body) {
+        Ok(payload) => payload,
+        Err(e) => return Response::json(400, &json!({ "error": format!("Invalid JSON: {}", e) })),
+    };
+
+    if route.format == WebhookFormat::Alertmanager {
+        return handle_alertmanager(route, payload, config, state).await;
+    }
+
+    let event = request
+        .header("x-github-event")
+        .or_else(|| request.header("x-gitlab-event"));
+    let notification = match webhook::to_notification(
+        route.format,
+        event,
+        &payload,
+        route.notification.as_ref(),
+    ) {
+        Ok(notification) => notification,
+        Err(e) => return Response::json(400, &json!({ "error": e.to_string() })),
+    };
+
+    let results = deliver(
+        config,
+        state,
+        &route_devices(route, config),
+        &[(notification, Priority::Normal)],
+        None,
+    )
+    .await;
+    summarize(route, results)
+}
+
+/// One notification per alert, with the indicator lit while critical alerts fire
+async fn handle_alertmanager(
+    route: &WebhookRoute,
+    payload: Value,
+    config: &Config,
+    state: &ServeState,
+) -> Response {
+    let payload: alertmanager::Payload = match serde_json::from_value(payload) {
+        Ok(payload) => payload,
+        Err(e) => {
+            return Response::json(
+                400,
+                &json!({ "error": format!("Invalid Alertmanager payload: {}", e) }),
+            )
+        }
+    };
+
+    let notifications = match payload
+        .alerts
+        .iter()
+        .map(|alert| {
+            // Firing critical alerts get through quiet hours
+            let priority = if alert.is_firing() && alert.severity() == alertmanager::CRITICAL {
+                Priority::Critical
+            } else {
+                Priority::Normal
+            };
+            alertmanager::notification(alert, &route.severities, route.notification.as_ref())
+                .map(|notification| (notification, priority))
+        })
+        .collect::<awtrix3::Result<Vec<_>>>()
+    {
+        Ok(notifications) => notifications,
+        Err(e) => return Response::json(400, &json!({ "error": e.to_string() })),
+    };
+
+    let critical = state
+        .firing
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(route.path.clone())
+        .or_default()
+        .update(&payload);
+
+    let indicator = route.indicator.map(|indicator| {
+        let color = alertmanager::style(&route.severities, alertmanager::CRITICAL)
+            .color
+            .unwrap_or(Color::RED);
+        (indicator, critical.then_some(color))
+    });
+
+    let results = deliver(
+        config,
+        state,
+        &route_devices(route, config),
+        &notifications,
+        indicator,
+    )
+    .await;
+    summarize(route, results)
+}
+
+/// The route's devices, or the default device
+fn route_devices(route: &WebhookRoute, config: &Config) -> Vec<String> {
+    if route.devices.is_empty() {
+        config.default_device.iter().cloned().collect()
+    } else {
+        route.devices.clone()
+    }
+}
+
+/// Response listing per-device results; 502 if no device was reached
+fn summarize(route: &WebhookRoute, results: Vec<(String, awtrix3::Result<()>)>) -> Response {
+    let sent = results.iter().filter(|(_, result)| result.is_ok()).count();
+    tracing::info!(
+        "Webhook {}: notified {}/{} device(s)",
+        route.path,
+        sent,
+        results.len()
+    );
+
+    let status = if sent == 0 { 502 } else { 200 };
+    let devices: Vec<Value> = results
+        .into_iter()
+        .map(|(device, result)| match result {
+            Ok(()) => json!({ "device": device, "ok": true }),
+            Err(e) => json!({ "device": device, "ok": false, "error": e.to_string() }),
+        })
+        .collect();
+    Response::json(status, &json!({ "sent": sent, "devices": devices }))
+}
+
+/// Send notifications to each device and set or clear an indicator, reporting per-device results
+async fn deliver(
+    config: &Config,
+    state: &ServeState,
+    devices: &[String],
+    notifications: &[(Notification, Priority)],
+    indicator: Option<(u8, Option<Color>)>,
+) -> Vec<(String, awtrix3::Result<()>)> {
+    let mut results = Vec::new();
+    for device in devices {
+        let result = async {
+            let client = awtrix3::Client::new(config.resolve_host(device))?;
+            let notified = async {
+                for (index, (notification, priority)) in notifications.iter().enumerate() {
+                    let notification =
+                        match quiet_gate(config, device, notification.clone(), *priority)
+                            .map_err(AwtrixError::Other)?
+                        {
+                            Gate::Send(notification) => notification,
+                            Gate::Blocked(_) | Gate::Deferred(_) => continue,
+                        };
+
+                    let result = client.notify(notification.clone()).await;
+                    record(state, device, &notification, *priority, &result);
+                    if let Err(e) = result {
+                        if config.preferences.offline_queue && e.kind() == ErrorKind::Unreachable {
+                            crate::commands::notify::enqueue(
+                                config,
+                                device,
+                                &notifications[index..],
+                            );
+                        }
+                        return Err(e);
+                    }
+                }
+                Ok(())
+            }
+            .await;
+
+            // The indicator follows the alert state even if a notification failed
+            let lit = match indicator {
+                Some((indicator, color)) => client.set_indicator(indicator, color).await,
+                None => Ok(()),
+            };
+            notified.and(lit)
+        }
+        .await;
+
+        if let Err(e) = &result {
+            tracing::error!("Failed to notify {}: {}", device, e);
+        }
+        results.push((device.clone(), result));
+    }
+    results
+}
+
+fn record(
+    state: &ServeState,
+    device: &str,
+    notification: &Notification,
+    priority: Priority,
+    result: &awtrix3::Result<()>,
+) {
+    let Some(history) = &state.history else {
+        return;
+    };
+    let error = result.as_ref().err().map(ToString::to_string);
+    if let Err(e) = history.record(device, "serve", notification, priority, error) {
+        tracing::warn!("Could not record notification history: {:#}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn request(path: &str, body: Value) -> Request {
+        Request {
+            method: "POST".to_string(),
+            path: path.to_string(),
+            query: HashMap::new(),
+            headers: HashMap::from([("x-github-event".to_string(), "push".to_string())]),
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    fn config(host: String, token: Option<&str>) -> Config {
+        Config {
+            default_device: Some(host),
+            webhooks: vec![WebhookRoute {
+                path: "/github".to_string(),
+                format: WebhookFormat::GitHub,
+                devices: Vec::new(),
+                notification: None,
+                token: token.map(str::to_string),
+                severities: HashMap::new(),
+                indicator: None,
+            }],
+            ..Config::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_github_push_notifies_device() {
+        let device = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/notify"))
+            .and(body_json(json!({ "text": "awtrix3-rs: push by jeder" })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&device)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let history = NotificationLog::new(dir.path().join("history.jsonl"), 10);
+        let state = ServeState {
+            history: Some(history.clone()),
+            ..ServeState::default()
+        };
+
+        let payload =
+            json!({ "repository": { "name": "awtrix3-rs" }, "pusher": { "name": "jeder" } });
+        let response = handle(
+            request("/github", payload),
+            &config(device.uri(), None),
+            &state,
+        )
+        .await;
+        assert_eq!(response.status, 200);
+        assert!(response.body.contains("\"sent\":1"));
+
+        let entries = history.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].source, "serve");
+        assert_eq!(entries[0].device, device.uri());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_bad_requests() {
+        let config = config("127.0.0.1:9".to_string(), Some("s3cret"));
+        let state = ServeState::default();
+
+        let response = handle(request("/nope", json!({})), &config, &state).await;
+        assert_eq!(response.status, 404);
+
+        let response = handle(request("/github", json!({})), &config, &state).await;
+        assert_eq!(response.status, 401);
+
+        let mut bad_json = request("/github", json!({}));
+        bad_json
+            .query
+            .insert("token".to_string(), "s3cret".to_string());
+        bad_json.body = b"not json".to_vec();
+        assert_eq!(handle(bad_json, &config, &state).await.status, 400);
+    }
+
+    #[tokio::test]
+    async fn test_alertmanager_indicator() {
+        let device = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/notify"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&device)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/indicator2"))
+            .and(body_json(json!({ "color": [255, 0, 0] })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&device)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/indicator2"))
+            .and(body_json(json!({})))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&device)
+            .await;
+
+        let mut config = config(device.uri(), None);
+        config.webhooks[0].path = "/alerts".to_string();
+        config.webhooks[0].format = WebhookFormat::Alertmanager;
+        config.webhooks[0].indicator = Some(2);
+        let state = ServeState::default();
+
+        let alert = |status: &str| {
+            json!({
+                "status": status,
+                "alerts": [{
+                    "status": status,
+                    "labels": { "alertname": "NodeDown", "severity": "critical" },
+                    "fingerprint": "abc",
+                }],
+            })
+        };
+
+        let response = handle(request("/alerts", alert("firing")), &config, &state).await;
+        assert_eq!(response.status, 200);
+        let response = handle(request("/alerts", alert("resolved")), &config, &state).await;
+        assert_eq!(response.status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_indicator_set_when_notify_fails() {
+        let device = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/notify"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&device)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/indicator1"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&device)
+            .await;
+
+        let mut config = config(device.uri(), None);
+        config.webhooks[0].path = "/alerts".to_string();
+        config.webhooks[0].format = WebhookFormat::Alertmanager;
+        config.webhooks[0].indicator = Some(1);
+
+        let payload = json!({
+            "status": "firing",
+            "alerts": [{
+                "status": "firing",
+                "labels": { "alertname": "NodeDown", "severity": "critical" },
+            }],
+        });
+        let response = handle(request("/alerts", payload), &config, &ServeState::default()).await;
+        assert_eq!(response.status, 502);
+    }
+}
@@ -1,47 +1,142 @@
 use crate::cli::*;
+use crate::config::{Config, ProfileStore};
+use crate::output::CommandOutput;
 // use crate::utils::parse_color;
 use anyhow::Result;
+use awtrix3::models::SettingChange;
+use awtrix3::AwtrixError;
 use awtrix3::Settings;
-// use serde_json::Value;
+use colored::Colorize;
+use dialoguer::Confirm;
+use serde_json::{json, Value};
+use std::fmt::Write;
 use std::fs;
-
-pub async fn execute(client: awtrix3::Client, command: SettingsCommands) -> Result<()> {
-    match command {
+use std::io::IsTerminal;
+
+pub async fn execute(
+    client: awtrix3::Client,
+    command: SettingsCommands,
+    config: &Config,
+    device: &str,
+) -> Result<CommandOutput> {
+    let output = match command {
         SettingsCommands::Get { key } => {
             let settings = client.get_settings().await?;
 
             if let Some(key) = key {
-                display_specific_setting(&settings, &key)?;
+                let text = display_specific_setting(&settings, &key)?;
+                let value = settings.flatten().remove(&key);
+                CommandOutput::data(&json!({ "key": key, "value": value }))?.text(text)
             } else {
-                display_all_settings(&settings);
+                CommandOutput::data(&settings)?.text(display_all_settings(&settings))
             }
         }
-        SettingsCommands::Set { key, value } => {
-            // Get current settings
-            let mut settings = client.get_settings().await?;
+        SettingsCommands::Set { key, value, save } => {
+            // Build an update containing only the changed setting
+            let mut update = Settings::new();
+            update_setting(&mut update, &key, &value)?;
+
+            // Apply all-or-nothing, restoring the previous settings on failure
+            client
+                .settings_transaction()
+                .update(update)
+                .save(save)
+                .commit()
+                .await?;
+
+            CommandOutput::message(format!("Setting '{}' updated to '{}'", key, value))
+                .field("key", key)
+                .field("value", value)
+        }
+        SettingsCommands::Import {
+            file,
+            dry_run,
+            only,
+            yes,
+            save,
+        } => {
+            let imported_settings = read_settings_file(&file)?;
+            let current = client.get_settings().await?;
+
+            // Keys missing from the file are left untouched on the device
+            let changes: Vec<SettingChange> = current
+                .diff(&imported_settings)
+                .into_iter()
+                .filter(|change| change.desired.is_some())
+                .collect();
+
+            let diff = display_diff(&changes, "device", &file);
+
+            if dry_run {
+                return Ok(
+                    CommandOutput::data(&json!({ "changes": changes, "applied": false }))?
+                        .text(format!("{}\nDry run - no settings were applied", diff)),
+                );
+            }
 
-            // Update the specific setting
-            update_setting(&mut settings, &key, &value)?;
+            if crate::output::is_human() {
+                println!("{}", diff);
+            }
 
-            // Send updated settings
-            client.update_settings(&settings).await?;
+            let payload = match only {
+                ImportScope::All => imported_settings,
+                ImportScope::Changed => current.changes_to(&imported_settings),
+            };
+
+            if payload.is_empty() {
+                return Ok(CommandOutput::message(format!(
+                    "Device already matches {} - nothing to import",
+                    file
+                ))
+                .field("changes", changes));
+            }
 
-            println!("Setting '{}' updated to '{}'", key, value);
-        }
-        SettingsCommands::Import { file } => {
-            // Validate file exists
-            if !std::path::Path::new(&file).exists() {
-                return Err(anyhow::anyhow!("Settings file not found: {}", file));
+            if !yes {
+                if !std::io::stdin().is_terminal() {
+                    return Err(AwtrixError::Validation(
+                        "Cannot ask for confirmation without a terminal; use --yes to apply non-interactively"
+                            .to_string(),
+                    )
+                    .into());
+                }
+                let confirmed = Confirm::new()
+                    .with_prompt("Apply these settings to the device?")
+                    .default(false)
+                    .interact()?;
+
+                if !confirmed {
+                    return Ok(CommandOutput::message("Import cancelled").field("cancelled", true));
+                }
             }
 
-            // Read and parse settings file
-            let content = fs::read_to_string(&file)?;
-            let imported_settings: Settings = serde_json::from_str(&content)?;
+            // Apply imported settings, restoring the previous settings on failure
+            client
+                .settings_transaction()
+                .update(payload)
+                .save(save)
+                .commit()
+                .await?;
+
+            CommandOutput::message(format!("Settings imported from: {}", file))
+                .field("file", file)
+                .field("changes", changes)
+        }
+        SettingsCommands::Diff { file, with } => {
+            let current = client.get_settings().await?;
 
-            // Apply imported settings
-            client.update_settings(&imported_settings).await?;
+            let (label, other) = if let Some(device) = with {
+                let other_client = awtrix3::Client::new(config.resolve_host(&device))?;
+                (device, other_client.get_settings().await?)
+            } else {
+                let file = file
+                    .ok_or_else(|| AwtrixError::Validation("No settings file given".to_string()))?;
+                let settings = read_settings_file(&file)?;
+                (file, settings)
+            };
 
-            println!("Settings imported from: {}", file);
+            let changes = current.diff(&other);
+            let diff = display_diff(&changes, "device", &label);
+            CommandOutput::data(&json!({ "with": label, "changes": changes }))?.text(diff)
         }
         SettingsCommands::Export { output } => {
             let settings = client.get_settings().await?;
@@ -57,217 +152,420 @@ pub async fn execute(client: awtrix3::Client, command: SettingsCommands) -> Resu
 
             if filename == "-" {
                 // Output to stdout
-                println!("{}", json_content);
+                CommandOutput::data(&settings)?.text(json_content)
             } else {
                 // Write to file
                 fs::write(&filename, json_content)?;
-                println!("Settings exported to: {}", filename);
+                CommandOutput::message(format!("Settings exported to: {}", filename))
+                    .field("file", filename)
             }
         }
         SettingsCommands::List => {
-            display_settings_documentation();
+            let settings: Vec<Value> = SETTINGS_DOCUMENTATION
+                .iter()
+                .flat_map(|(section, keys)| {
+                    keys.iter().map(move |(key, description)| {
+                        json!({ "key": key, "section": section, "description": description })
+                    })
+                })
+                .collect();
+            CommandOutput::data(&json!({ "settings": settings }))?
+                .text(display_settings_documentation())
+        }
+        SettingsCommands::Profile { command } => {
+            execute_profile(&client, command, &config.snapshot_key(device)).await?
+        }
+        SettingsCommands::Sync { .. } => sync(command, config).await?,
+    };
+
+    Ok(output)
+}
+
+/// Copy settings from one device to others
+pub async fn sync(command: SettingsCommands, config: &Config) -> Result<CommandOutput> {
+    let SettingsCommands::Sync {
+        from,
+        to,
+        keys,
+        dry_run,
+        save,
+    } = command
+    else {
+        unreachable!("sync called with another settings command")
+    };
+
+    let source = awtrix3::Client::new(config.resolve_host(&from))?
+        .get_settings()
+        .await?;
+    let source = if keys.is_empty() {
+        source
+    } else {
+        source.filter_keys(&keys)
+    };
+
+    if source.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No settings on '{}' match the given keys",
+            from
+        ));
+    }
+
+    let mut lines = Vec::new();
+    let mut results = Vec::new();
+    let mut failed = Vec::new();
+
+    for target in &to {
+        match sync_device(config, &source, target, dry_run, save).await {
+            Ok((changes, synced)) => {
+                if crate::output::is_human() {
+                    println!("\n{}", display_diff(&changes, target, &from));
+                }
+                if synced {
+                    lines.push(format!("✅ {} synced from {}", target, from));
+                }
+                results.push(json!({ "device": target, "changes": changes, "synced": synced }));
+            }
+            Err(e) => {
+                eprintln!("{} {}: {}", "Failed:".red().bold(), target, e);
+                failed.push(target.as_str());
+            }
         }
     }
 
+    if dry_run {
+        lines.push("Dry run - no settings were applied".to_string());
+    }
+
+    if failed.is_empty() {
+        Ok(
+            CommandOutput::data(&json!({ "from": from, "devices": results, "dry_run": dry_run }))?
+                .text(format!("\n{}", lines.join("\n"))),
+        )
+    } else {
+        Err(anyhow::anyhow!(
+            "Sync failed for {} of {} device(s): {}",
+            failed.len(),
+            to.len(),
+            failed.join(", ")
+        ))
+    }
+}
+
+/// Sync one device, returning the changes and whether they were applied
+async fn sync_device(
+    config: &Config,
+    source: &Settings,
+    target: &str,
+    dry_run: bool,
+    save: bool,
+) -> Result<(Vec<SettingChange>, bool)> {
+    let client = awtrix3::Client::new(config.resolve_host(target))?;
+    let current = client.get_settings().await?;
+
+    let changes: Vec<SettingChange> = current
+        .diff(source)
+        .into_iter()
+        .filter(|change| change.desired.is_some())
+        .collect();
+
+    if dry_run || changes.is_empty() {
+        return Ok((changes, false));
+    }
+
+    client
+        .settings_transaction()
+        .update(current.changes_to(source))
+        .save(save)
+        .commit()
+        .await?;
+
+    Ok((changes, true))
+}
+
+/// `snapshot` is the device's [`Config::snapshot_key`]
+async fn execute_profile(
+    client: &awtrix3::Client,
+    command: ProfileCommands,
+    snapshot: &str,
+) -> Result<CommandOutput> {
+    let store = ProfileStore::open()?;
+
+    let output = match command {
+        ProfileCommands::Save { name, file } => {
+            let settings = match file {
+                Some(file) => read_settings_file(&file)?,
+                None => client.get_settings().await?,
+            };
+
+            let path = store.save(&name, &settings)?;
+            CommandOutput::message(format!("Profile '{}' saved to: {}", name, path.display()))
+                .field("profile", name)
+                .field("file", path)
+        }
+        ProfileCommands::Apply { name } => {
+            apply_profile(client, &store, &name, snapshot).await?;
+            CommandOutput::message(format!(
+                "Profile '{}' applied\nUse 'awtrix settings profile revert' to undo",
+                name
+            ))
+            .field("profile", name)
+        }
+        ProfileCommands::Revert => {
+            // Restores the settings from before the revert if it fails
+            client
+                .settings_transaction()
+                .update(store.load_snapshot(snapshot)?)
+                .commit()
+                .await?;
+            CommandOutput::message(
+                "Settings reverted to snapshot taken before the last profile was applied",
+            )
+        }
+        ProfileCommands::List => {
+            let names = store.list()?;
+            let text = if names.is_empty() {
+                "No profiles saved.\nUse 'awtrix settings profile save <name>' to create one."
+                    .to_string()
+            } else {
+                let mut lines = vec!["Saved Profiles:".to_string()];
+                lines.extend(names.iter().map(|name| format!("  - {}", name)));
+                lines.join("\n")
+            };
+            CommandOutput::data(&json!({ "profiles": names }))?.text(text)
+        }
+        ProfileCommands::Delete { name } => {
+            store.delete(&name)?;
+            CommandOutput::message(format!("Profile '{}' deleted", name)).field("profile", name)
+        }
+    };
+
+    Ok(output)
+}
+
+/// Apply a saved profile, snapshotting the current settings first for `profile revert`
+///
+/// `snapshot` is the device's [`Config::snapshot_key`].
+pub async fn apply_profile(
+    client: &awtrix3::Client,
+    store: &ProfileStore,
+    name: &str,
+    snapshot: &str,
+) -> Result<()> {
+    let profile = store.load(name)?;
+
+    let current = client.get_settings().await?;
+    store.save_snapshot(snapshot, &current)?;
+
+    client
+        .settings_transaction()
+        .update(profile)
+        .commit()
+        .await?;
     Ok(())
 }
 
-fn display_all_settings(settings: &Settings) {
-    println!("Current AWTRIX3 Settings:");
-    println!("");
+fn read_settings_file(file: &str) -> Result<Settings> {
+    // Validate file exists
+    if !std::path::Path::new(file).exists() {
+        return Err(anyhow::anyhow!("Settings file not found: {}", file));
+    }
+
+    let content = fs::read_to_string(file)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn display_diff(changes: &[SettingChange], from: &str, to: &str) -> String {
+    if changes.is_empty() {
+        return format!("No differences between {} and {}", from, to);
+    }
+
+    let mut out = format!("Settings diff ({} → {}):\n", from, to);
+
+    for change in changes {
+        let _ = match (&change.current, &change.desired) {
+            (Some(current), Some(desired)) => writeln!(
+                out,
+                "  {} {}: {} → {}",
+                "~".yellow().bold(),
+                change.key,
+                format_value(current).red(),
+                format_value(desired).green()
+            ),
+            (None, Some(desired)) => writeln!(
+                out,
+                "  {} {}: {}",
+                "+".green().bold(),
+                change.key,
+                format_value(desired).green()
+            ),
+            (Some(current), None) => writeln!(
+                out,
+                "  {} {}: {}",
+                "-".red().bold(),
+                change.key,
+                format_value(current).red()
+            ),
+            (None, None) => Ok(()),
+        };
+    }
+
+    let _ = write!(out, "\n{} setting(s) differ", changes.len());
+    out
+}
+
+pub fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        // Colors are sent as RGB arrays; show them as hex like `settings get`
+        Value::Array(rgb) if rgb.len() == 3 => {
+            serde_json::from_value::<awtrix3::Color>(value.clone())
+                .map(|color| color.to_hex())
+                .unwrap_or_else(|_| value.to_string())
+        }
+        _ => value.to_string(),
+    }
+}
+
+fn display_all_settings(settings: &Settings) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Current AWTRIX3 Settings:");
+    let _ = writeln!(out);
 
     // Display settings
     if let Some(brightness) = settings.brightness {
-        println!("  brightness: {} (0-255)", brightness);
+        let _ = writeln!(out, "  brightness: {} (0-255)", brightness);
     }
 
     if let Some(auto_brightness) = settings.auto_brightness {
-        println!("  auto_brightness: {}", auto_brightness);
+        let _ = writeln!(out, "  auto_brightness: {}", auto_brightness);
     }
 
     if let Some(auto_transition) = settings.auto_transition {
-        println!("  auto_transition: {}", auto_transition);
+        let _ = writeln!(out, "  auto_transition: {}", auto_transition);
     }
 
     if let Some(app_time) = settings.app_time {
-        println!("  app_time: {} seconds", app_time);
+        let _ = writeln!(out, "  app_time: {} seconds", app_time);
     }
 
     if let Some(transition) = &settings.transition {
-        println!("  transition: {}", transition);
+        let _ = writeln!(out, "  transition: {}", transition);
     }
 
     if let Some(transition_time) = settings.transition_time {
-        println!("  transition_time: {} ms", transition_time);
+        let _ = writeln!(out, "  transition_time: {} ms", transition_time);
     }
 
     if let Some(text_color) = &settings.text_color {
-        println!("  text_color: {}", text_color.to_hex());
+        let _ = writeln!(out, "  text_color: {}", text_color.to_hex());
     }
 
     if let Some(temp_unit) = &settings.temp_unit {
-        println!("  temp_unit: {}", temp_unit);
+        let _ = writeln!(out, "  temp_unit: {}", temp_unit);
     }
 
     if let Some(scroll_speed) = settings.scroll_speed {
-        println!("  scroll_speed: {}%", scroll_speed);
+        let _ = writeln!(out, "  scroll_speed: {}%", scroll_speed);
     }
 
     // Time app settings
     if let Some(time_app) = &settings.time_app {
-        println!("");
-        println!("  Time App Settings:");
+        let _ = writeln!(out);
+        let _ = writeln!(out, "  Time App Settings:");
         if let Some(format) = time_app.format {
-            println!("    time_app.format: {}", format);
+            let _ = writeln!(out, "    time_app.format: {}", format);
         }
         if let Some(show_weekday) = time_app.show_weekday {
-            println!("    time_app.show_weekday: {}", show_weekday);
+            let _ = writeln!(out, "    time_app.show_weekday: {}", show_weekday);
         }
         if let Some(cal_header_color) = &time_app.cal_header_color {
-            println!(
+            let _ = writeln!(
+                out,
                 "    time_app.cal_header_color: {}",
                 cal_header_color.to_hex()
             );
         }
         if let Some(cal_body_color) = &time_app.cal_body_color {
-            println!("    time_app.cal_body_color: {}", cal_body_color.to_hex());
+            let _ = writeln!(
+                out,
+                "    time_app.cal_body_color: {}",
+                cal_body_color.to_hex()
+            );
         }
         if let Some(cal_text_color) = &time_app.cal_text_color {
-            println!("    time_app.cal_text_color: {}", cal_text_color.to_hex());
+            let _ = writeln!(
+                out,
+                "    time_app.cal_text_color: {}",
+                cal_text_color.to_hex()
+            );
         }
     }
 
     // Date app settings
     if let Some(date_app) = &settings.date_app {
-        println!("");
-        println!("  Date App Settings:");
+        let _ = writeln!(out);
+        let _ = writeln!(out, "  Date App Settings:");
         if let Some(enabled) = date_app.enabled {
-            println!("    date_app.enabled: {}", enabled);
+            let _ = writeln!(out, "    date_app.enabled: {}", enabled);
         }
         if let Some(format) = &date_app.format {
-            println!("    date_app.format: {}", format);
+            let _ = writeln!(out, "    date_app.format: {}", format);
         }
     }
+
+    out.trim_end().to_string()
 }
 
-fn display_specific_setting(settings: &Settings, key: &str) -> Result<()> {
-    match key {
-        "brightness" => {
-            if let Some(value) = settings.brightness {
-                println!("{}", value);
-            } else {
-                println!("Setting '{}' not set", key);
-            }
-        }
-        "auto_brightness" => {
-            if let Some(value) = settings.auto_brightness {
-                println!("{}", value);
-            } else {
-                println!("Setting '{}' not set", key);
-            }
-        }
-        "auto_transition" => {
-            if let Some(value) = settings.auto_transition {
-                println!("{}", value);
-            } else {
-                println!("Setting '{}' not set", key);
-            }
-        }
-        "app_time" => {
-            if let Some(value) = settings.app_time {
-                println!("{}", value);
-            } else {
-                println!("Setting '{}' not set", key);
-            }
-        }
-        "transition" => {
-            if let Some(value) = &settings.transition {
-                println!("{}", value);
-            } else {
-                println!("Setting '{}' not set", key);
-            }
-        }
-        "transition_time" => {
-            if let Some(value) = settings.transition_time {
-                println!("{}", value);
-            } else {
-                println!("Setting '{}' not set", key);
-            }
-        }
-        "text_color" => {
-            if let Some(value) = &settings.text_color {
-                println!("{}", value.to_hex());
-            } else {
-                println!("Setting '{}' not set", key);
-            }
-        }
-        "temp_unit" => {
-            if let Some(value) = &settings.temp_unit {
-                println!("{}", value);
-            } else {
-                println!("Setting '{}' not set", key);
-            }
-        }
-        "scroll_speed" => {
-            if let Some(value) = settings.scroll_speed {
-                println!("{}", value);
-            } else {
-                println!("Setting '{}' not set", key);
-            }
-        }
-        "time_app.format" => {
-            if let Some(time_app) = &settings.time_app {
-                if let Some(value) = time_app.format {
-                    println!("{}", value);
-                } else {
-                    println!("Setting '{}' not set", key);
-                }
-            } else {
-                println!("Setting '{}' not set", key);
-            }
-        }
-        "time_app.show_weekday" => {
-            if let Some(time_app) = &settings.time_app {
-                if let Some(value) = time_app.show_weekday {
-                    println!("{}", value);
-                } else {
-                    println!("Setting '{}' not set", key);
-                }
-            } else {
-                println!("Setting '{}' not set", key);
-            }
-        }
-        _ => {
-            return Err(anyhow::anyhow!("Unknown setting key: {}", key));
-        }
+fn display_specific_setting(settings: &Settings, key: &str) -> Result<String> {
+    const KEYS: &[&str] = &[
+        "brightness",
+        "auto_brightness",
+        "auto_transition",
+        "app_time",
+        "transition",
+        "transition_time",
+        "text_color",
+        "temp_unit",
+        "scroll_speed",
+        "time_app.format",
+        "time_app.show_weekday",
+    ];
+
+    if !KEYS.contains(&key) {
+        return Err(AwtrixError::Validation(format!("Unknown setting key: {}", key)).into());
     }
 
-    Ok(())
+    Ok(match settings.flatten().get(key) {
+        Some(value) => format_value(value),
+        None => format!("Setting '{}' not set", key),
+    })
 }
 
 fn update_setting(settings: &mut Settings, key: &str, value: &str) -> Result<()> {
     match key {
         "brightness" => {
-            let val: u8 = value
-                .parse()
-                .map_err(|_| anyhow::anyhow!("Invalid brightness value. Must be 0-255"))?;
+            let val: u8 = value.parse().map_err(|_| {
+                AwtrixError::Validation("Invalid brightness value. Must be 0-255".to_string())
+            })?;
             settings.brightness = Some(val);
         }
         "auto_brightness" => {
-            let val: bool = value
-                .parse()
-                .map_err(|_| anyhow::anyhow!("Invalid boolean value. Use 'true' or 'false'"))?;
+            let val: bool = value.parse().map_err(|_| {
+                AwtrixError::Validation("Invalid boolean value. Use 'true' or 'false'".to_string())
+            })?;
             settings.auto_brightness = Some(val);
         }
         "auto_transition" => {
-            let val: bool = value
-                .parse()
-                .map_err(|_| anyhow::anyhow!("Invalid boolean value. Use 'true' or 'false'"))?;
+            let val: bool = value.parse().map_err(|_| {
+                AwtrixError::Validation("Invalid boolean value. Use 'true' or 'false'".to_string())
+            })?;
             settings.auto_transition = Some(val);
         }
         "app_time" => {
             let val: u32 = value.parse().map_err(|_| {
-                anyhow::anyhow!("Invalid app_time value. Must be a positive number")
+                AwtrixError::Validation(
+                    "Invalid app_time value. Must be a positive number".to_string(),
+                )
             })?;
             settings.app_time = Some(val);
         }
@@ -276,7 +574,9 @@ fn update_setting(settings: &mut Settings, key: &str, value: &str) -> Result<()>
         }
         "transition_time" => {
             let val: u32 = value.parse().map_err(|_| {
-                anyhow::anyhow!("Invalid transition_time value. Must be a positive number")
+                AwtrixError::Validation(
+                    "Invalid transition_time value. Must be a positive number".to_string(),
+                )
             })?;
             settings.transition_time = Some(val);
         }
@@ -289,16 +589,18 @@ fn update_setting(settings: &mut Settings, key: &str, value: &str) -> Result<()>
         }
         "scroll_speed" => {
             let val: u32 = value.parse().map_err(|_| {
-                anyhow::anyhow!("Invalid scroll_speed value. Must be a positive number")
+                AwtrixError::Validation(
+                    "Invalid scroll_speed value. Must be a positive number".to_string(),
+                )
             })?;
             settings.scroll_speed = Some(val);
         }
         "time_app.format" => {
-            let val: u8 = value
-                .parse()
-                .map_err(|_| anyhow::anyhow!("Invalid time format. Must be 0-5"))?;
+            let val: u8 = value.parse().map_err(|_| {
+                AwtrixError::Validation("Invalid time format. Must be 0-5".to_string())
+            })?;
             if val > 5 {
-                return Err(anyhow::anyhow!("Time format must be 0-5"));
+                return Err(AwtrixError::Validation("Time format must be 0-5".to_string()).into());
             }
             if settings.time_app.is_none() {
                 settings.time_app = Some(awtrix3::models::settings::TimeAppSettings {
@@ -314,9 +616,9 @@ fn update_setting(settings: &mut Settings, key: &str, value: &str) -> Result<()>
             }
         }
         "time_app.show_weekday" => {
-            let val: bool = value
-                .parse()
-                .map_err(|_| anyhow::anyhow!("Invalid boolean value. Use 'true' or 'false'"))?;
+            let val: bool = value.parse().map_err(|_| {
+                AwtrixError::Validation("Invalid boolean value. Use 'true' or 'false'".to_string())
+            })?;
             if settings.time_app.is_none() {
                 settings.time_app = Some(awtrix3::models::settings::TimeAppSettings {
                     format: None,
@@ -331,54 +633,87 @@ fn update_setting(settings: &mut Settings, key: &str, value: &str) -> Result<()>
             }
         }
         _ => {
-            return Err(anyhow::anyhow!(
+            return Err(AwtrixError::Validation(format!(
                 "Unknown setting key: {}. Use 'settings list' to see available settings",
                 key
-            ));
+            ))
+            .into());
         }
     }
 
     Ok(())
 }
 
-fn display_settings_documentation() {
-    println!("Available AWTRIX3 Settings:");
-    println!("");
-
-    println!("📱 Display Settings:");
-    println!("  brightness                  Matrix brightness (0-255)");
-    println!("  auto_brightness             Automatic brightness control (true/false)");
-    println!("  text_color                  Global text color (hex: #FF0000, rgb: 255,0,0, or name: red)");
-    println!("");
-
-    println!("🔄 App Management:");
-    println!("  auto_transition             Automatic app switching (true/false)");
-    println!("  app_time                    App display duration in seconds");
-    println!("  transition                  Transition effect name (slide, fade, etc.)");
-    println!("  transition_time             Transition duration in milliseconds");
-    println!("");
-
-    println!("🕐 Time App Settings:");
-    println!("  time_app.format             Time format (0-5)");
-    println!("  time_app.show_weekday       Show weekday (true/false)");
-    println!("  time_app.cal_header_color   Calendar header color");
-    println!("  time_app.cal_body_color     Calendar body color");
-    println!("  time_app.cal_text_color     Calendar text color");
-    println!("");
-
-    println!("📅 Date App Settings:");
-    println!("  date_app.enabled            Enable date app (true/false)");
-    println!("  date_app.format             Date format string");
-    println!("");
-
-    println!("⚙️  Other Settings:");
-    println!("  scroll_speed                Scroll speed percentage (0-200)");
-    println!("  temp_unit                   Temperature unit (C/F)");
-    println!("");
-
-    println!("Example usage:");
-    println!("  awtrix settings set brightness 150");
-    println!("  awtrix settings set text_color \"#FF0000\"");
-    println!("  awtrix settings set time_app.format 2");
-    println!("  awtrix settings set auto_transition true");
+/// Documented settings by section, as (key, description)
+const SETTINGS_DOCUMENTATION: &[(&str, &[(&str, &str)])] = &[
+    (
+        "📱 Display Settings",
+        &[
+            ("brightness", "Matrix brightness (0-255)"),
+            (
+                "auto_brightness",
+                "Automatic brightness control (true/false)",
+            ),
+            (
+                "text_color",
+                "Global text color (hex: #FF0000, rgb: 255,0,0, or name: red)",
+            ),
+        ],
+    ),
+    (
+        "🔄 App Management",
+        &[
+            ("auto_transition", "Automatic app switching (true/false)"),
+            ("app_time", "App display duration in seconds"),
+            ("transition", "Transition effect name (slide, fade, etc.)"),
+            ("transition_time", "Transition duration in milliseconds"),
+        ],
+    ),
+    (
+        "🕐 Time App Settings",
+        &[
+            ("time_app.format", "Time format (0-5)"),
+            ("time_app.show_weekday", "Show weekday (true/false)"),
+            ("time_app.cal_header_color", "Calendar header color"),
+            ("time_app.cal_body_color", "Calendar body color"),
+            ("time_app.cal_text_color", "Calendar text color"),
+        ],
+    ),
+    (
+        "📅 Date App Settings",
+        &[
+            ("date_app.enabled", "Enable date app (true/false)"),
+            ("date_app.format", "Date format string"),
+        ],
+    ),
+    (
+        "⚙️  Other Settings",
+        &[
+            ("scroll_speed", "Scroll speed percentage (0-200)"),
+            ("temp_unit", "Temperature unit (C/F)"),
+        ],
+    ),
+];
+
+fn display_settings_documentation() -> String {
+    let mut out = String::from("Available AWTRIX3 Settings:\n\n");
+
+    for (section, keys) in SETTINGS_DOCUMENTATION {
+        let _ = writeln!(out, "{}:", section);
+        for (key, description) in *keys {
+            let _ = writeln!(out, "  {:<27} {}", key, description);
+        }
+        out.push('\n');
+    }
+
+    out.push_str(
+        "Example usage:
+  awtrix settings set brightness 150
+  awtrix settings diff examples/settings/bedroom-mode.json
+  awtrix settings import examples/settings/bedroom-mode.json --only changed
+  awtrix settings set text_color \"#FF0000\"
+  awtrix settings set time_app.format 2
+  awtrix settings set auto_transition true",
+    );
+    out
 }
@@ -1,52 +1,75 @@
 use crate::cli::*;
+use crate::config::Config;
+use crate::output::CommandOutput;
 use anyhow::Result;
+use awtrix3::models::Priority;
+use awtrix3::AwtrixError;
+use chrono::Local;
 
-pub async fn execute(client: awtrix3::Client, command: SoundCommands) -> Result<()> {
-    match command {
+pub async fn execute(
+    client: awtrix3::Client,
+    command: SoundCommands,
+    device: &str,
+    config: &Config,
+) -> Result<CommandOutput> {
+    // Sounds count as normal priority under quiet hours
+    if !matches!(command, SoundCommands::List) {
+        if let Some((quiet, until)) = config.quiet_hours(device, Local::now().naive_local())? {
+            if quiet.min_priority > Priority::Normal {
+                return Ok(CommandOutput::message(format!(
+                    "Quiet hours on {} until {}; sound not played",
+                    device,
+                    until.format("%H:%M")
+                ))
+                .field("blocked", true));
+            }
+        }
+    }
+
+    let output = match command {
         SoundCommands::Play { sound, loop_sound } => {
             client.play_sound(&sound).await?;
 
             if loop_sound {
-                println!("Playing sound '{}' (looped)", sound);
-                println!("Note: Loop functionality depends on server implementation");
+                CommandOutput::message(format!(
+                    "Playing sound '{}' (looped)\nNote: Loop functionality depends on server implementation",
+                    sound
+                ))
             } else {
-                println!("Playing sound '{}'", sound);
+                CommandOutput::message(format!("Playing sound '{}'", sound))
             }
+            .field("sound", sound)
         }
         SoundCommands::Rtttl { rtttl } => {
             // Validate RTTTL format (basic check)
             if !rtttl.contains(':') {
-                return Err(anyhow::anyhow!(
-                    "Invalid RTTTL format. Expected format: 'name:d=4,o=5,b=140:notes'"
-                ));
+                return Err(AwtrixError::Validation(
+                    "Invalid RTTTL format. Expected format: 'name:d=4,o=5,b=140:notes'".to_string(),
+                )
+                .into());
             }
 
             client.play_rtttl(&rtttl).await?;
-            println!(
-                "Playing RTTTL: {}",
-                rtttl.split(':').next().unwrap_or("Unknown")
-            );
+            let name = rtttl.split(':').next().unwrap_or("Unknown");
+            CommandOutput::message(format!("Playing RTTTL: {}", name)).field("rtttl", name)
         }
         SoundCommands::R2d2 => {
             client.play_r2d2().await?;
-            println!("Playing R2D2 sound");
+            CommandOutput::message("Playing R2D2 sound")
         }
         SoundCommands::List => {
-            println!("Available sounds:");
-            println!("  Built-in sounds:");
-            println!("    - notification");
-            println!("    - success");
-            println!("    - error");
-            println!("    - warning");
-            println!("    - beep");
-            println!("  Special:");
-            println!("    - r2d2 (use 'sound r2d2' command)");
-            println!("    - rtttl (use 'sound rtttl' command)");
-            println!("");
-            println!("Note: Actual available sounds depend on device configuration.");
-            println!("Custom sounds can be uploaded to the device via web interface.");
+            let sounds = ["notification", "success", "error", "warning", "beep"];
+            let text = format!(
+                "Available sounds:\n  Built-in sounds:\n{}\n  Special:\n    - r2d2 (use 'sound r2d2' command)\n    - rtttl (use 'sound rtttl' command)\n\nNote: Actual available sounds depend on device configuration.\nCustom sounds can be uploaded to the device via web interface.",
+                sounds
+                    .iter()
+                    .map(|s| format!("    - {}", s))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+            CommandOutput::data(&serde_json::json!({ "sounds": sounds }))?.text(text)
         }
-    }
+    };
 
-    Ok(())
+    Ok(output)
 }
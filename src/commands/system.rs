@@ -1,21 +1,35 @@
 use crate::cli::*;
+use crate::output::CommandOutput;
 use anyhow::Result;
 use dialoguer::Confirm;
+use std::fmt::Write;
 use std::fs;
+use std::io::IsTerminal;
 use std::path::Path;
 
-pub async fn execute(client: awtrix3::Client, command: SystemCommands) -> Result<()> {
-    match command {
-        SystemCommands::Stats => {
+pub async fn execute(client: awtrix3::Client, command: SystemCommands) -> Result<CommandOutput> {
+    let output = match command {
+        SystemCommands::Stats {
+            record: Some(path),
+            interval,
+            ..
+        } => {
+            let path = match path {
+                Some(path) => path,
+                None => crate::commands::recording::default_path()?,
+            };
+            crate::commands::recording::record(client, path, interval).await?
+        }
+        SystemCommands::Stats { .. } => {
             let stats = client.get_stats().await?;
-            display_stats(&stats);
+            CommandOutput::data(&stats)?.text(display_stats(&stats))
         }
         SystemCommands::Reboot => {
             if confirm_destructive_action("reboot the device")? {
                 client.reboot().await?;
-                println!("Device reboot initiated");
+                CommandOutput::message("Device reboot initiated")
             } else {
-                println!("Reboot cancelled");
+                CommandOutput::message("Reboot cancelled").field("cancelled", true)
             }
         }
         SystemCommands::FactoryReset { confirm } => {
@@ -25,22 +39,24 @@ pub async fn execute(client: awtrix3::Client, command: SystemCommands) -> Result
                 )?
             {
                 client.factory_reset().await?;
-                println!("Factory reset initiated - device will restart with default settings");
+                CommandOutput::message(
+                    "Factory reset initiated - device will restart with default settings",
+                )
             } else {
-                println!("Factory reset cancelled");
+                CommandOutput::message("Factory reset cancelled").field("cancelled", true)
             }
         }
         SystemCommands::ResetSettings { confirm } => {
             if confirm || confirm_destructive_action("reset settings to defaults")? {
                 client.reset_settings().await?;
-                println!("Settings reset to defaults");
+                CommandOutput::message("Settings reset to defaults")
             } else {
-                println!("Settings reset cancelled");
+                CommandOutput::message("Settings reset cancelled").field("cancelled", true)
             }
         }
         SystemCommands::Save => {
             client.save_config().await?;
-            println!("Configuration saved");
+            CommandOutput::message("Configuration saved")
         }
         SystemCommands::Update { file } => {
             // Validate file exists
@@ -50,8 +66,11 @@ pub async fn execute(client: awtrix3::Client, command: SystemCommands) -> Result
 
             // TODO: Implement firmware upload
             // This requires multipart file upload which isn't implemented in the client yet
-            println!("Firmware update not yet implemented");
-            println!("Would upload: {}", file);
+            CommandOutput::message(format!(
+                "Firmware update not yet implemented\nWould upload: {}",
+                file
+            ))
+            .field("status", "unsupported")
         }
         SystemCommands::Backup { output } => {
             // Generate default filename if none provided
@@ -66,75 +85,111 @@ pub async fn execute(client: awtrix3::Client, command: SystemCommands) -> Result
             let backup_data = serde_json::to_string_pretty(&stats)?;
 
             fs::write(&filename, backup_data)?;
-            println!("Backup saved to: {}", filename);
-            println!(
-                "Note: Currently only saves device statistics. Full backup implementation pending."
-            );
+            CommandOutput::message(format!(
+                "Backup saved to: {}\nNote: Currently only saves device statistics. Full backup implementation pending.",
+                filename
+            ))
+            .field("file", filename)
         }
-    }
+    };
 
-    Ok(())
+    Ok(output)
 }
 
-fn display_stats(stats: &awtrix3::models::response::Stats) {
-    println!("Device Statistics:");
+fn display_stats(stats: &awtrix3::models::response::Stats) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Device Statistics:");
 
     // Basic info
-    println!(
-        "  Uptime: {} seconds ({:.1} hours)",
-        stats.uptime,
-        stats.uptime as f64 / 3600.0
-    );
-    println!("  WiFi Signal: {} dBm", stats.wifi_signal);
-    println!("  Free Memory: {} bytes", stats.heap);
-    println!("  Matrix: {}", if stats.matrix { "ON" } else { "OFF" });
+    if let Some(version) = &stats.version {
+        let _ = writeln!(out, "  Firmware: {}", version);
+    }
+    if let Some(uid) = &stats.uid {
+        let _ = writeln!(out, "  Device ID: {}", uid);
+    }
+    if let Some(ip) = &stats.ip {
+        let _ = writeln!(out, "  IP Address: {}", ip);
+    }
+    if let Some(uptime) = stats.uptime {
+        let _ = writeln!(
+            out,
+            "  Uptime: {} seconds ({:.1} hours)",
+            uptime,
+            uptime as f64 / 3600.0
+        );
+    }
+    if let Some(wifi_signal) = stats.wifi_signal {
+        let _ = writeln!(out, "  WiFi Signal: {} dBm", wifi_signal);
+    }
+    if let Some(ram) = stats.ram {
+        let _ = writeln!(out, "  Free Memory: {} bytes", ram);
+    }
+    if let Some(matrix) = stats.matrix {
+        let _ = writeln!(out, "  Matrix: {}", if matrix { "ON" } else { "OFF" });
+    }
+    if let Some(brightness) = stats.brightness {
+        let _ = writeln!(out, "  Brightness: {}", brightness);
+    }
+    if let Some(messages) = stats.messages {
+        let _ = writeln!(out, "  Messages: {}", messages);
+    }
 
     // Current app
     if let Some(app) = &stats.current_app {
-        println!("  Current App: {}", app);
+        let _ = writeln!(out, "  Current App: {}", app);
     }
 
     // Sensors
     if let Some(temp) = stats.temperature {
-        println!("  Temperature: {:.1}°C", temp);
+        let _ = writeln!(out, "  Temperature: {:.1}°C", temp);
     }
 
     if let Some(humidity) = stats.humidity {
-        println!("  Humidity: {:.1}%", humidity);
+        let _ = writeln!(out, "  Humidity: {:.1}%", humidity);
     }
 
-    if let Some(ldr) = stats.ldr {
-        println!("  Light Sensor (LDR): {}", ldr);
+    if let Some(ldr) = stats.ldr_raw {
+        let _ = writeln!(out, "  Light Sensor (LDR): {}", ldr);
     }
 
     if let Some(lux) = stats.lux {
-        println!("  Light Level: {:.1} lux", lux);
+        let _ = writeln!(out, "  Light Level: {:.1} lux", lux);
     }
 
     if let Some(battery) = stats.battery {
-        println!("  Battery: {}%", battery);
+        let _ = writeln!(out, "  Battery: {}%", battery);
     }
 
     // Indicators
-    if let Some(indicators) = &stats.indicators {
-        println!("  Indicators:");
-        println!(
+    if let Some(indicators) = stats.indicators() {
+        let _ = writeln!(out, "  Indicators:");
+        let _ = writeln!(
+            out,
             "    1: {}",
             if indicators.indicator1 { "ON" } else { "OFF" }
         );
-        println!(
+        let _ = writeln!(
+            out,
             "    2: {}",
             if indicators.indicator2 { "ON" } else { "OFF" }
         );
-        println!(
+        let _ = writeln!(
+            out,
             "    3: {}",
             if indicators.indicator3 { "ON" } else { "OFF" }
         );
     }
+
+    out.trim_end().to_string()
 }
 
 fn confirm_destructive_action(action: &str) -> Result<bool> {
-    println!("⚠️  WARNING: This will {}", action);
+    // Without a terminal there is nobody to ask, so refuse
+    if !std::io::stdin().is_terminal() {
+        return Ok(false);
+    }
+
+    eprintln!("⚠️  WARNING: This will {}", action);
     let confirmed = Confirm::new()
         .with_prompt("Are you sure you want to continue?")
         .default(false)
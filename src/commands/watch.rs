@@ -0,0 +1,329 @@
+use crate::config::Config;
+use crate::output::CommandOutput;
+use anyhow::Result;
+use awtrix3::models::response::Stats;
+use awtrix3::utils::format_duration;
+use awtrix3::{AwtrixError, ClientBuilder};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use tabled::builder::Builder;
+use tabled::settings::object::Rows;
+use tabled::settings::{Color, Modify, Style};
+use tokio::task::JoinSet;
+
+/// Result of polling one device's stats
+pub struct Sample {
+    /// Device name from the config, or the host if it is not configured
+    pub device: String,
+    pub stats: awtrix3::Result<Stats>,
+    pub latency: Duration,
+}
+
+/// Fetch stats from all devices at once, in the order given
+pub async fn poll(config: &Config, devices: &[String], timeout: Duration) -> Vec<Sample> {
+    let mut requests = JoinSet::new();
+
+    for (index, device) in devices.iter().enumerate() {
+        let device = device.clone();
+        let host = config.resolve_host(&device);
+
+        requests.spawn(async move {
+            let started = Instant::now();
+            let stats = match ClientBuilder::new().host(&host).timeout(timeout).build() {
+                Ok(client) => client.get_stats().await,
+                Err(e) => Err(e),
+            };
+            let sample = Sample {
+                device,
+                stats,
+                latency: started.elapsed(),
+            };
+            (index, sample)
+        });
+    }
+
+    let mut samples = Vec::with_capacity(devices.len());
+    while let Some(result) = requests.join_next().await {
+        if let Ok(indexed) = result {
+            samples.push(indexed);
+        }
+    }
+    samples.sort_by_key(|(index, _)| *index);
+    samples.into_iter().map(|(_, sample)| sample).collect()
+}
+
+/// The named devices, or every configured device when none are named
+pub fn select_devices(config: &Config, devices: Vec<String>) -> Result<Vec<String>> {
+    if !devices.is_empty() {
+        return Ok(devices);
+    }
+
+    let mut names: Vec<String> = config.devices.keys().cloned().collect();
+    if names.is_empty() {
+        return Err(AwtrixError::Config(
+            "No devices configured. Add one with 'awtrix device add'".to_string(),
+        )
+        .into());
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// One row of the dashboard
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Health {
+    pub device: String,
+    pub online: bool,
+    pub rssi: Option<i16>,
+    pub heap: Option<u32>,
+    pub temp: Option<f32>,
+    pub uptime: Option<u64>,
+    pub app: Option<String>,
+    pub latency_ms: u64,
+    pub alerts: Vec<String>,
+}
+
+/// What is remembered about a device between polls
+#[derive(Debug, Default)]
+struct History {
+    uptime: Option<u64>,
+    rebooted_at: Option<DateTime<Local>>,
+    seen_offline: bool,
+}
+
+/// Tracks devices across polls to spot regressions
+struct Tracker {
+    low_heap: u32,
+    history: HashMap<String, History>,
+}
+
+impl Tracker {
+    fn new(low_heap: u32) -> Self {
+        Self {
+            low_heap,
+            history: HashMap::new(),
+        }
+    }
+
+    fn update(&mut self, sample: Sample, now: DateTime<Local>) -> Health {
+        let history = self.history.entry(sample.device.clone()).or_default();
+        let latency_ms = sample.latency.as_millis() as u64;
+        let mut alerts = Vec::new();
+
+        let stats = match sample.stats {
+            Ok(stats) => stats,
+            Err(e) => {
+                history.seen_offline = true;
+                return Health {
+                    device: sample.device,
+                    online: false,
+                    rssi: None,
+                    heap: None,
+                    temp: None,
+                    uptime: None,
+                    app: None,
+                    latency_ms,
+                    alerts: vec![e.kind().to_string()],
+                };
+            }
+        };
+
+        // Uptime going backwards means the device restarted since the last poll
+        if let (Some(previous), Some(uptime)) = (history.uptime, stats.uptime) {
+            if uptime < previous {
+                history.rebooted_at = Some(now);
+            }
+        }
+        if stats.uptime.is_some() {
+            history.uptime = stats.uptime;
+        }
+
+        if let Some(at) = history.rebooted_at {
+            alerts.push(format!("rebooted {}", at.format("%H:%M:%S")));
+        }
+        if stats.ram.is_some_and(|heap| heap < self.low_heap) {
+            alerts.push("low heap".to_string());
+        }
+        if history.seen_offline {
+            alerts.push("was offline".to_string());
+        }
+
+        Health {
+            device: sample.device,
+            online: true,
+            rssi: stats.wifi_signal,
+            heap: stats.ram,
+            temp: stats.temperature,
+            uptime: stats.uptime,
+            app: stats.current_app,
+            latency_ms,
+            alerts,
+        }
+    }
+}
+
+pub async fn execute(
+    devices: Vec<String>,
+    interval: u64,
+    low_heap: u32,
+    once: bool,
+    config: &Config,
+) -> Result<CommandOutput> {
+    if interval == 0 {
+        return Err(
+            AwtrixError::Validation("Interval must be greater than 0 seconds".to_string()).into(),
+        );
+    }
+
+    let devices = select_devices(config, devices)?;
+    // A slow device must not hold up the next refresh
+    let timeout = Duration::from_secs(interval.min(5));
+    let mut tracker = Tracker::new(low_heap);
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => return Ok(CommandOutput::empty()),
+        }
+
+        let now = Local::now();
+        let rows: Vec<Health> = poll(config, &devices, timeout)
+            .await
+            .into_iter()
+            .map(|sample| tracker.update(sample, now))
+            .collect();
+
+        let output = CommandOutput::data(&serde_json::json!({ "devices": rows }))?.text(render(
+            &rows,
+            now,
+            (!once).then_some(interval),
+        ));
+        if once {
+            return Ok(output);
+        }
+
+        let rendered = output.render(crate::output::format())?;
+        let mut stdout = std::io::stdout().lock();
+        if crate::output::is_human() {
+            // Redraw in place rather than scrolling
+            write!(stdout, "\x1b[2J\x1b[H")?;
+        }
+        writeln!(stdout, "{}", rendered)?;
+        stdout.flush()?;
+    }
+}
+
+fn render(rows: &[Health], now: DateTime<Local>, refresh: Option<u64>) -> String {
+    let mut builder = Builder::default();
+    builder.push_record([
+        "Device", "Status", "RSSI", "Heap", "Temp", "Uptime", "App", "Latency", "Alerts",
+    ]);
+
+    let dash = || "-".to_string();
+    for row in rows {
+        builder.push_record([
+            row.device.clone(),
+            if row.online { "online" } else { "offline" }.to_string(),
+            row.rssi.map_or_else(dash, |rssi| format!("{} dBm", rssi)),
+            row.heap
+                .map_or_else(dash, |heap| format!("{} KB", heap / 1024)),
+            row.temp.map_or_else(dash, |temp| format!("{:.1} °C", temp)),
+            row.uptime.map_or_else(dash, format_duration),
+            row.app.clone().unwrap_or_else(dash),
+            format!("{} ms", row.latency_ms),
+            row.alerts.join(", "),
+        ]);
+    }
+
+    let mut table = builder.build();
+    table.with(Style::rounded());
+    if colored::control::SHOULD_COLORIZE.should_colorize() {
+        for (index, row) in rows.iter().enumerate() {
+            let color = if !row.online {
+                Color::FG_RED
+            } else if !row.alerts.is_empty() {
+                Color::FG_YELLOW
+            } else {
+                continue;
+            };
+            // Row 0 is the header
+            table.with(Modify::new(Rows::single(index + 1)).with(color));
+        }
+    }
+
+    let online = rows.iter().filter(|row| row.online).count();
+    let mut header = format!(
+        "{} - {}/{} online",
+        now.format("%H:%M:%S"),
+        online,
+        rows.len()
+    );
+    if let Some(interval) = refresh {
+        header.push_str(&format!(
+            ", refreshing every {}s (Ctrl+C to stop)",
+            interval
+        ));
+    }
+    format!("{}\n{}", header, table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(uptime: u64, heap: u32) -> Sample {
+        Sample {
+            device: "lobby".to_string(),
+            stats: Ok(Stats {
+                uptime: Some(uptime),
+                ram: Some(heap),
+                ..Stats::default()
+            }),
+            latency: Duration::from_millis(42),
+        }
+    }
+
+    #[test]
+    fn test_detects_reboot_and_low_heap() {
+        let mut tracker = Tracker::new(20_000);
+        let now = Local::now();
+
+        let health = tracker.update(sample(3600, 80_000), now);
+        assert!(health.online);
+        assert_eq!(health.latency_ms, 42);
+        assert!(health.alerts.is_empty());
+
+        let health = tracker.update(sample(10, 10_000), now);
+        assert_eq!(health.alerts.len(), 2);
+        assert!(health.alerts[0].starts_with("rebooted"));
+        assert_eq!(health.alerts[1], "low heap");
+
+        // The reboot stays flagged after uptime grows again
+        let health = tracker.update(sample(70, 80_000), now);
+        assert_eq!(health.alerts.len(), 1);
+    }
+
+    #[test]
+    fn test_offline_device() {
+        let mut tracker = Tracker::new(20_000);
+        let offline = Sample {
+            device: "lobby".to_string(),
+            stats: Err(AwtrixError::DeviceUnreachable {
+                device: "lobby".to_string(),
+                host: "192.168.1.20".to_string(),
+            }),
+            latency: Duration::from_secs(5),
+        };
+
+        let health = tracker.update(offline, Local::now());
+        assert!(!health.online);
+        assert_eq!(health.alerts, vec!["unreachable"]);
+
+        let health = tracker.update(sample(60, 80_000), Local::now());
+        assert_eq!(health.alerts, vec!["was offline"]);
+    }
+}
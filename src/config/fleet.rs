@@ -0,0 +1,545 @@
+use super::Config;
+use crate::models::{Color, CustomApp, SettingChange, Settings};
+use crate::Client;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Remote directory for icons on the device filesystem
+const ICONS_DIR: &str = "/ICONS";
+
+/// Remote directory for melodies on the device filesystem
+const MELODIES_DIR: &str = "/MELODIES";
+
+/// Declarative description of the desired state of a fleet of devices
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Fleet {
+    /// Named groups of devices
+    #[serde(default)]
+    pub groups: BTreeMap<String, Vec<String>>,
+
+    /// Desired state per device or group name
+    #[serde(default)]
+    pub devices: BTreeMap<String, DeviceState>,
+
+    /// Directory that relative icon and melody paths are resolved against
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+}
+
+/// Desired state of a single device
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceState {
+    /// Device settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<Settings>,
+
+    /// App loop order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<String>,
+
+    /// Custom apps by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom_apps: BTreeMap<String, CustomApp>,
+
+    /// Indicator colors by number (1-3), or "off"
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub indicators: BTreeMap<String, String>,
+
+    /// Icons to install, file name on the device to local path
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub icons: BTreeMap<String, PathBuf>,
+
+    /// Melodies to install, name on the device to local path
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub melodies: BTreeMap<String, PathBuf>,
+}
+
+/// A single change needed to reconcile a device
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Update settings that differ from the device
+    UpdateSettings {
+        changes: Vec<SettingChange>,
+        payload: Settings,
+    },
+
+    /// Reorder the app loop
+    ReorderApps {
+        current: Vec<String>,
+        desired: Vec<String>,
+    },
+
+    /// Create or update a custom app
+    PutCustomApp {
+        name: String,
+        app: CustomApp,
+        exists: bool,
+    },
+
+    /// Set or clear an indicator
+    SetIndicator { indicator: u8, color: Option<Color> },
+
+    /// Upload a missing file (icon or melody)
+    UploadFile { remote: String, local: PathBuf },
+}
+
+impl Action {
+    /// Whether the action is applied every time because the device does not
+    /// report the state it would change (custom app content, indicator colors)
+    pub fn always_applied(&self) -> bool {
+        matches!(
+            self,
+            Action::PutCustomApp { .. } | Action::SetIndicator { .. }
+        )
+    }
+}
+
+/// Planned changes for one device
+#[derive(Debug, Clone)]
+pub struct DevicePlan {
+    /// Device name from the fleet file
+    pub device: String,
+
+    /// Resolved host
+    pub host: String,
+
+    /// Changes to apply, in order
+    pub actions: Vec<Action>,
+}
+
+impl Fleet {
+    /// Load a fleet file, choosing TOML or YAML by extension
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read fleet file {}: {}", path.display(), e))?;
+
+        let mut fleet: Fleet = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&content)?,
+            Some("json") => serde_json::from_str(&content)?,
+            _ => serde_yaml::from_str(&content)?,
+        };
+        fleet.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(fleet)
+    }
+
+    /// Resolve groups into the desired state of each individual device.
+    ///
+    /// Group entries are applied first, so device entries override them.
+    pub fn resolve(&self) -> Result<BTreeMap<String, DeviceState>> {
+        let mut resolved: BTreeMap<String, DeviceState> = BTreeMap::new();
+
+        let (group_entries, device_entries): (Vec<_>, Vec<_>) = self
+            .devices
+            .iter()
+            .partition(|(name, _)| self.groups.contains_key(*name));
+
+        for (name, state) in group_entries.into_iter().chain(device_entries) {
+            let members = match self.groups.get(name) {
+                Some(members) => members.clone(),
+                None => vec![name.clone()],
+            };
+
+            for member in members {
+                resolved.entry(member).or_default().merge(state);
+            }
+        }
+
+        for state in resolved.values_mut() {
+            for path in state.icons.values_mut().chain(state.melodies.values_mut()) {
+                if path.is_relative() {
+                    *path = self.base_dir.join(&*path);
+                }
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// Compare the desired state with the live devices.
+    ///
+    /// Only devices named in `only` are planned, unless it is empty.
+    pub async fn plan<S: AsRef<str>>(
+        &self,
+        config: &Config,
+        only: &[S],
+    ) -> Result<Vec<DevicePlan>> {
+        let mut plans = Vec::new();
+
+        for (device, state) in self.resolve()? {
+            if !only.is_empty() && !only.iter().any(|name| name.as_ref() == device) {
+                continue;
+            }
+
+            let host = config.resolve_host(&device);
+            let client = Client::new(&host)?;
+            let actions = state
+                .plan(&client)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}: {}", device, e))?;
+
+            plans.push(DevicePlan {
+                device,
+                host,
+                actions,
+            });
+        }
+
+        Ok(plans)
+    }
+}
+
+impl DeviceState {
+    /// Overlay another state onto this one
+    pub fn merge(&mut self, other: &DeviceState) {
+        if let Some(settings) = &other.settings {
+            self.settings
+                .get_or_insert_with(Settings::new)
+                .merge(settings);
+        }
+        if !other.apps.is_empty() {
+            self.apps = other.apps.clone();
+        }
+        self.custom_apps.extend(other.custom_apps.clone());
+        self.indicators.extend(other.indicators.clone());
+        self.icons.extend(other.icons.clone());
+        self.melodies.extend(other.melodies.clone());
+    }
+
+    /// Parse the indicator entries into indicator numbers and colors
+    pub fn parsed_indicators(&self) -> Result<Vec<(u8, Option<Color>)>> {
+        self.indicators
+            .iter()
+            .map(|(indicator, color)| {
+                let number: u8 = indicator
+                    .parse()
+                    .ok()
+                    .filter(|n| (1..=3).contains(n))
+                    .ok_or_else(|| anyhow::anyhow!("Invalid indicator '{}'. Use 1-3", indicator))?;
+
+                let color = if color.eq_ignore_ascii_case("off") {
+                    None
+                } else {
+                    Some(Color::from_hex(color)?)
+                };
+
+                Ok((number, color))
+            })
+            .collect()
+    }
+
+    /// Compute the actions needed to bring a device to this state
+    pub async fn plan(&self, client: &Client) -> Result<Vec<Action>> {
+        let mut actions = Vec::new();
+
+        if let Some(desired) = &self.settings {
+            let current = client.get_settings().await?;
+            let changes: Vec<SettingChange> = current
+                .diff(desired)
+                .into_iter()
+                .filter(|change| change.desired.is_some())
+                .collect();
+
+            if !changes.is_empty() {
+                actions.push(Action::UpdateSettings {
+                    changes,
+                    payload: current.changes_to(desired),
+                });
+            }
+        }
+
+        let current_apps: Vec<String> = if self.apps.is_empty() && self.custom_apps.is_empty() {
+            Vec::new()
+        } else {
+            client
+                .get_apps()
+                .await?
+                .apps
+                .into_iter()
+                .map(|app| app.name)
+                .collect()
+        };
+
+        // The device does not report custom app content, so they are always applied
+        for (name, app) in &self.custom_apps {
+            actions.push(Action::PutCustomApp {
+                name: name.clone(),
+                app: app.clone(),
+                exists: current_apps.contains(name),
+            });
+        }
+
+        if !self.apps.is_empty() && self.apps != current_apps {
+            actions.push(Action::ReorderApps {
+                current: current_apps.clone(),
+                desired: self.apps.clone(),
+            });
+        }
+
+        // Indicator colors are not reported either, so they are always applied
+        for (indicator, color) in self.parsed_indicators()? {
+            actions.push(Action::SetIndicator { indicator, color });
+        }
+
+        actions.extend(plan_uploads(client, ICONS_DIR, &self.icons, true).await?);
+        actions.extend(plan_uploads(client, MELODIES_DIR, &self.melodies, false).await?);
+
+        Ok(actions)
+    }
+}
+
+/// Plan uploads for files missing from a device directory
+async fn plan_uploads(
+    client: &Client,
+    dir: &str,
+    files: &BTreeMap<String, PathBuf>,
+    keep_extension: bool,
+) -> Result<Vec<Action>> {
+    if files.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let installed = client.list_files(dir).await?;
+    let mut actions = Vec::new();
+
+    for (name, local) in files {
+        // Icons keep their image extension, melodies are stored as .txt
+        let file_name = if keep_extension {
+            match local.extension().and_then(|e| e.to_str()) {
+                Some(ext) if !name.contains('.') => format!("{}.{}", name, ext),
+                _ => name.clone(),
+            }
+        } else if name.ends_with(".txt") {
+            name.clone()
+        } else {
+            format!("{}.txt", name)
+        };
+
+        if !installed.contains(&file_name) {
+            actions.push(Action::UploadFile {
+                remote: format!("{}/{}", dir, file_name),
+                local: local.clone(),
+            });
+        }
+    }
+
+    Ok(actions)
+}
+
+impl DevicePlan {
+    /// Number of actions that change something known to differ on the device
+    pub fn changes(&self) -> usize {
+        self.actions
+            .iter()
+            .filter(|action| !action.always_applied())
+            .count()
+    }
+
+    /// Apply the planned actions in order
+    pub async fn apply(&self, save: bool) -> Result<()> {
+        let client = Client::new(&self.host)?;
+
+        for action in &self.actions {
+            match action {
+                Action::UpdateSettings { payload, .. } => {
+                    client
+                        .settings_transaction()
+                        .update(payload.clone())
+                        .save(save)
+                        .commit()
+                        .await?;
+                }
+                Action::ReorderApps { desired, .. } => client.reorder_apps(desired).await?,
+                Action::PutCustomApp { name, app, .. } => {
+                    client.create_custom_app(name, app).await?
+                }
+                Action::SetIndicator { indicator, color } => {
+                    client.set_indicator(*indicator, *color).await?
+                }
+                Action::UploadFile { remote, local } => {
+                    let contents = std::fs::read(local)
+                        .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", local.display(), e))?;
+                    client.upload_file(remote, contents).await?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const FLEET_YAML: &str = r##"
+groups:
+  office: [lobby, meeting]
+
+devices:
+  office:
+    settings:
+      brightness: 80
+      time_app:
+        format: 1
+    indicators:
+      "1": "#FF0000"
+  lobby:
+    settings:
+      brightness: 150
+    apps: [Time, Date]
+    icons:
+      "1234": icons/1234.gif
+"##;
+
+    #[test]
+    fn test_resolve_groups() {
+        let mut fleet: Fleet = serde_yaml::from_str(FLEET_YAML).unwrap();
+        fleet.base_dir = PathBuf::from("fleet");
+
+        let resolved = fleet.resolve().unwrap();
+        assert_eq!(resolved.keys().collect::<Vec<_>>(), ["lobby", "meeting"]);
+
+        // Device entries override group entries
+        let lobby = &resolved["lobby"];
+        let settings = lobby.settings.as_ref().unwrap();
+        assert_eq!(settings.brightness, Some(150));
+        assert_eq!(settings.time_app.as_ref().unwrap().format, Some(1));
+        assert_eq!(lobby.apps, ["Time", "Date"]);
+        assert_eq!(lobby.icons["1234"], PathBuf::from("fleet/icons/1234.gif"));
+
+        let meeting = &resolved["meeting"];
+        assert_eq!(meeting.settings.as_ref().unwrap().brightness, Some(80));
+        assert_eq!(
+            meeting.parsed_indicators().unwrap(),
+            [(1, Some(Color::RED))]
+        );
+    }
+
+    #[test]
+    fn test_toml_fleet() {
+        let fleet: Fleet = toml::from_str(
+            r##"
+            [devices.lobby.settings]
+            brightness = 100
+
+            [devices.lobby.indicators]
+            2 = "off"
+            "##,
+        )
+        .unwrap();
+
+        let resolved = fleet.resolve().unwrap();
+        assert_eq!(resolved["lobby"].parsed_indicators().unwrap(), [(2, None)]);
+    }
+
+    #[test]
+    fn test_invalid_indicator() {
+        let mut state = DeviceState::default();
+        state
+            .indicators
+            .insert("4".to_string(), "#FF0000".to_string());
+        assert!(state.parsed_indicators().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_plan_against_device() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/settings"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"brightness": 150, "appTime": 10})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/loop"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "apps": [{"name": "Date"}, {"name": "Time"}],
+                "current": "Time"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/list"))
+            .and(query_param("dir", "/ICONS"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!([{"type": "file", "name": "999.gif"}])),
+            )
+            .mount(&server)
+            .await;
+
+        let state: DeviceState = serde_yaml::from_str(
+            r#"
+settings:
+  brightness: 150
+  app_time: 15
+apps: [Time, Date]
+icons:
+  "1234": 1234.gif
+  "999": 999.gif
+"#,
+        )
+        .unwrap();
+
+        let client = Client::new(server.uri()).unwrap();
+        let actions = state.plan(&client).await.unwrap();
+
+        assert_eq!(actions.len(), 3);
+        match &actions[0] {
+            Action::UpdateSettings { changes, payload } => {
+                assert_eq!(changes.len(), 1);
+                assert_eq!(changes[0].key, "app_time");
+                assert_eq!(payload.brightness, None);
+                assert_eq!(payload.app_time, Some(15));
+            }
+            other => panic!("unexpected action: {:?}", other),
+        }
+        assert!(matches!(&actions[1], Action::ReorderApps { .. }));
+        match &actions[2] {
+            Action::UploadFile { remote, .. } => assert_eq!(remote, "/ICONS/1234.gif"),
+            other => panic!("unexpected action: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unreported_state_is_not_a_change() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/loop"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "apps": [{"name": "Time"}, {"name": "stocks"}],
+                "current": "Time"
+            })))
+            .mount(&server)
+            .await;
+
+        let state: DeviceState = serde_yaml::from_str(
+            r#"
+custom_apps:
+  stocks:
+    text: AAPL 190
+indicators:
+  "1": "off"
+"#,
+        )
+        .unwrap();
+
+        let client = Client::new(server.uri()).unwrap();
+        let plan = DevicePlan {
+            device: "lobby".to_string(),
+            host: server.uri(),
+            actions: state.plan(&client).await.unwrap(),
+        };
+
+        assert_eq!(plan.actions.len(), 2);
+        assert!(plan.actions.iter().all(Action::always_applied));
+        assert_eq!(plan.changes(), 0);
+    }
+}
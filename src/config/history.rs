@@ -0,0 +1,231 @@
+use super::jsonl::{self, Id};
+use crate::error::AwtrixError;
+use crate::models::{Notification, Priority};
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset, Local, SubsecRound};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A notification that was sent, or attempted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub timestamp: DateTime<FixedOffset>,
+    /// Device name or host the notification was sent to
+    pub device: String,
+    /// What sent it: `notify`, `resend`, `serve`, ...
+    pub source: String,
+    pub notification: Notification,
+    /// Priority it was sent with, used again when it is resent
+    #[serde(default)]
+    pub priority: Priority,
+    /// Why delivery failed, if it did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Log of sent notifications, one JSON object per line in the config directory
+///
+/// Entries are appended; the file is trimmed back to the limit only once it
+/// has grown a quarter past it. Writers from several processes are
+/// serialized with a file lock, so ids stay unique.
+#[derive(Debug, Clone)]
+pub struct NotificationLog {
+    path: PathBuf,
+    limit: usize,
+}
+
+impl NotificationLog {
+    /// Open the log in the default config directory, keeping at most `limit` entries
+    pub fn open(limit: usize) -> Result<Self> {
+        Ok(Self::new(
+            super::get_config_dir()?.join("history.jsonl"),
+            limit,
+        ))
+    }
+
+    /// Open the log at `path`, keeping at most `limit` entries
+    pub fn new<P: AsRef<Path>>(path: P, limit: usize) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            limit,
+        }
+    }
+
+    /// Append an entry for a delivery attempt; returns `None` if the log is off
+    pub fn record(
+        &self,
+        device: &str,
+        source: &str,
+        notification: &Notification,
+        priority: Priority,
+        error: Option<String>,
+    ) -> Result<Option<HistoryEntry>> {
+        if self.limit == 0 {
+            return Ok(None);
+        }
+        let _lock = jsonl::lock(&self.path)?;
+
+        let last = match jsonl::last::<Id>(&self.path)? {
+            Some(last) => Some(last.id),
+            None => self.read()?.last().map(|entry| entry.id),
+        };
+        let entry = HistoryEntry {
+            id: last.map_or(1, |last| last + 1),
+            timestamp: Local::now().fixed_offset().trunc_subsecs(0),
+            device: device.to_string(),
+            source: source.to_string(),
+            notification: notification.clone(),
+            priority,
+            error,
+        };
+        jsonl::append(&self.path, &entry)?;
+
+        let first = jsonl::first::<Id>(&self.path)?;
+        let slack = (self.limit / 4).max(1) as u64;
+        if first.map_or(true, |first| {
+            entry.id - first.id >= self.limit as u64 + slack
+        }) {
+            let mut entries = self.read()?;
+            entries.drain(..entries.len().saturating_sub(self.limit));
+            jsonl::rewrite(&self.path, &entries)?;
+        }
+
+        Ok(Some(entry))
+    }
+
+    /// The last `limit` entries, oldest first; unreadable lines are skipped
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        let mut entries = self.read()?;
+        entries.drain(..entries.len().saturating_sub(self.limit));
+        Ok(entries)
+    }
+
+    /// Every entry in the file
+    fn read(&self) -> Result<Vec<HistoryEntry>> {
+        jsonl::read(&self.path, "history")
+    }
+
+    /// Look up an entry by id
+    pub fn get(&self, id: u64) -> Result<HistoryEntry> {
+        self.entries()?
+            .into_iter()
+            .find(|entry| entry.id == id)
+            .ok_or_else(|| {
+                AwtrixError::Validation(format!(
+                    "No history entry #{}. Use 'history list' to list entries",
+                    id
+                ))
+                .into()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Notification {
+        Notification::builder().text(text).build()
+    }
+
+    #[test]
+    fn test_record_and_get() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = NotificationLog::new(dir.path().join("history.jsonl"), 10);
+
+        let first = log
+            .record("lobby", "notify", &text("one"), Priority::Normal, None)
+            .unwrap();
+        let second = log
+            .record(
+                "kitchen",
+                "serve",
+                &text("two"),
+                Priority::Critical,
+                Some("timed out".into()),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.unwrap().id, 1);
+        assert_eq!(second.id, 2);
+
+        let entries = log.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        let found = log.get(2).unwrap();
+        assert_eq!(found.device, "kitchen");
+        assert_eq!(found.error, second.error);
+        assert_eq!(found.priority, Priority::Critical);
+        assert_eq!(found.notification.text.as_deref(), Some("two"));
+        assert!(log.get(3).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_records_get_unique_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = NotificationLog::new(dir.path().join("history.jsonl"), 100);
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                // Separate handles lock like separate processes would
+                let log = log.clone();
+                scope.spawn(move || {
+                    for i in 0..10 {
+                        log.record(
+                            "lobby",
+                            "notify",
+                            &text(&i.to_string()),
+                            Priority::Normal,
+                            None,
+                        )
+                        .unwrap();
+                    }
+                });
+            }
+        });
+
+        let ids: Vec<u64> = log.entries().unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, (1..=40).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = NotificationLog::new(dir.path().join("history.jsonl"), 3);
+        for i in 0..5 {
+            log.record(
+                "lobby",
+                "notify",
+                &text(&i.to_string()),
+                Priority::Normal,
+                None,
+            )
+            .unwrap();
+        }
+
+        let ids: Vec<u64> = log.entries().unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![3, 4, 5]);
+
+        // The file is trimmed once it is past the limit by more than the slack
+        for i in 5..20 {
+            log.record(
+                "lobby",
+                "notify",
+                &text(&i.to_string()),
+                Priority::Normal,
+                None,
+            )
+            .unwrap();
+        }
+        assert!(log.read().unwrap().len() <= 4);
+        let ids: Vec<u64> = log.entries().unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![18, 19, 20]);
+
+        let off = NotificationLog::new(dir.path().join("off.jsonl"), 0);
+        assert!(off
+            .record("lobby", "notify", &text("x"), Priority::Normal, None)
+            .unwrap()
+            .is_none());
+        assert!(off.entries().unwrap().is_empty());
+    }
+}
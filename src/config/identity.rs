@@ -0,0 +1,217 @@
+use super::{get_config_dir, Config};
+use crate::client::DeviceAddress;
+use crate::utils::discovery;
+use anyhow::Result;
+use chrono::{DateTime, SubsecRound, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long to look for a device that moved
+const LOCATE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long a lookup is trusted before a device is looked for again
+const LOCATE_TTL: TimeDelta = TimeDelta::minutes(5);
+
+/// Where configured devices were last found, kept in `device-cache.json` in
+/// the config directory so that `config.toml` is never rewritten behind the
+/// user's back
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceCache {
+    #[serde(flatten)]
+    pub devices: HashMap<String, CachedDevice>,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+/// Last lookup of one configured device
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CachedDevice {
+    /// When the device was last looked for
+    pub located_at: Option<DateTime<Utc>>,
+
+    /// Address the device was found at, if it moved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<DeviceAddress>,
+
+    /// Configured address it moved from; the entry is ignored once the
+    /// config points somewhere else
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moved_from: Option<DeviceAddress>,
+}
+
+impl DeviceCache {
+    /// Open the cache in the config directory
+    pub fn open() -> Result<Self> {
+        Self::load(get_config_dir()?.join("device-cache.json"))
+    }
+
+    /// Open a cache file, starting empty if it does not exist or is unreadable
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let mut cache: DeviceCache = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring invalid device cache {}: {}", path.display(), e);
+                DeviceCache::default()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => DeviceCache::default(),
+            Err(e) => return Err(e.into()),
+        };
+        cache.path = path;
+        Ok(cache)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Address a configured device was last found at, if it moved away from
+    /// its configured host
+    pub fn moved_host(&self, name: &str) -> Option<&DeviceAddress> {
+        let device = self.devices.get(name)?;
+        let cached = self.cache.devices.get(name)?;
+        (cached.moved_from.as_ref() == Some(&device.host))
+            .then_some(cached.host.as_ref())
+            .flatten()
+    }
+
+    /// Look for a configured device that stopped answering, by its `uid`
+    ///
+    /// Meant to be called after a request failed as unreachable. Runs mDNS and
+    /// a scan of the last known /24 at most once per device every few minutes;
+    /// the result is kept in the device cache, not in `config.toml`. Returns
+    /// the new address if the device moved.
+    pub async fn relocate_device(&mut self, name: &str) -> Result<Option<DeviceAddress>> {
+        let Some(device) = self.devices.get(name) else {
+            return Ok(None);
+        };
+        // Devices behind a proxy or TLS are not found by discovery
+        let Some(uid) = device.uid.clone().filter(|_| device.host.is_direct()) else {
+            return Ok(None);
+        };
+
+        let now = Utc::now().trunc_subsecs(0);
+        let cached = self.cache.devices.get(name).cloned().unwrap_or_default();
+        if cached
+            .located_at
+            .is_some_and(|located_at| now - located_at < LOCATE_TTL)
+        {
+            return Ok(None);
+        }
+
+        let configured = device.host.clone();
+        let current = self.moved_host(name).unwrap_or(&configured).clone();
+        let found = discovery::locate(&uid, Some(current.host()), LOCATE_TIMEOUT).await?;
+
+        let moved = match found {
+            Some(found) => Some(found.address().parse::<DeviceAddress>()?),
+            None => None,
+        }
+        .filter(|host| *host != current);
+
+        let entry = match &moved {
+            Some(host) => {
+                tracing::info!("Device '{}' moved from {} to {}", name, current, host);
+                CachedDevice {
+                    located_at: Some(now),
+                    host: Some(host.clone()),
+                    moved_from: Some(configured),
+                }
+            }
+            None => CachedDevice {
+                located_at: Some(now),
+                ..cached
+            },
+        };
+        self.cache.devices.insert(name.to_string(), entry);
+        self.cache.save()?;
+
+        Ok(moved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DeviceConfig;
+
+    fn config_for(host: &str, cache: DeviceCache) -> Config {
+        let mut config = Config {
+            cache,
+            ..Config::default()
+        };
+        config.devices.insert(
+            "lobby".to_string(),
+            DeviceConfig {
+                host: host.parse().unwrap(),
+                uid: Some("awtrix_ab12cd".to_string()),
+                name: "Lobby".to_string(),
+                timeout: 30,
+                retries: 3,
+            },
+        );
+        config
+    }
+
+    fn moved(from: &str, to: &str, located_at: DateTime<Utc>) -> DeviceCache {
+        let mut cache = DeviceCache::default();
+        cache.devices.insert(
+            "lobby".to_string(),
+            CachedDevice {
+                located_at: Some(located_at),
+                host: Some(to.parse().unwrap()),
+                moved_from: Some(from.parse().unwrap()),
+            },
+        );
+        cache
+    }
+
+    #[test]
+    fn test_moved_host() {
+        let cache = moved("192.168.1.20", "192.168.1.57", Utc::now());
+
+        let config = config_for("192.168.1.20", cache.clone());
+        assert_eq!(config.resolve_host("lobby"), "192.168.1.57");
+
+        // A host edited in the config wins over the cache
+        let config = config_for("10.0.0.5", cache);
+        assert_eq!(config.resolve_host("lobby"), "10.0.0.5");
+    }
+
+    #[tokio::test]
+    async fn test_recent_lookup_is_not_repeated() {
+        let cache = moved("192.168.1.20", "192.168.1.57", Utc::now());
+        let mut config = config_for("192.168.1.20", cache);
+
+        // Within the TTL nothing is looked up, so nothing changes
+        assert_eq!(config.relocate_device("lobby").await.unwrap(), None);
+        assert_eq!(config.resolve_host("lobby"), "192.168.1.57");
+    }
+
+    #[tokio::test]
+    async fn test_ignores_unknown_names() {
+        let mut config = Config::default();
+        assert_eq!(config.relocate_device("192.168.1.20").await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("device-cache.json");
+
+        let mut cache = DeviceCache::load(path.clone()).unwrap();
+        assert!(cache.devices.is_empty());
+        cache.devices = moved("192.168.1.20", "192.168.1.57", Utc::now()).devices;
+        cache.save().unwrap();
+
+        let loaded = DeviceCache::load(path).unwrap();
+        assert_eq!(loaded.devices, cache.devices);
+    }
+}
@@ -0,0 +1,170 @@
+//! Files of one JSON object per line, shared between processes
+//!
+//! Writers hold an exclusive lock on a `.lock` file next to the data file for
+//! the whole read-modify-write, append single lines and replace the file by
+//! renaming a temporary copy, so readers never see it half rewritten.
+
+use anyhow::Result;
+use fs2::FileExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// How much of the end of a file is read at a time when looking for its last line
+const CHUNK: u64 = 4096;
+
+/// The `id` of an entry, without parsing the rest of it
+#[derive(Debug, Deserialize)]
+pub(super) struct Id {
+    pub id: u64,
+}
+
+/// Exclusive lock on a file, released when dropped
+pub(super) struct Lock {
+    _file: File,
+}
+
+/// Lock `path` against writers in this and other processes, creating its
+/// directory if needed
+pub(super) fn lock(path: &Path) -> Result<Lock> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling(path, ".lock"))?;
+    file.lock_exclusive()?;
+    Ok(Lock { _file: file })
+}
+
+/// All entries, oldest first; unreadable lines are skipped
+pub(super) fn read<T: DeserializeOwned>(path: &Path, what: &str) -> Result<Vec<T>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                tracing::warn!("Skipping unreadable {} entry: {}", what, e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// First entry, if the file has one and it is readable
+pub(super) fn first<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            return Ok(serde_json::from_str(&line).ok());
+        }
+    }
+    Ok(None)
+}
+
+/// Last entry, if the file has one and it is readable; only the end of the
+/// file is read
+pub(super) fn last<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut end = file.metadata()?.len();
+    let mut tail = Vec::new();
+    while end > 0 {
+        let start = end.saturating_sub(CHUNK);
+        let mut chunk = vec![0; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+        end = start;
+
+        // Stop once a newline precedes the last line
+        let content = tail.iter().rposition(|b| !b.is_ascii_whitespace());
+        if content.is_some_and(|content| tail[..content].contains(&b'\n')) {
+            break;
+        }
+    }
+
+    let tail = String::from_utf8_lossy(&tail);
+    let line = tail.trim_end().rsplit('\n').next().unwrap_or_default();
+    Ok(serde_json::from_str(line).ok())
+}
+
+/// Append one entry
+pub(super) fn append<T: Serialize>(path: &Path, entry: &T) -> Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Replace the file with `entries`
+pub(super) fn rewrite<T: Serialize>(path: &Path, entries: &[T]) -> Result<()> {
+    let mut content = String::new();
+    for entry in entries {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+
+    let temp = sibling(path, ".tmp");
+    let mut file = File::create(&temp)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// `path` with `suffix` added to its file name
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_and_last() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("entries.jsonl");
+        assert!(last::<Id>(&path).unwrap().is_none());
+
+        let long = "x".repeat(CHUNK as usize * 2);
+        for id in 1..=3 {
+            append(&path, &serde_json::json!({ "id": id, "text": long })).unwrap();
+        }
+        assert_eq!(first::<Id>(&path).unwrap().unwrap().id, 1);
+        assert_eq!(last::<Id>(&path).unwrap().unwrap().id, 3);
+
+        rewrite(&path, &[serde_json::json!({ "id": 7 })]).unwrap();
+        assert_eq!(first::<Id>(&path).unwrap().unwrap().id, 7);
+        assert_eq!(last::<Id>(&path).unwrap().unwrap().id, 7);
+        assert!(!sibling(&path, ".tmp").exists());
+    }
+}
@@ -1,29 +1,79 @@
+use crate::utils::alertmanager::SeverityStyle;
+use crate::utils::webhook::WebhookFormat;
+use crate::DeviceAddress;
 use anyhow::Result;
+use chrono::{NaiveDateTime, NaiveTime};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+pub mod fleet;
+pub mod history;
+pub mod identity;
+mod jsonl;
+pub mod profiles;
+pub mod queue;
+pub mod quiet;
+pub mod templates;
+
+pub use fleet::Fleet;
+pub use history::{HistoryEntry, NotificationLog};
+pub use identity::DeviceCache;
+pub use profiles::ProfileStore;
+pub use queue::{OfflineQueue, QueuedNotification};
+pub use quiet::{QuietAction, QuietDecision, QuietHours};
+
 /// Main configuration structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     /// Default device to use if none specified
     pub default_device: Option<String>,
 
     /// Device configurations
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_devices")]
     pub devices: HashMap<String, DeviceConfig>,
 
     /// CLI preferences
     #[serde(default)]
     pub preferences: Preferences,
+
+    /// Scheduled profile switches (applied by `awtrix daemon`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedule: Vec<ScheduleEntry>,
+
+    /// Webhook routes (served by `awtrix serve`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookRoute>,
+
+    /// Named notification templates (`notify --template`); more can be kept
+    /// as files in the `templates` directory
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub templates: HashMap<String, serde_json::Value>,
+
+    /// Do-not-disturb periods for notifications and sounds
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quiet_hours: Vec<QuietHours>,
+
+    /// Where devices that moved were last found (`device-cache.json`)
+    #[serde(skip)]
+    pub cache: DeviceCache,
+
+    /// Device entries that could not be read, written back unchanged on save
+    #[serde(skip)]
+    pub invalid_devices: HashMap<String, toml::Value>,
 }
 
 /// Device-specific configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceConfig {
-    /// Device hostname or IP address
-    pub host: String,
+    /// Device hostname, IP address or URL (the last known address when `uid` is set)
+    pub host: DeviceAddress,
+
+    /// Unique device ID reported by the firmware, used to find the device
+    /// again when its address changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
 
     /// Human-readable device name
     pub name: String,
@@ -37,6 +87,52 @@ pub struct DeviceConfig {
     pub retries: u32,
 }
 
+/// A profile switch at a fixed local time of day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleEntry {
+    /// Local time of day (HH:MM)
+    pub at: String,
+
+    /// Profile to apply
+    pub profile: String,
+
+    /// Devices to apply the profile to (default device if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub devices: Vec<String>,
+}
+
+/// A webhook route turning incoming JSON into a notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookRoute {
+    /// URL path, e.g. `/github`
+    pub path: String,
+
+    /// Payload format
+    #[serde(default)]
+    pub format: WebhookFormat,
+
+    /// Devices to notify (default device if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub devices: Vec<String>,
+
+    /// Notification template whose strings may contain `{{field}}` placeholders
+    /// (the format's default if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification: Option<serde_json::Value>,
+
+    /// Secret expected in the `X-Awtrix-Token` or `X-Gitlab-Token` header, or the `token` query parameter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+
+    /// Alertmanager: notification style per `severity` label (and `resolved`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub severities: HashMap<String, SeverityStyle>,
+
+    /// Alertmanager: indicator (1-3) lit while any critical alert is firing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indicator: Option<u8>,
+}
+
 /// CLI preferences
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Preferences {
@@ -51,15 +147,96 @@ pub struct Preferences {
     /// Log level
     #[serde(default = "default_log_level")]
     pub log_level: String,
+
+    /// Sent notifications kept for `history list`; 0 turns the log off
+    #[serde(default = "default_history_limit")]
+    pub history_limit: usize,
+
+    /// Queue notifications for unreachable devices and deliver them later
+    /// (`queue flush`, or automatically by `awtrix daemon`)
+    #[serde(default)]
+    pub offline_queue: bool,
+
+    /// Seconds a queued notification is kept before it is dropped
+    #[serde(default = "default_queue_expiry")]
+    pub queue_expiry: u64,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            default_device: None,
-            devices: HashMap::new(),
-            preferences: Preferences::default(),
+impl Config {
+    /// Resolve a device name from the config to its host, treating unknown names as hosts
+    pub fn resolve_host(&self, device: &str) -> String {
+        self.devices
+            .get(device)
+            .map(|device_config| {
+                self.moved_host(device)
+                    .unwrap_or(&device_config.host)
+                    .to_unredacted_string()
+            })
+            .unwrap_or_else(|| device.to_string())
+    }
+
+    /// Parse a config file, keeping device entries that could not be read so
+    /// that saving does not delete them
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let mut config: Config = toml::from_str(content)?;
+        let mut table: toml::Table = content.parse()?;
+        if let Some(toml::Value::Table(devices)) = table.remove("devices") {
+            config.invalid_devices = devices
+                .into_iter()
+                .filter(|(name, _)| !config.devices.contains_key(name))
+                .collect();
+        }
+        Ok(config)
+    }
+
+    /// Serialize the config, including unreadable device entries unless a
+    /// device of the same name replaced them
+    pub fn to_toml(&self) -> Result<String> {
+        let mut table = toml::Table::try_from(self)?;
+        let invalid = self
+            .invalid_devices
+            .iter()
+            .filter(|(name, _)| !self.devices.contains_key(*name));
+        if let toml::Value::Table(devices) = table
+            .entry("devices")
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        {
+            devices.extend(invalid.map(|(name, entry)| (name.clone(), entry.clone())));
         }
+        Ok(toml::to_string_pretty(&table)?)
+    }
+}
+
+impl ScheduleEntry {
+    /// Parse the time of day
+    pub fn time(&self) -> Result<NaiveTime> {
+        NaiveTime::parse_from_str(&self.at, "%H:%M").map_err(|_| {
+            anyhow::anyhow!(
+                "Invalid schedule time '{}' for profile '{}'. Use HH:MM",
+                self.at,
+                self.profile
+            )
+        })
+    }
+
+    /// Check whether the entry fell due in the interval `(last, now]`
+    pub fn is_due(&self, last: NaiveDateTime, now: NaiveDateTime) -> Result<bool> {
+        let time = self.time()?;
+
+        // Check every day the interval touches so runs across midnight are not missed
+        let mut date = last.date();
+        while date <= now.date() {
+            let at = date.and_time(time);
+            if at > last && at <= now {
+                return Ok(true);
+            }
+            date = match date.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        Ok(false)
     }
 }
 
@@ -69,6 +246,9 @@ impl Default for Preferences {
             default_format: default_format(),
             colored_output: default_color(),
             log_level: default_log_level(),
+            history_limit: default_history_limit(),
+            offline_queue: false,
+            queue_expiry: default_queue_expiry(),
         }
     }
 }
@@ -88,6 +268,32 @@ fn default_color() -> bool {
 fn default_log_level() -> String {
     "info".to_string()
 }
+fn default_history_limit() -> usize {
+    1000
+}
+fn default_queue_expiry() -> u64 {
+    24 * 60 * 60
+}
+
+/// Read the devices, skipping invalid ones with a warning so that one bad
+/// entry does not make every command fail (see [`Config::from_toml`] for how
+/// they are kept)
+fn deserialize_devices<'de, D>(deserializer: D) -> Result<HashMap<String, DeviceConfig>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let entries = HashMap::<String, toml::Value>::deserialize(deserializer)?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|(name, entry)| match entry.try_into() {
+            Ok(device) => Some((name, device)),
+            Err(e) => {
+                tracing::warn!("Skipping device '{}': {}", name, e);
+                None
+            }
+        })
+        .collect())
+}
 
 /// Load configuration from file or create default
 pub fn load_config() -> Result<Config> {
@@ -95,16 +301,28 @@ pub fn load_config() -> Result<Config> {
 
     if config_path.exists() {
         let content = std::fs::read_to_string(&config_path)?;
-        let config: Config = toml::from_str(&content)?;
+        let mut config = Config::from_toml(&content)?;
+        config.cache = load_cache();
         Ok(config)
     } else {
         // Create default config file
-        let config = Config::default();
+        let config = Config {
+            cache: load_cache(),
+            ..Config::default()
+        };
         save_config(&config)?;
         Ok(config)
     }
 }
 
+/// The device cache only saves lookups, so it never stops the config from loading
+fn load_cache() -> DeviceCache {
+    DeviceCache::open().unwrap_or_else(|e| {
+        tracing::warn!("Ignoring device cache: {:#}", e);
+        DeviceCache::default()
+    })
+}
+
 /// Save configuration to file
 pub fn save_config(config: &Config) -> Result<()> {
     let config_path = get_config_path()?;
@@ -114,22 +332,27 @@ pub fn save_config(config: &Config) -> Result<()> {
         std::fs::create_dir_all(parent)?;
     }
 
-    let content = toml::to_string_pretty(config)?;
+    let content = config.to_toml()?;
     std::fs::write(config_path, content)?;
 
     Ok(())
 }
 
-/// Get the configuration file path
-fn get_config_path() -> Result<PathBuf> {
+/// Get the configuration directory
+pub fn get_config_dir() -> Result<PathBuf> {
     if let Some(proj_dirs) = ProjectDirs::from("", "awtrix3", "awtrix3") {
-        Ok(proj_dirs.config_dir().join("config.toml"))
+        Ok(proj_dirs.config_dir().to_path_buf())
     } else {
         // Fallback to current directory
-        Ok(PathBuf::from("config.toml"))
+        Ok(PathBuf::from("."))
     }
 }
 
+/// Get the configuration file path
+fn get_config_path() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("config.toml"))
+}
+
 /// Add a device to the configuration
 pub fn add_device(
     name: String,
@@ -140,7 +363,8 @@ pub fn add_device(
     let mut config = load_config()?;
 
     let device_config = DeviceConfig {
-        host,
+        host: host.parse()?,
+        uid: None,
         name: device_name,
         timeout: default_timeout(),
         retries: default_retries(),
@@ -161,6 +385,7 @@ pub fn remove_device(name: &str) -> Result<()> {
     let mut config = load_config()?;
 
     config.devices.remove(name);
+    config.invalid_devices.remove(name);
 
     // Clear default if it was the removed device
     if config.default_device.as_deref() == Some(name) {
@@ -174,7 +399,6 @@ pub fn remove_device(name: &str) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tempfile::tempdir;
 
     #[test]
     fn test_default_config() {
@@ -186,11 +410,14 @@ mod tests {
 
     #[test]
     fn test_config_serialization() {
-        let mut config = Config::default();
-        config.default_device = Some("test".to_string());
+        let mut config = Config {
+            default_device: Some("test".to_string()),
+            ..Default::default()
+        };
 
         let device = DeviceConfig {
-            host: "192.168.1.100".to_string(),
+            host: "192.168.1.100".parse().unwrap(),
+            uid: Some("awtrix_ab12cd".to_string()),
             name: "Test Device".to_string(),
             timeout: 30,
             retries: 3,
@@ -202,5 +429,108 @@ mod tests {
 
         assert_eq!(config.default_device, parsed.default_device);
         assert_eq!(config.devices.len(), parsed.devices.len());
+        assert_eq!(parsed.devices["test"].uid.as_deref(), Some("awtrix_ab12cd"));
+    }
+
+    #[test]
+    fn test_invalid_devices_are_skipped() {
+        let config: Config = toml::from_str(
+            r#"
+            [devices.lobby]
+            host = "192.168.1.100"
+            name = "Lobby"
+
+            [devices.broken]
+            host = "ftp://nope"
+            name = "Broken"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.devices.len(), 1);
+        assert!(config.devices.contains_key("lobby"));
+    }
+
+    #[test]
+    fn test_invalid_devices_survive_save() {
+        let config = Config::from_toml(
+            r#"
+            [devices.lobby]
+            host = "192.168.1.100"
+            name = "Lobby"
+
+            [devices.broken]
+            host = "ftp://nope"
+            name = "Broken"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.devices.len(), 1);
+
+        let saved: toml::Table = config.to_toml().unwrap().parse().unwrap();
+        let broken = &saved["devices"]["broken"];
+        assert_eq!(broken["host"].as_str(), Some("ftp://nope"));
+        assert_eq!(broken["name"].as_str(), Some("Broken"));
+        assert_eq!(saved["devices"]["lobby"]["name"].as_str(), Some("Lobby"));
+
+        // Adding a device of the same name replaces the bad entry
+        let mut config = Config::from_toml(&config.to_toml().unwrap()).unwrap();
+        config
+            .devices
+            .insert("broken".to_string(), config.devices["lobby"].clone());
+        let saved: toml::Table = config.to_toml().unwrap().parse().unwrap();
+        assert_eq!(saved["devices"]["broken"]["name"].as_str(), Some("Lobby"));
+    }
+
+    #[test]
+    fn test_schedule_parsing() {
+        let config: Config = toml::from_str(
+            r#"
+            default_device = "bedroom"
+
+            [[schedule]]
+            at = "22:00"
+            profile = "night"
+
+            [[schedule]]
+            at = "07:30"
+            profile = "day"
+            devices = ["bedroom", "office"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.schedule.len(), 2);
+        assert!(config.schedule[0].devices.is_empty());
+        assert_eq!(config.schedule[1].devices, ["bedroom", "office"]);
+    }
+
+    #[test]
+    fn test_schedule_is_due() {
+        let entry = ScheduleEntry {
+            at: "22:00".to_string(),
+            profile: "night".to_string(),
+            devices: vec![],
+        };
+        let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+
+        assert!(entry
+            .is_due(at("2025-01-01 21:59"), at("2025-01-01 22:00"))
+            .unwrap());
+        assert!(!entry
+            .is_due(at("2025-01-01 22:00"), at("2025-01-01 22:01"))
+            .unwrap());
+        assert!(entry
+            .is_due(at("2025-01-01 21:00"), at("2025-01-02 01:00"))
+            .unwrap());
+        assert!(!entry
+            .is_due(at("2025-01-01 22:30"), at("2025-01-02 01:00"))
+            .unwrap());
+
+        let invalid = ScheduleEntry {
+            at: "25:00".to_string(),
+            ..entry
+        };
+        assert!(invalid.time().is_err());
     }
 }
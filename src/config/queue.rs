@@ -0,0 +1,253 @@
+use super::jsonl::{self, Id};
+use crate::models::{Notification, Priority};
+use anyhow::Result;
+use chrono::{DateTime, Duration as TimeDelta, FixedOffset, Local, SubsecRound};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A notification waiting for its device to come back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedNotification {
+    pub id: u64,
+    pub queued_at: DateTime<FixedOffset>,
+    /// When the notification is dropped if it has not been delivered
+    pub expires_at: DateTime<FixedOffset>,
+    /// Device name or host to deliver to
+    pub device: String,
+    pub notification: Notification,
+    /// Priority it was sent with, applied again to quiet hours on delivery
+    #[serde(default)]
+    pub priority: Priority,
+    /// Not delivered before this time (deferred by quiet hours)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<DateTime<FixedOffset>>,
+}
+
+impl QueuedNotification {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Local::now()
+    }
+
+    /// Whether the notification may be delivered now
+    pub fn is_due(&self) -> bool {
+        self.not_before
+            .map_or(true, |not_before| not_before <= Local::now())
+    }
+
+    /// Whether a later `stack: false` notification may replace it undelivered;
+    /// held and critical notifications are always delivered
+    pub fn can_be_superseded(&self) -> bool {
+        self.notification.hold != Some(true) && self.priority < Priority::Critical
+    }
+}
+
+/// Durable queue of undelivered notifications, one JSON object per line in
+/// the config directory
+///
+/// Changes are made under a file lock, so several processes can queue and
+/// flush at the same time, and rewrites replace the file atomically.
+#[derive(Debug, Clone)]
+pub struct OfflineQueue {
+    path: PathBuf,
+}
+
+impl OfflineQueue {
+    /// Open the queue in the default config directory
+    pub fn open() -> Result<Self> {
+        Ok(Self::new(super::get_config_dir()?.join("queue.jsonl")))
+    }
+
+    /// Open the queue at `path`
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Queue a notification for a device, kept for `expiry`
+    pub fn push(
+        &self,
+        device: &str,
+        notification: &Notification,
+        priority: Priority,
+        expiry: TimeDelta,
+    ) -> Result<QueuedNotification> {
+        self.append(device, notification, priority, None, expiry)
+    }
+
+    /// Queue a notification that must not be delivered before `until`,
+    /// kept for `expiry` after that
+    pub fn defer(
+        &self,
+        device: &str,
+        notification: &Notification,
+        priority: Priority,
+        until: DateTime<FixedOffset>,
+        expiry: TimeDelta,
+    ) -> Result<QueuedNotification> {
+        self.append(device, notification, priority, Some(until), expiry)
+    }
+
+    fn append(
+        &self,
+        device: &str,
+        notification: &Notification,
+        priority: Priority,
+        not_before: Option<DateTime<FixedOffset>>,
+        expiry: TimeDelta,
+    ) -> Result<QueuedNotification> {
+        let _lock = jsonl::lock(&self.path)?;
+
+        let last = match jsonl::last::<Id>(&self.path)? {
+            Some(last) => Some(last.id),
+            None => self.entries()?.last().map(|entry| entry.id),
+        };
+        let now = Local::now().fixed_offset().trunc_subsecs(0);
+        let not_before = not_before.map(|not_before| not_before.trunc_subsecs(0));
+        let entry = QueuedNotification {
+            id: last.map_or(1, |last| last + 1),
+            queued_at: now,
+            expires_at: not_before.unwrap_or(now) + expiry,
+            device: device.to_string(),
+            notification: notification.clone(),
+            priority,
+            not_before,
+        };
+        jsonl::append(&self.path, &entry)?;
+
+        Ok(entry)
+    }
+
+    /// All entries in queue order, including expired ones
+    pub fn entries(&self) -> Result<Vec<QueuedNotification>> {
+        jsonl::read(&self.path, "queue")
+    }
+
+    /// Entries that have not expired, in queue order
+    pub fn pending(&self) -> Result<Vec<QueuedNotification>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|entry| !entry.is_expired())
+            .collect())
+    }
+
+    /// Remove delivered entries and drop expired ones; returns how many expired
+    pub fn remove(&self, ids: &[u64]) -> Result<usize> {
+        let _lock = jsonl::lock(&self.path)?;
+
+        let entries = self.entries()?;
+        let expired = entries.iter().filter(|entry| entry.is_expired()).count();
+        let kept: Vec<_> = entries
+            .into_iter()
+            .filter(|entry| !entry.is_expired() && !ids.contains(&entry.id))
+            .collect();
+
+        if kept.is_empty() {
+            if self.path.exists() {
+                fs::remove_file(&self.path)?;
+            }
+        } else {
+            jsonl::rewrite(&self.path, &kept)?;
+        }
+        Ok(expired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Notification {
+        Notification::builder().text(text).build()
+    }
+
+    #[test]
+    fn test_push_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = OfflineQueue::new(dir.path().join("queue.jsonl"));
+        let hour = TimeDelta::hours(1);
+
+        queue
+            .push("lobby", &text("one"), Priority::Normal, hour)
+            .unwrap();
+        queue
+            .push("kitchen", &text("two"), Priority::Normal, hour)
+            .unwrap();
+        queue
+            .push("lobby", &text("three"), Priority::Normal, hour)
+            .unwrap();
+
+        let ids: Vec<u64> = queue.pending().unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+
+        assert_eq!(queue.remove(&[1, 3]).unwrap(), 0);
+        let pending = queue.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].device, "kitchen");
+
+        // Ids keep counting up while entries are waiting
+        assert_eq!(
+            queue
+                .push("lobby", &text("four"), Priority::Normal, hour)
+                .unwrap()
+                .id,
+            3
+        );
+        queue.remove(&[2, 3]).unwrap();
+        assert!(!dir.path().join("queue.jsonl").exists());
+    }
+
+    #[test]
+    fn test_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = OfflineQueue::new(dir.path().join("queue.jsonl"));
+
+        queue
+            .push(
+                "lobby",
+                &text("stale"),
+                Priority::Normal,
+                TimeDelta::seconds(-1),
+            )
+            .unwrap();
+        queue
+            .push(
+                "lobby",
+                &text("fresh"),
+                Priority::Normal,
+                TimeDelta::hours(1),
+            )
+            .unwrap();
+
+        assert_eq!(queue.entries().unwrap().len(), 2);
+        assert_eq!(queue.pending().unwrap().len(), 1);
+        assert_eq!(queue.remove(&[]).unwrap(), 1);
+        assert_eq!(queue.entries().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_defer() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = OfflineQueue::new(dir.path().join("queue.jsonl"));
+        let until = Local::now().fixed_offset() + TimeDelta::hours(8);
+
+        let deferred = queue
+            .defer(
+                "bedroom",
+                &text("later"),
+                Priority::High,
+                until,
+                TimeDelta::hours(1),
+            )
+            .unwrap();
+        assert!(!deferred.is_due());
+        assert!(!deferred.is_expired());
+        assert_eq!(deferred.priority, Priority::High);
+        assert_eq!(
+            deferred.expires_at,
+            until.trunc_subsecs(0) + TimeDelta::hours(1)
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use super::Config;
+use crate::models::{Notification, Priority};
+use anyhow::Result;
+use chrono::{Datelike, Duration as TimeDelta, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// A do-not-disturb period on some devices
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    /// Local start time (HH:MM)
+    pub from: String,
+
+    /// Local end time (HH:MM); earlier than `from` for periods across midnight
+    pub to: String,
+
+    /// Days the period starts on, e.g. `["mon", "tue"]` (every day if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<String>,
+
+    /// Devices the period applies to (all devices if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub devices: Vec<String>,
+
+    /// What happens to notifications below `min_priority`
+    #[serde(default)]
+    pub action: QuietAction,
+
+    /// Notifications at or above this priority are let through unchanged
+    #[serde(default = "default_min_priority")]
+    pub min_priority: Priority,
+}
+
+fn default_min_priority() -> Priority {
+    Priority::Critical
+}
+
+/// Handling of non-critical notifications during quiet hours
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuietAction {
+    /// Show them without sound, `wakeup` or `hold`
+    #[default]
+    Silence,
+    /// Drop them
+    Block,
+    /// Deliver them once quiet hours end
+    Defer,
+}
+
+/// What to do with a notification, given the quiet hours in effect
+#[derive(Debug, Clone)]
+pub enum QuietDecision {
+    /// Send it, possibly silenced
+    Send(Notification),
+    /// Drop it; quiet hours last until the given time
+    Block { until: NaiveDateTime },
+    /// Hold it back until quiet hours end
+    Defer { until: NaiveDateTime },
+}
+
+impl QuietHours {
+    fn time(&self, time: &str) -> Result<NaiveTime> {
+        NaiveTime::parse_from_str(time, "%H:%M")
+            .map_err(|_| anyhow::anyhow!("Invalid quiet hours time '{}'. Use HH:MM", time))
+    }
+
+    fn weekdays(&self) -> Result<Vec<Weekday>> {
+        self.days
+            .iter()
+            .map(|day| {
+                day.parse::<Weekday>().map_err(|_| {
+                    anyhow::anyhow!("Invalid quiet hours day '{}'. Use mon, tue, ...", day)
+                })
+            })
+            .collect()
+    }
+
+    /// Check the times and days
+    pub fn validate(&self) -> Result<()> {
+        self.time(&self.from)?;
+        self.time(&self.to)?;
+        self.weekdays()?;
+        Ok(())
+    }
+
+    pub fn applies_to(&self, device: &str) -> bool {
+        self.devices.is_empty() || self.devices.iter().any(|d| d == device)
+    }
+
+    /// When the period ends, if it is in effect at `now`
+    pub fn active_until(&self, now: NaiveDateTime) -> Result<Option<NaiveDateTime>> {
+        let (from, to) = (self.time(&self.from)?, self.time(&self.to)?);
+        let days = self.weekdays()?;
+
+        // A period that started yesterday may still be running
+        for start_date in [now.date() - TimeDelta::days(1), now.date()] {
+            if !days.is_empty() && !days.contains(&start_date.weekday()) {
+                continue;
+            }
+            let start = start_date.and_time(from);
+            let end = if to > from {
+                start_date.and_time(to)
+            } else {
+                (start_date + TimeDelta::days(1)).and_time(to)
+            };
+            if start <= now && now < end {
+                return Ok(Some(end));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Apply the period to a notification
+    pub fn decide(
+        &self,
+        until: NaiveDateTime,
+        notification: Notification,
+        priority: Priority,
+    ) -> QuietDecision {
+        if priority >= self.min_priority {
+            return QuietDecision::Send(notification);
+        }
+        match self.action {
+            QuietAction::Silence => QuietDecision::Send(Notification {
+                sound: None,
+                rtttl: None,
+                loop_sound: None,
+                wakeup: None,
+                hold: None,
+                ..notification
+            }),
+            QuietAction::Block => QuietDecision::Block { until },
+            QuietAction::Defer => QuietDecision::Defer { until },
+        }
+    }
+}
+
+impl Config {
+    /// Quiet hours in effect for a device at `now`, and when they end
+    ///
+    /// With overlapping periods, the one that ends last wins.
+    pub fn quiet_hours(
+        &self,
+        device: &str,
+        now: NaiveDateTime,
+    ) -> Result<Option<(&QuietHours, NaiveDateTime)>> {
+        let mut active: Option<(&QuietHours, NaiveDateTime)> = None;
+        for quiet in self.quiet_hours.iter().filter(|q| q.applies_to(device)) {
+            if let Some(until) = quiet.active_until(now)? {
+                if active.map_or(true, |(_, end)| until > end) {
+                    active = Some((quiet, until));
+                }
+            }
+        }
+        Ok(active)
+    }
+
+    /// Decide what to do with a notification for a device at `now`
+    pub fn quiet_decision(
+        &self,
+        device: &str,
+        notification: Notification,
+        priority: Priority,
+        now: NaiveDateTime,
+    ) -> Result<QuietDecision> {
+        Ok(match self.quiet_hours(device, now)? {
+            Some((quiet, until)) => quiet.decide(until, notification, priority),
+            None => QuietDecision::Send(notification),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        // 2024-01-01 is a Monday
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+    }
+
+    fn quiet(from: &str, to: &str, days: &[&str]) -> QuietHours {
+        QuietHours {
+            from: from.to_string(),
+            to: to.to_string(),
+            days: days.iter().map(|d| d.to_string()).collect(),
+            devices: vec!["bedroom".to_string()],
+            action: QuietAction::Silence,
+            min_priority: Priority::Critical,
+        }
+    }
+
+    #[test]
+    fn test_active_until() {
+        let night = quiet("22:00", "07:00", &[]);
+        assert_eq!(night.active_until(at(1, "21:59")).unwrap(), None);
+        assert_eq!(
+            night.active_until(at(1, "23:00")).unwrap(),
+            Some(at(2, "07:00"))
+        );
+        assert_eq!(
+            night.active_until(at(2, "03:00")).unwrap(),
+            Some(at(2, "07:00"))
+        );
+        assert_eq!(night.active_until(at(2, "07:00")).unwrap(), None);
+
+        // Friday night only: runs into Saturday morning, not Sunday's
+        let friday = quiet("22:00", "09:00", &["fri"]);
+        assert_eq!(
+            friday.active_until(at(6, "08:00")).unwrap(),
+            Some(at(6, "09:00"))
+        );
+        assert_eq!(friday.active_until(at(7, "08:00")).unwrap(), None);
+
+        let lunch = quiet("12:00", "13:00", &["mon", "tue"]);
+        assert!(lunch.active_until(at(2, "12:30")).unwrap().is_some());
+        assert!(lunch.active_until(at(3, "12:30")).unwrap().is_none());
+
+        assert!(quiet("25:00", "07:00", &[]).validate().is_err());
+        assert!(quiet("22:00", "07:00", &["someday"]).validate().is_err());
+    }
+
+    #[test]
+    fn test_decisions() {
+        let config = Config {
+            quiet_hours: vec![quiet("22:00", "07:00", &[])],
+            ..Config::default()
+        };
+        let loud = Notification::builder()
+            .text("Washer done")
+            .sound("beep")
+            .wakeup(true)
+            .build();
+        let night = at(1, "23:00");
+
+        match config
+            .quiet_decision("bedroom", loud.clone(), Priority::Normal, night)
+            .unwrap()
+        {
+            QuietDecision::Send(silenced) => {
+                assert_eq!(silenced.text.as_deref(), Some("Washer done"));
+                assert!(silenced.sound.is_none() && silenced.wakeup.is_none());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        match config
+            .quiet_decision("bedroom", loud.clone(), Priority::Critical, night)
+            .unwrap()
+        {
+            QuietDecision::Send(sent) => assert_eq!(sent.sound.as_deref(), Some("beep")),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Other devices are not affected
+        match config
+            .quiet_decision("kitchen", loud.clone(), Priority::Low, night)
+            .unwrap()
+        {
+            QuietDecision::Send(sent) => assert_eq!(sent.sound.as_deref(), Some("beep")),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let mut deferring = config.clone();
+        deferring.quiet_hours[0].action = QuietAction::Defer;
+        assert!(matches!(
+            deferring
+                .quiet_decision("bedroom", loud, Priority::High, night)
+                .unwrap(),
+            QuietDecision::Defer { until } if until == at(2, "07:00")
+        ));
+    }
+}
@@ -0,0 +1,94 @@
+use super::Config;
+use crate::error::AwtrixError;
+use anyhow::Result;
+use serde_json::Value;
+use std::path::Path;
+
+impl Config {
+    /// Look up a notification template by name
+    ///
+    /// Templates in the config's `[templates]` table come first, then
+    /// `<name>.json` or `<name>.toml` in the `templates` directory next to the
+    /// config file.
+    pub fn template(&self, name: &str) -> Result<Value> {
+        if let Some(template) = self.templates.get(name) {
+            return Ok(template.clone());
+        }
+        find_template(&super::get_config_dir()?.join("templates"), name)
+    }
+}
+
+fn find_template(dir: &Path, name: &str) -> Result<Value> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(AwtrixError::Validation(format!(
+            "Invalid template name '{}'. Use letters, digits, '-' and '_'",
+            name
+        ))
+        .into());
+    }
+
+    let json = dir.join(format!("{}.json", name));
+    if json.exists() {
+        let content = std::fs::read_to_string(&json)?;
+        return serde_json::from_str(&content).map_err(|e| {
+            AwtrixError::Config(format!("Invalid template {}: {}", json.display(), e)).into()
+        });
+    }
+
+    let toml = dir.join(format!("{}.toml", name));
+    if toml.exists() {
+        let content = std::fs::read_to_string(&toml)?;
+        return toml::from_str(&content).map_err(|e| {
+            AwtrixError::Config(format!("Invalid template {}: {}", toml.display(), e)).into()
+        });
+    }
+
+    Err(AwtrixError::Config(format!(
+        "Template '{}' not found in the config or in {}",
+        name,
+        dir.display()
+    ))
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_find_template() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("deploy.json"), r#"{ "text": "{{app}}" }"#).unwrap();
+        std::fs::write(
+            dir.path().join("backup.toml"),
+            "text = \"Backup {{status}}\"\nhold = true\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_template(dir.path(), "deploy").unwrap(),
+            json!({ "text": "{{app}}" })
+        );
+        assert_eq!(
+            find_template(dir.path(), "backup").unwrap(),
+            json!({ "text": "Backup {{status}}", "hold": true })
+        );
+        assert!(find_template(dir.path(), "missing").is_err());
+        assert!(find_template(dir.path(), "../deploy").is_err());
+    }
+
+    #[test]
+    fn test_config_templates_win() {
+        let config: Config =
+            toml::from_str("[templates.deploy]\ntext = \"from config\"\n").unwrap();
+        assert_eq!(
+            config.template("deploy").unwrap(),
+            json!({ "text": "from config" })
+        );
+    }
+}
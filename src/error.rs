@@ -23,10 +23,30 @@ pub enum AwtrixError {
     #[error("Invalid icon ID: {0}")]
     InvalidIcon(u32),
 
+    /// Invalid input, such as an out-of-range value
+    #[error("{0}")]
+    Validation(String),
+
     /// API error from device
     #[error("API error: {message} (code: {code})")]
     Api { message: String, code: u16 },
 
+    /// Feature not supported by the device firmware
+    #[error("{feature} is not available on this device (requires firmware >= {required}, found {found})")]
+    Unsupported {
+        feature: String,
+        required: String,
+        found: String,
+    },
+
+    /// Settings transaction failed
+    #[error("Settings transaction failed: {reason} (rolled back: {rolled_back})")]
+    Transaction { reason: String, rolled_back: bool },
+
+    /// Waiting on the device took too long
+    #[error("Timed out after {seconds}s waiting for {what}")]
+    Timeout { what: String, seconds: u64 },
+
     /// Serialization/deserialization error
     #[error("Serialization error")]
     Serialization(#[from] serde_json::Error),
@@ -44,5 +64,150 @@ pub enum AwtrixError {
     Other(#[from] anyhow::Error),
 }
 
+/// Broad category of an error, stable for scripting
+///
+/// Each kind maps to a documented process exit code, see [`ErrorKind::exit_code`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Anything not covered below
+    Other,
+    /// Missing or invalid configuration, including unknown devices
+    Config,
+    /// The device could not be reached
+    Unreachable,
+    /// The device rejected the credentials
+    Auth,
+    /// Invalid input
+    Validation,
+    /// The device returned an error
+    Api,
+    /// The device firmware does not support the feature
+    Unsupported,
+    /// Waiting on the device took too long
+    Timeout,
+}
+
+impl ErrorKind {
+    /// Name used in machine-readable output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Other => "other",
+            ErrorKind::Config => "config",
+            ErrorKind::Unreachable => "unreachable",
+            ErrorKind::Auth => "auth",
+            ErrorKind::Validation => "validation",
+            ErrorKind::Api => "api",
+            ErrorKind::Unsupported => "unsupported",
+            ErrorKind::Timeout => "timeout",
+        }
+    }
+
+    /// Process exit code for the CLI
+    ///
+    /// | Code | Kind        |
+    /// |------|-------------|
+    /// | 1    | other       |
+    /// | 2    | usage (invalid arguments, reported by the parser) |
+    /// | 3    | config      |
+    /// | 4    | unreachable |
+    /// | 5    | auth        |
+    /// | 6    | validation  |
+    /// | 7    | api         |
+    /// | 8    | unsupported |
+    /// | 9    | timeout     |
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Config => 3,
+            ErrorKind::Unreachable => 4,
+            ErrorKind::Auth => 5,
+            ErrorKind::Validation => 6,
+            ErrorKind::Api => 7,
+            ErrorKind::Unsupported => 8,
+            ErrorKind::Timeout => 9,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AwtrixError {
+    /// Category of this error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            AwtrixError::Http(e) => http_kind(e),
+            AwtrixError::Config(_) | AwtrixError::Url(_) => ErrorKind::Config,
+            AwtrixError::DeviceUnreachable { .. } => ErrorKind::Unreachable,
+            AwtrixError::InvalidColor(_)
+            | AwtrixError::InvalidIcon(_)
+            | AwtrixError::Validation(_)
+            | AwtrixError::Serialization(_) => ErrorKind::Validation,
+            AwtrixError::Api {
+                code: 401 | 403, ..
+            } => ErrorKind::Auth,
+            AwtrixError::Api { .. } | AwtrixError::Transaction { .. } => ErrorKind::Api,
+            AwtrixError::Unsupported { .. } => ErrorKind::Unsupported,
+            AwtrixError::Timeout { .. } => ErrorKind::Timeout,
+            AwtrixError::Io(_) => ErrorKind::Other,
+            AwtrixError::Other(e) => e
+                .chain()
+                .find_map(|cause| cause.downcast_ref::<AwtrixError>())
+                .map(AwtrixError::kind)
+                .unwrap_or(ErrorKind::Other),
+        }
+    }
+}
+
+/// Category of a transport-level error
+pub fn http_kind(error: &reqwest::Error) -> ErrorKind {
+    match error.status().map(|status| status.as_u16()) {
+        Some(401 | 403) => ErrorKind::Auth,
+        Some(_) => ErrorKind::Api,
+        // A response arrived but could not be decoded
+        None if error.is_decode() => ErrorKind::Api,
+        None if error.is_builder() => ErrorKind::Config,
+        None => ErrorKind::Unreachable,
+    }
+}
+
 /// Result type alias for AWTRIX operations
 pub type Result<T> = std::result::Result<T, AwtrixError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_kinds() {
+        let api = |code| AwtrixError::Api {
+            message: String::new(),
+            code,
+        };
+
+        assert_eq!(api(401).kind(), ErrorKind::Auth);
+        assert_eq!(api(500).kind(), ErrorKind::Api);
+        assert_eq!(
+            AwtrixError::InvalidColor("x".into()).kind(),
+            ErrorKind::Validation
+        );
+        assert_eq!(
+            AwtrixError::Config("x".into()).kind().exit_code(),
+            ErrorKind::Config.exit_code()
+        );
+
+        // Wrapped errors keep their kind
+        let wrapped = AwtrixError::Other(anyhow::Error::new(api(403)).context("while syncing"));
+        assert_eq!(wrapped.kind(), ErrorKind::Auth);
+    }
+
+    #[tokio::test]
+    async fn test_connection_refused_is_unreachable() {
+        // Nothing listens on port 1
+        let err = reqwest::get("http://127.0.0.1:1/").await.unwrap_err();
+        assert_eq!(http_kind(&err), ErrorKind::Unreachable);
+    }
+}
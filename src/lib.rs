@@ -31,8 +31,8 @@ pub mod config;
 pub mod utils;
 
 // Re-exports for convenience
-pub use client::{Client, ClientBuilder};
-pub use error::{AwtrixError, Result};
+pub use client::{Client, ClientBuilder, DeviceAddress};
+pub use error::{AwtrixError, ErrorKind, Result};
 pub use models::{Color, CustomApp, Effect, Notification, Settings, Transition};
 
 /// Prelude module for easy imports
@@ -42,7 +42,6 @@ pub mod prelude {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
 
     #[test]
     fn test_version() {
@@ -1,12 +1,16 @@
 use anyhow::Result;
 use clap::Parser;
-use colored::{control, Colorize};
+use colored::control;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod cli;
 mod commands;
-mod config;
-mod utils;
+mod output;
+mod server;
+
+use awtrix3::config;
+#[cfg(feature = "discovery")]
+use awtrix3::utils;
 
 use cli::Cli;
 
@@ -24,27 +28,40 @@ async fn main() -> Result<()> {
     // Parse CLI arguments
     let cli = Cli::parse();
 
-    // Store json flag before moving cli
-    let json_output = cli.json;
+    // Load configuration
+    let config = match config::load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            // The configured default format is unavailable, so use the flags alone
+            let format = cli.output_format(&config::Config::default());
+            let e = anyhow::Error::new(awtrix3::AwtrixError::Config(format!("{:#}", e)));
+            eprintln!("{}", output::render_error(&e, format)?);
+            std::process::exit(output::error_kind(&e).exit_code());
+        }
+    };
+
+    // Resolve output format before moving cli
+    let format = cli.output_format(&config);
+    output::set_format(format);
 
     // Set up colored output
-    if !json_output {
+    if cli.no_color || !config.preferences.colored_output || !format.is_human() {
+        control::set_override(false);
+    } else {
         control::set_override(true);
     }
 
-    // Load configuration
-    let config = config::load_config()?;
-
     // Execute command
     match cli.execute(config).await {
-        Ok(()) => Ok(()),
-        Err(e) => {
-            if !json_output {
-                eprintln!("{} {}", "Error:".red().bold(), e);
-            } else {
-                eprintln!(r#"{{"error": "{}"}}"#, e);
+        Ok(output) => {
+            if !output.is_empty() {
+                println!("{}", output.render(format)?);
             }
-            std::process::exit(1);
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{}", output::render_error(&e, format)?);
+            std::process::exit(output::error_kind(&e).exit_code());
         }
     }
 }
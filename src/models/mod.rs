@@ -8,6 +8,6 @@ pub mod settings;
 pub use app::{AppInfo, CustomApp};
 pub use color::Color;
 pub use effect::{Effect, Transition};
-pub use notification::{Notification, NotificationBuilder};
+pub use notification::{Notification, NotificationBuilder, Priority};
 pub use response::{LoopInfo, Stats};
-pub use settings::Settings;
+pub use settings::{SettingChange, Settings};
@@ -1,5 +1,8 @@
+use crate::error::AwtrixError;
 use crate::models::color::Color;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 /// Represents a notification to be sent to the AWTRIX3 device
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +89,50 @@ pub struct EffectSettings {
     pub blend: Option<bool>,
 }
 
+/// How urgent a notification is, for local relaying and policies
+///
+/// Not sent to the device; `critical` notifications may interrupt others and
+/// bypass limits.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Critical,
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Critical => "critical",
+        })
+    }
+}
+
+impl FromStr for Priority {
+    type Err = AwtrixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "low" => Ok(Priority::Low),
+            "normal" => Ok(Priority::Normal),
+            "high" => Ok(Priority::High),
+            "critical" => Ok(Priority::Critical),
+            _ => Err(AwtrixError::Validation(format!(
+                "Invalid priority '{}', expected low, normal, high or critical",
+                s
+            ))),
+        }
+    }
+}
+
 /// Builder for creating notifications
 pub struct NotificationBuilder {
     notification: Notification,
@@ -162,6 +209,12 @@ impl NotificationBuilder {
     }
 }
 
+impl Default for NotificationBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Notification {
     pub fn builder() -> NotificationBuilder {
         NotificationBuilder::new()
@@ -185,6 +238,14 @@ mod tests {
         assert_eq!(notification.duration, Some(10));
     }
 
+    #[test]
+    fn test_priority() {
+        assert!(Priority::Critical > Priority::High);
+        assert!(Priority::Low < Priority::default());
+        assert_eq!("HIGH".parse::<Priority>().unwrap(), Priority::High);
+        assert!("urgent".parse::<Priority>().is_err());
+    }
+
     #[test]
     fn test_progress_clamping() {
         let notification = Notification::builder().progress(150).build();
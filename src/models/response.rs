@@ -1,49 +1,103 @@
 use crate::models::app::AppInfo;
 use serde::{Deserialize, Serialize};
 
-/// Device statistics response
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// Device statistics response (`/api/stats`)
+///
+/// Every field is optional, since the reported fields vary between firmware
+/// versions and hardware. Unknown fields are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Stats {
-    /// Device uptime in seconds
-    pub uptime: u64,
+    /// Battery percentage (if battery powered)
+    #[serde(rename = "bat", skip_serializing_if = "Option::is_none")]
+    pub battery: Option<u8>,
 
-    /// WiFi signal strength
-    pub wifi_signal: i8,
+    /// Raw battery ADC value
+    #[serde(rename = "bat_raw", skip_serializing_if = "Option::is_none")]
+    pub battery_raw: Option<u32>,
 
-    /// Free heap memory
-    pub heap: u32,
+    /// Hardware type
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub device_type: Option<u8>,
 
-    /// Temperature in Celsius (if sensor available)
+    /// Light level
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub lux: Option<f32>,
+
+    /// Raw LDR (light sensor) value
+    #[serde(skip_serializing_if = "Option::is_none", alias = "ldr")]
+    pub ldr_raw: Option<u32>,
+
+    /// Free heap memory in bytes
+    #[serde(skip_serializing_if = "Option::is_none", alias = "heap")]
+    pub ram: Option<u32>,
+
+    /// Current matrix brightness
+    #[serde(rename = "bri", skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<u8>,
+
+    /// Temperature (if sensor available)
+    #[serde(rename = "temp", skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
 
     /// Humidity percentage (if sensor available)
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "hum", skip_serializing_if = "Option::is_none")]
     pub humidity: Option<f32>,
 
-    /// LDR (light sensor) value
+    /// Device uptime in seconds
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub ldr: Option<u16>,
+    pub uptime: Option<u64>,
 
-    /// Light level
+    /// WiFi signal strength in dBm
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub lux: Option<f32>,
+    pub wifi_signal: Option<i16>,
 
-    /// Battery percentage (if battery powered)
+    /// Number of received messages
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub battery: Option<u8>,
+    pub messages: Option<u32>,
 
-    /// Matrix on/off state
-    pub matrix: bool,
+    /// Firmware version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
 
-    /// Current app name
+    /// Indicator 1 state
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub indicator1: Option<bool>,
+
+    /// Indicator 2 state
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indicator2: Option<bool>,
+
+    /// Indicator 3 state
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indicator3: Option<bool>,
+
+    /// Current app name
+    #[serde(rename = "app", skip_serializing_if = "Option::is_none")]
     pub current_app: Option<String>,
 
-    /// Indicator states
+    /// Unique device ID
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub indicators: Option<IndicatorStates>,
+    pub uid: Option<String>,
+
+    /// Matrix on/off state
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<bool>,
+
+    /// Device IP address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+}
+
+impl Stats {
+    /// Indicator states, if the firmware reports all three
+    pub fn indicators(&self) -> Option<IndicatorStates> {
+        Some(IndicatorStates {
+            indicator1: self.indicator1?,
+            indicator2: self.indicator2?,
+            indicator3: self.indicator3?,
+        })
+    }
 }
 
 /// Indicator LED states
@@ -93,3 +147,37 @@ pub struct ScreenData {
     /// Pixel data (RGB values)
     pub pixels: Vec<Vec<Vec<u8>>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_firmware_fields() {
+        let stats: Stats = serde_json::from_str(
+            r#"{"bat":100,"bat_raw":671,"type":0,"lux":4,"ldr_raw":7,"ram":148080,"bri":26,
+                "temp":25,"hum":33,"uptime":1060,"wifi_signal":-65,"messages":0,"version":"0.96",
+                "indicator1":false,"indicator2":true,"indicator3":false,"app":"Time",
+                "uid":"awtrix_4e8f3c","matrix":true,"ip":"192.168.178.49"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(stats.battery, Some(100));
+        assert_eq!(stats.ram, Some(148080));
+        assert_eq!(stats.brightness, Some(26));
+        assert_eq!(stats.temperature, Some(25.0));
+        assert_eq!(stats.wifi_signal, Some(-65));
+        assert_eq!(stats.current_app.as_deref(), Some("Time"));
+        assert_eq!(stats.uid.as_deref(), Some("awtrix_4e8f3c"));
+        assert!(stats.indicators().unwrap().indicator2);
+    }
+
+    #[test]
+    fn test_stats_missing_and_extra_fields() {
+        let stats: Stats = serde_json::from_str(r#"{"uptime": 5, "unknown": [1, 2]}"#).unwrap();
+
+        assert_eq!(stats.uptime, Some(5));
+        assert_eq!(stats.matrix, None);
+        assert!(stats.indicators().is_none());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::models::color::Color;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
 
 /// Device settings structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,15 +12,15 @@ pub struct Settings {
     pub brightness: Option<u8>,
 
     /// Automatic brightness control
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "auto_brightness")]
     pub auto_brightness: Option<bool>,
 
     /// Automatic app switching
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "auto_transition")]
     pub auto_transition: Option<bool>,
 
     /// App display duration
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "app_time")]
     pub app_time: Option<u32>,
 
     /// Transition effect
@@ -26,27 +28,27 @@ pub struct Settings {
     pub transition: Option<String>,
 
     /// Transition time
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "transition_time")]
     pub transition_time: Option<u32>,
 
     /// Global text color
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "text_color")]
     pub text_color: Option<Color>,
 
     /// Time app settings
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "time_app")]
     pub time_app: Option<TimeAppSettings>,
 
     /// Date app settings
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "date_app")]
     pub date_app: Option<DateAppSettings>,
 
     /// Temperature unit
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "temp_unit")]
     pub temp_unit: Option<String>,
 
     /// Scroll speed percentage
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "scroll_speed")]
     pub scroll_speed: Option<u32>,
 }
 
@@ -58,19 +60,19 @@ pub struct TimeAppSettings {
     pub format: Option<u8>,
 
     /// Show weekday
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "show_weekday")]
     pub show_weekday: Option<bool>,
 
     /// Calendar header color
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "cal_header_color")]
     pub cal_header_color: Option<Color>,
 
     /// Calendar body color
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "cal_body_color")]
     pub cal_body_color: Option<Color>,
 
     /// Calendar text color
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "cal_text_color")]
     pub cal_text_color: Option<Color>,
 }
 
@@ -109,3 +111,263 @@ impl Default for Settings {
         Self::new()
     }
 }
+
+/// A single field that differs between two settings snapshots
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SettingChange {
+    /// Dotted setting key, e.g. `time_app.format`
+    pub key: String,
+
+    /// Value in the current settings (None if unset)
+    pub current: Option<Value>,
+
+    /// Value in the desired settings (None if unset)
+    pub desired: Option<Value>,
+}
+
+impl Settings {
+    /// Flatten settings into dotted snake_case keys (as used by `settings get/set`)
+    pub fn flatten(&self) -> BTreeMap<String, Value> {
+        let mut fields = BTreeMap::new();
+        if let Ok(value) = serde_json::to_value(self) {
+            flatten_into(&mut fields, "", &value);
+        }
+        fields
+    }
+
+    /// Compare these (current) settings with `desired`, field by field
+    pub fn diff(&self, desired: &Settings) -> Vec<SettingChange> {
+        let current = self.flatten();
+        let desired = desired.flatten();
+
+        let mut keys: Vec<&String> = current.keys().chain(desired.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        keys.into_iter()
+            .filter(|key| current.get(*key) != desired.get(*key))
+            .map(|key| SettingChange {
+                key: key.clone(),
+                current: current.get(key).cloned(),
+                desired: desired.get(key).cloned(),
+            })
+            .collect()
+    }
+
+    /// Build settings containing only the fields of `desired` that differ from these
+    pub fn changes_to(&self, desired: &Settings) -> Settings {
+        let current = serde_json::to_value(self).unwrap_or(Value::Null);
+        let desired = serde_json::to_value(desired).unwrap_or(Value::Null);
+
+        changed_fields(&current, &desired)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    }
+
+    /// Check whether no field is set
+    pub fn is_empty(&self) -> bool {
+        self.flatten().is_empty()
+    }
+
+    /// Keep only the fields whose dotted key matches one of `patterns` (`*` is a wildcard)
+    pub fn filter_keys<S: AsRef<str>>(&self, patterns: &[S]) -> Settings {
+        let mut value = serde_json::to_value(self).unwrap_or(Value::Null);
+        retain_keys(&mut value, "", &|key| {
+            patterns.iter().any(|p| key_matches(p.as_ref(), key))
+        });
+        serde_json::from_value(value).unwrap_or_default()
+    }
+
+    /// Overlay the fields set in `other` onto these settings
+    pub fn merge(&mut self, other: &Settings) {
+        let mut merged = serde_json::to_value(&*self).unwrap_or(Value::Null);
+        if let Ok(other) = serde_json::to_value(other) {
+            merge_values(&mut merged, other);
+        }
+        if let Ok(merged) = serde_json::from_value(merged) {
+            *self = merged;
+        }
+    }
+}
+
+fn merge_values(target: &mut Value, other: Value) {
+    match (target, other) {
+        (Value::Object(target), Value::Object(other)) => {
+            for (key, value) in other {
+                merge_values(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, other) => *target = other,
+    }
+}
+
+fn flatten_into(fields: &mut BTreeMap<String, Value>, prefix: &str, value: &Value) {
+    if let Value::Object(map) = value {
+        for (key, value) in map {
+            let key = format!("{}{}", prefix, to_snake_case(key));
+            if value.is_object() {
+                flatten_into(fields, &format!("{}.", key), value);
+            } else {
+                fields.insert(key, value.clone());
+            }
+        }
+    }
+}
+
+/// Keep only the leaves of `desired` that differ from `current`
+fn changed_fields(current: &Value, desired: &Value) -> Option<Value> {
+    match (current, desired) {
+        (Value::Object(current), Value::Object(desired)) => {
+            let changed: Map<String, Value> = desired
+                .iter()
+                .filter_map(|(key, value)| {
+                    let current = current.get(key).unwrap_or(&Value::Null);
+                    changed_fields(current, value).map(|value| (key.clone(), value))
+                })
+                .collect();
+
+            if changed.is_empty() {
+                None
+            } else {
+                Some(Value::Object(changed))
+            }
+        }
+        (current, desired) if current == desired => None,
+        (_, desired) => Some(desired.clone()),
+    }
+}
+
+/// Remove leaves whose dotted snake_case key is rejected by `keep`
+fn retain_keys(value: &mut Value, prefix: &str, keep: &dyn Fn(&str) -> bool) {
+    if let Value::Object(map) = value {
+        map.retain(|key, value| {
+            let key = format!("{}{}", prefix, to_snake_case(key));
+            if value.is_object() {
+                retain_keys(value, &format!("{}.", key), keep);
+                value.as_object().is_some_and(|m| !m.is_empty())
+            } else {
+                keep(&key)
+            }
+        });
+    }
+}
+
+/// Match a key against a pattern where `*` matches any sequence of characters
+fn key_matches(pattern: &str, key: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == key,
+        Some((prefix, rest)) => {
+            let Some(key) = key.strip_prefix(prefix) else {
+                return false;
+            };
+            if rest.is_empty() {
+                return true;
+            }
+            (0..=key.len())
+                .filter(|&i| key.is_char_boundary(i))
+                .any(|i| key_matches(rest, &key[i..]))
+        }
+    }
+}
+
+fn to_snake_case(key: &str) -> String {
+    let mut snake = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snake_case_aliases() {
+        let settings: Settings = serde_json::from_str(
+            r#"{"auto_brightness": true, "time_app": {"show_weekday": false}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(settings.auto_brightness, Some(true));
+        assert_eq!(settings.time_app.unwrap().show_weekday, Some(false));
+    }
+
+    #[test]
+    fn test_settings_diff() {
+        let current: Settings =
+            serde_json::from_str(r#"{"brightness": 100, "appTime": 10, "timeApp": {"format": 1}}"#)
+                .unwrap();
+        let desired: Settings =
+            serde_json::from_str(r#"{"brightness": 50, "appTime": 10, "timeApp": {"format": 2}}"#)
+                .unwrap();
+
+        let changes = current.diff(&desired);
+        let keys: Vec<&str> = changes.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(keys, ["brightness", "time_app.format"]);
+        assert_eq!(changes[0].current, Some(Value::from(100)));
+        assert_eq!(changes[0].desired, Some(Value::from(50)));
+    }
+
+    #[test]
+    fn test_changes_to_only_keeps_differing_fields() {
+        let current: Settings =
+            serde_json::from_str(r#"{"brightness": 100, "appTime": 10, "timeApp": {"format": 1}}"#)
+                .unwrap();
+        let desired: Settings = serde_json::from_str(
+            r#"{"brightness": 100, "appTime": 15, "timeApp": {"format": 1, "showWeekday": true}}"#,
+        )
+        .unwrap();
+
+        let changes = current.changes_to(&desired);
+        assert_eq!(changes.brightness, None);
+        assert_eq!(changes.app_time, Some(15));
+        let time_app = changes.time_app.unwrap();
+        assert_eq!(time_app.format, None);
+        assert_eq!(time_app.show_weekday, Some(true));
+
+        assert!(current.changes_to(&current).is_empty());
+    }
+
+    #[test]
+    fn test_filter_keys() {
+        let settings: Settings = serde_json::from_str(
+            r#"{"brightness": 100, "appTime": 10, "timeApp": {"format": 1, "showWeekday": true}}"#,
+        )
+        .unwrap();
+
+        let filtered = settings.filter_keys(&["time_app.*"]);
+        assert_eq!(filtered.brightness, None);
+        assert_eq!(filtered.time_app.as_ref().unwrap().format, Some(1));
+
+        let filtered = settings.filter_keys(&["brightness", "*_weekday"]);
+        assert_eq!(filtered.brightness, Some(100));
+        assert_eq!(filtered.app_time, None);
+        let time_app = filtered.time_app.unwrap();
+        assert_eq!(time_app.format, None);
+        assert_eq!(time_app.show_weekday, Some(true));
+
+        assert!(settings.filter_keys(&["nothing"]).is_empty());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut settings: Settings =
+            serde_json::from_str(r#"{"brightness": 100, "timeApp": {"format": 1}}"#).unwrap();
+        let overlay: Settings =
+            serde_json::from_str(r#"{"appTime": 15, "timeApp": {"showWeekday": true}}"#).unwrap();
+
+        settings.merge(&overlay);
+
+        assert_eq!(settings.brightness, Some(100));
+        assert_eq!(settings.app_time, Some(15));
+        let time_app = settings.time_app.unwrap();
+        assert_eq!(time_app.format, Some(1));
+        assert_eq!(time_app.show_weekday, Some(true));
+    }
+}
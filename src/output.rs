@@ -0,0 +1,386 @@
+use anyhow::Result;
+use awtrix3::ErrorKind;
+use clap::ValueEnum;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// Output format for command results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Yaml,
+    Plain,
+}
+
+impl OutputFormat {
+    /// Parse a format name from the config, e.g. `preferences.default_format`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::from_str(name, true).ok()
+    }
+
+    /// Whether the format is meant for humans rather than scripts
+    pub fn is_human(&self) -> bool {
+        matches!(self, OutputFormat::Table | OutputFormat::Plain)
+    }
+}
+
+static FORMAT: OnceCell<OutputFormat> = OnceCell::new();
+
+/// Set the output format for this run
+pub fn set_format(format: OutputFormat) {
+    let _ = FORMAT.set(format);
+}
+
+/// The output format for this run
+pub fn format() -> OutputFormat {
+    FORMAT.get().copied().unwrap_or(OutputFormat::Table)
+}
+
+/// Whether human-oriented output (progress, diffs, prompts) should be printed
+pub fn is_human() -> bool {
+    format().is_human()
+}
+
+/// Result of a command: structured data plus an optional human rendering
+#[derive(Debug, Clone)]
+pub struct CommandOutput {
+    data: Value,
+    text: Option<String>,
+}
+
+impl CommandOutput {
+    /// A status message for a completed action
+    pub fn message<S: Into<String>>(message: S) -> Self {
+        let message = message.into();
+        let mut data = Map::new();
+        data.insert("status".to_string(), Value::from("ok"));
+        data.insert("message".to_string(), Value::from(message.clone()));
+
+        Self {
+            data: Value::Object(data),
+            text: Some(message),
+        }
+    }
+
+    /// No output, for commands that write directly to stdout
+    pub fn empty() -> Self {
+        Self {
+            data: Value::Null,
+            text: Some(String::new()),
+        }
+    }
+
+    /// Whether there is nothing to print
+    pub fn is_empty(&self) -> bool {
+        self.data.is_null()
+    }
+
+    /// Structured data, rendered generically unless `text` is given
+    pub fn data<T: Serialize>(data: &T) -> Result<Self> {
+        Ok(Self {
+            data: serde_json::to_value(data)?,
+            text: None,
+        })
+    }
+
+    /// Set the human-readable rendering, used instead of the generic one
+    pub fn text<S: Into<String>>(mut self, text: S) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Add a field to the structured data
+    pub fn field<T: Serialize>(mut self, key: &str, value: T) -> Self {
+        if let Value::Object(map) = &mut self.data {
+            map.insert(
+                key.to_string(),
+                serde_json::to_value(value).unwrap_or(Value::Null),
+            );
+        }
+        self
+    }
+
+    /// Render in the given format
+    pub fn render(&self, format: OutputFormat) -> Result<String> {
+        Ok(match format {
+            OutputFormat::Json => serde_json::to_string_pretty(&self.data)?,
+            OutputFormat::Yaml => serde_yaml::to_string(&self.data)?.trim_end().to_string(),
+            OutputFormat::Plain => match &self.text {
+                Some(text) => text.clone(),
+                None => render_plain(&self.data),
+            },
+            OutputFormat::Table => match &self.text {
+                Some(text) => text.clone(),
+                None => render_table(&self.data),
+            },
+        })
+    }
+}
+
+/// Category of a command error, looking through any added context
+pub fn error_kind(error: &anyhow::Error) -> ErrorKind {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<awtrix3::AwtrixError>() {
+            return e.kind();
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return awtrix3::error::http_kind(e);
+        }
+        if cause.is::<serde_json::Error>()
+            || cause.is::<serde_yaml::Error>()
+            || cause.is::<toml::de::Error>()
+        {
+            return ErrorKind::Validation;
+        }
+    }
+    ErrorKind::Other
+}
+
+/// Suggested next step for an error kind
+pub fn error_hint(kind: ErrorKind) -> Option<&'static str> {
+    match kind {
+        ErrorKind::Unreachable => Some(
+            "Check that the device is powered on and on the same network, then run 'awtrix device test'",
+        ),
+        ErrorKind::Auth => {
+            Some("The device requires authentication; check the credentials in its web interface")
+        }
+        ErrorKind::Config => Some(
+            "Run 'awtrix device list' to see configured devices or 'awtrix device add <name> <host>' to add one",
+        ),
+        ErrorKind::Validation => Some("Run the command with --help to see accepted values"),
+        ErrorKind::Api => Some("Run 'awtrix device test' to check the device is responding normally"),
+        ErrorKind::Unsupported => Some("Update the device firmware to use this feature"),
+        ErrorKind::Timeout => Some("Raise the timeout or check what the device is showing"),
+        ErrorKind::Other => None,
+    }
+}
+
+/// Render a command error in the given format
+pub fn render_error(error: &anyhow::Error, format: OutputFormat) -> Result<String> {
+    let kind = error_kind(error);
+    let hint = error_hint(kind);
+
+    let mut object = Map::new();
+    object.insert("code".to_string(), Value::from(kind.exit_code()));
+    object.insert("kind".to_string(), Value::from(kind.as_str()));
+    object.insert("message".to_string(), Value::from(format!("{:#}", error)));
+    object.insert(
+        "hint".to_string(),
+        hint.map(Value::from).unwrap_or(Value::Null),
+    );
+    let data = serde_json::json!({ "error": object });
+
+    Ok(match format {
+        OutputFormat::Json => serde_json::to_string_pretty(&data)?,
+        OutputFormat::Yaml => serde_yaml::to_string(&data)?.trim_end().to_string(),
+        OutputFormat::Table | OutputFormat::Plain => {
+            use colored::Colorize;
+
+            let mut text = format!("{} {:#}", "Error:".red().bold(), error);
+            if let Some(hint) = hint {
+                text.push_str(&format!("\n{} {}", "Hint:".yellow().bold(), hint));
+            }
+            text
+        }
+    })
+}
+
+/// Render a value as `key: value` lines
+fn render_plain(value: &Value) -> String {
+    match value {
+        Value::Object(_) => flatten(value)
+            .into_iter()
+            .map(|(key, value)| format!("{}: {}", key, value))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Array(items) => items
+            .iter()
+            .map(render_plain)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => scalar(other),
+    }
+}
+
+/// Render a value as a table
+fn render_table(value: &Value) -> String {
+    match value {
+        // Objects wrapping a single list, like `{"effects": [...]}`, show the list
+        Value::Object(map) if map.len() == 1 && map.values().all(Value::is_array) => {
+            render_table(map.values().next().unwrap_or(&Value::Null))
+        }
+        Value::Object(_) => {
+            let mut builder = Builder::default();
+            builder.push_record(["Key", "Value"]);
+            for (key, value) in flatten(value) {
+                builder.push_record([key, value]);
+            }
+            builder.build().with(Style::rounded()).to_string()
+        }
+        Value::Array(items) if items.is_empty() => "(none)".to_string(),
+        Value::Array(items) if items.iter().all(Value::is_object) => {
+            // Columns in order of first appearance
+            let mut columns: Vec<String> = Vec::new();
+            let rows: Vec<Vec<(String, String)>> = items.iter().map(flatten).collect();
+            for row in &rows {
+                for (key, _) in row {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+
+            let mut builder = Builder::default();
+            builder.push_record(columns.clone());
+            for row in rows {
+                builder.push_record(columns.iter().map(|column| {
+                    row.iter()
+                        .find(|(key, _)| key == column)
+                        .map(|(_, value)| value.clone())
+                        .unwrap_or_default()
+                }));
+            }
+            builder.build().with(Style::rounded()).to_string()
+        }
+        Value::Array(items) => {
+            let mut builder = Builder::default();
+            for item in items {
+                builder.push_record([scalar(item)]);
+            }
+            builder.build().with(Style::rounded()).to_string()
+        }
+        other => scalar(other),
+    }
+}
+
+/// Flatten nested objects into dotted keys
+fn flatten(value: &Value) -> Vec<(String, String)> {
+    fn walk(prefix: &str, value: &Value, out: &mut Vec<(String, String)>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let key = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    walk(&key, value, out);
+                }
+            }
+            other => out.push((prefix.to_string(), scalar(other))),
+        }
+    }
+
+    let mut out = Vec::new();
+    walk("", value, &mut out);
+    out
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(scalar).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_output() {
+        let output = CommandOutput::message("Power state set to: ON").field("power", true);
+
+        assert_eq!(
+            output.render(OutputFormat::Table).unwrap(),
+            "Power state set to: ON"
+        );
+        let json: Value =
+            serde_json::from_str(&output.render(OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["status"], "ok");
+        assert_eq!(json["power"], true);
+        assert!(output
+            .render(OutputFormat::Yaml)
+            .unwrap()
+            .contains("power: true"));
+    }
+
+    #[test]
+    fn test_data_output() {
+        let output = CommandOutput::data(&serde_json::json!({
+            "uptime": 10,
+            "time_app": {"format": 1}
+        }))
+        .unwrap();
+
+        assert_eq!(
+            output.render(OutputFormat::Plain).unwrap(),
+            "time_app.format: 1\nuptime: 10"
+        );
+        let table = output.render(OutputFormat::Table).unwrap();
+        assert!(table.contains("time_app.format"));
+        assert!(table.contains("uptime"));
+    }
+
+    #[test]
+    fn test_list_table() {
+        let output = CommandOutput::data(&serde_json::json!({
+            "devices": [{"name": "lobby", "online": true}, {"name": "office"}]
+        }))
+        .unwrap();
+
+        let table = output.render(OutputFormat::Table).unwrap();
+        assert!(table.contains("name"));
+        assert!(table.contains("online"));
+        assert!(table.contains("office"));
+
+        let output = output.text("custom");
+        assert_eq!(output.render(OutputFormat::Table).unwrap(), "custom");
+        assert!(output
+            .render(OutputFormat::Json)
+            .unwrap()
+            .contains("\"office\""));
+    }
+
+    #[test]
+    fn test_error_rendering() {
+        let error = anyhow::Error::new(awtrix3::AwtrixError::Api {
+            message: "say \"no\"".to_string(),
+            code: 500,
+        })
+        .context("Failed to update settings");
+
+        assert_eq!(error_kind(&error), ErrorKind::Api);
+
+        // Quotes in the message must not break the JSON
+        let json: Value =
+            serde_json::from_str(&render_error(&error, OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["error"]["code"], 7);
+        assert_eq!(json["error"]["kind"], "api");
+        assert!(json["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("say \"no\""));
+        assert!(json["error"]["hint"].is_string());
+
+        let plain = anyhow::anyhow!("something odd");
+        assert_eq!(error_kind(&plain), ErrorKind::Other);
+        assert!(!render_error(&plain, OutputFormat::Plain)
+            .unwrap()
+            .contains("Hint"));
+    }
+
+    #[test]
+    fn test_format_names() {
+        assert_eq!(OutputFormat::from_name("table"), Some(OutputFormat::Table));
+        assert_eq!(OutputFormat::from_name("JSON"), Some(OutputFormat::Json));
+        assert_eq!(OutputFormat::from_name("xml"), None);
+    }
+}
@@ -0,0 +1,255 @@
+//! Minimal HTTP/1.1 handling for the local servers (`exporter`, `serve`, `relay`)
+//!
+//! One request per connection, which is all scrapers and webhook senders need.
+
+use anyhow::Result;
+use awtrix3::AwtrixError;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Largest request body accepted
+const MAX_BODY: usize = 1024 * 1024;
+
+/// Longest request line or header line accepted
+pub const MAX_LINE: usize = 8 * 1024;
+
+/// Most headers accepted in one request
+const MAX_HEADERS: usize = 64;
+
+/// How long a client gets to send its request, or to take the response
+pub const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An incoming request
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    /// Path without the query string
+    pub path: String,
+    pub query: HashMap<String, String>,
+    /// Header names are lowercase
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+}
+
+/// A response to send back
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &'static str, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    pub fn json(status: u16, body: &serde_json::Value) -> Self {
+        Self::new(status, "application/json", format!("{}\n", body))
+    }
+
+    pub fn not_found() -> Self {
+        Self::new(404, "text/plain", "Not found\n")
+    }
+
+    /// Response for a request that could not be read
+    pub fn read_failed(error: &std::io::Error) -> Self {
+        let status = match error.kind() {
+            ErrorKind::TimedOut => 408,
+            _ => 400,
+        };
+        Self::json(status, &serde_json::json!({ "error": error.to_string() }))
+    }
+}
+
+/// Parse a listen address like `:9877`, `127.0.0.1:9877` or `[::1]:9877`
+pub fn parse_listen(listen: &str) -> Result<SocketAddr> {
+    let full = match listen.strip_prefix(':') {
+        Some(port) => format!("0.0.0.0:{}", port),
+        None => listen.to_string(),
+    };
+
+    full.parse().map_err(|_| {
+        AwtrixError::Validation(format!(
+            "Invalid listen address '{}', expected e.g. :9877 or 127.0.0.1:9877",
+            listen
+        ))
+        .into()
+    })
+}
+
+/// Read one request from a connection
+///
+/// Lines, header count and body are size limited, and the whole request has
+/// to arrive within a few seconds so idle connections are not kept open.
+pub async fn read_request(stream: &mut TcpStream) -> std::io::Result<Request> {
+    tokio::time::timeout(IO_TIMEOUT, read(BufReader::new(stream)))
+        .await
+        .map_err(|_| std::io::Error::new(ErrorKind::TimedOut, "request not received in time"))?
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(ErrorKind::InvalidData, message)
+}
+
+async fn read<R: AsyncBufRead + Unpin>(mut reader: R) -> std::io::Result<Request> {
+    let mut line = String::new();
+    read_line(&mut reader, &mut line).await?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().ok_or_else(|| invalid("empty request"))?;
+    let target = parts
+        .next()
+        .ok_or_else(|| invalid("missing request target"))?;
+
+    let url = url::Url::parse("http://localhost")
+        .and_then(|base| base.join(target))
+        .map_err(|_| invalid("invalid request target"))?;
+
+    let mut headers = HashMap::new();
+    for count in 0.. {
+        let mut header = String::new();
+        if read_line(&mut reader, &mut header).await? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if count == MAX_HEADERS {
+            return Err(invalid("too many headers"));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    if length > MAX_BODY {
+        return Err(invalid("request body too large"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+
+    Ok(Request {
+        method: method.to_uppercase(),
+        path: url.path().to_string(),
+        query: url.query_pairs().into_owned().collect(),
+        headers,
+        body,
+    })
+}
+
+/// Read a line of at most [`MAX_LINE`] bytes
+async fn read_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    line: &mut String,
+) -> std::io::Result<usize> {
+    let read = (&mut *reader).take(MAX_LINE as u64).read_line(line).await?;
+    if read == MAX_LINE && !line.ends_with('\n') {
+        return Err(invalid("request line or header too long"));
+    }
+    Ok(read)
+}
+
+/// Compare a secret without revealing through timing how much of it matched
+pub fn secret_eq(given: &str, expected: &str) -> bool {
+    let (given, expected) = (given.as_bytes(), expected.as_bytes());
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Write a response and close the connection
+pub async fn write_response(stream: &mut TcpStream, response: Response) -> std::io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        429 => "Too Many Requests",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "",
+    };
+
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason,
+        response.content_type,
+        response.body.len()
+    );
+    let write = async {
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(response.body.as_bytes()).await?;
+        stream.shutdown().await
+    };
+    tokio::time::timeout(IO_TIMEOUT, write)
+        .await
+        .map_err(|_| std::io::Error::new(ErrorKind::TimedOut, "response not taken in time"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_listen() {
+        assert_eq!(
+            parse_listen(":9877").unwrap(),
+            "0.0.0.0:9877".parse().unwrap()
+        );
+        assert_eq!(
+            parse_listen("[::1]:9877").unwrap(),
+            "[::1]:9877".parse().unwrap()
+        );
+        assert!(parse_listen("9877").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_limits() {
+        let request = b"POST /hook?token=a HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}";
+        let request = read(&request[..]).await.unwrap();
+        assert_eq!(request.path, "/hook");
+        assert_eq!(request.header("Content-Length"), Some("2"));
+        assert_eq!(request.body, b"{}");
+
+        let long = format!("GET / HTTP/1.1\r\nX-Pad: {}\r\n\r\n", "a".repeat(MAX_LINE));
+        let error = read(long.as_bytes()).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let many = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X-Pad: a\r\n".repeat(MAX_HEADERS + 1)
+        );
+        let error = read(many.as_bytes()).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_secret_eq() {
+        assert!(secret_eq("s3cret", "s3cret"));
+        assert!(!secret_eq("s3creT", "s3cret"));
+        assert!(!secret_eq("s3cre", "s3cret"));
+        assert!(!secret_eq("", "s3cret"));
+    }
+}
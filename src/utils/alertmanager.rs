@@ -0,0 +1,288 @@
+//! Prometheus Alertmanager webhook payloads and their notifications
+
+use crate::error::{AwtrixError, Result};
+use crate::models::{Color, Notification};
+use crate::utils::template;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// Severity whose firing alerts light the indicator
+pub const CRITICAL: &str = "critical";
+
+/// Alertmanager webhook payload (version 4)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Payload {
+    pub status: String,
+    #[serde(default)]
+    pub alerts: Vec<Alert>,
+    #[serde(default)]
+    pub common_labels: HashMap<String, String>,
+    #[serde(default)]
+    pub group_key: Option<String>,
+}
+
+/// A single alert of a payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    /// `firing` or `resolved`
+    pub status: String,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+}
+
+impl Alert {
+    pub fn is_firing(&self) -> bool {
+        self.status == "firing"
+    }
+
+    /// `severity` label, `none` if unset
+    pub fn severity(&self) -> &str {
+        self.labels.get("severity").map_or("none", String::as_str)
+    }
+
+    /// Stable identity across payloads
+    pub fn id(&self) -> String {
+        self.fingerprint.clone().unwrap_or_else(|| {
+            let mut labels: Vec<_> = self.labels.iter().collect();
+            labels.sort();
+            format!("{:?}", labels)
+        })
+    }
+
+    fn name(&self) -> &str {
+        self.labels.get("alertname").map_or("alert", String::as_str)
+    }
+
+    fn summary(&self) -> Option<&str> {
+        self.annotations
+            .get("summary")
+            .or_else(|| self.annotations.get("description"))
+            .map(String::as_str)
+    }
+}
+
+/// How notifications of one severity look and sound
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeverityStyle {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+    /// Keep the notification on screen until dismissed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hold: Option<bool>,
+}
+
+/// Style for a severity, falling back to built-in defaults for
+/// `critical`, `warning`, `info` and `resolved`
+pub fn style(styles: &HashMap<String, SeverityStyle>, severity: &str) -> SeverityStyle {
+    if let Some(style) = styles.get(severity) {
+        return style.clone();
+    }
+
+    let (color, hold) = match severity {
+        CRITICAL => (Color::RED, Some(true)),
+        "warning" => (Color::ORANGE, None),
+        "resolved" => (Color::GREEN, None),
+        _ => (Color::BLUE, None),
+    };
+    SeverityStyle {
+        color: Some(color),
+        hold,
+        ..SeverityStyle::default()
+    }
+}
+
+/// Notification for one alert
+///
+/// Firing alerts use their severity's style; resolved alerts use the
+/// `resolved` style. A route template, if any, is rendered against the alert
+/// and wins over the style.
+pub fn notification(
+    alert: &Alert,
+    styles: &HashMap<String, SeverityStyle>,
+    template: Option<&Value>,
+) -> Result<Notification> {
+    let style = if alert.is_firing() {
+        style(styles, alert.severity())
+    } else {
+        style(styles, "resolved")
+    };
+
+    let text = match (alert.is_firing(), alert.summary()) {
+        (true, Some(summary)) => format!("{}: {}", alert.name(), summary),
+        (true, None) => alert.name().to_string(),
+        (false, _) => format!("Resolved: {}", alert.name()),
+    };
+
+    let mut notification = serde_json::to_value(Notification {
+        color: style.color,
+        icon: style.icon,
+        sound: style.sound,
+        // Resolved alerts must not stay on screen
+        hold: style.hold.filter(|_| alert.is_firing()),
+        ..Notification::builder().text(text).build()
+    })?;
+
+    if let (Some(template), Value::Object(fields)) = (template, &mut notification) {
+        let context = serde_json::to_value(alert)?;
+        if let Value::Object(rendered) = template::render_value(template, &context)? {
+            fields.extend(rendered);
+        }
+    }
+
+    serde_json::from_value(notification)
+        .map_err(|e| AwtrixError::Validation(format!("Invalid notification: {}", e)))
+}
+
+/// Critical alerts currently firing, tracked across payloads
+///
+/// Kept in memory only. Alertmanager sends every alert of a group with each
+/// notification, so a payload with a `groupKey` replaces what is known about
+/// that group; after a restart the state is complete again once each firing
+/// group has been re-sent (at the latest after Alertmanager's `repeat_interval`).
+#[derive(Debug, Default)]
+pub struct FiringCritical {
+    /// Per `groupKey`
+    groups: HashMap<String, HashSet<String>>,
+    /// From payloads without a `groupKey`, updated alert by alert
+    ungrouped: HashSet<String>,
+}
+
+impl FiringCritical {
+    /// Record a payload; returns whether any critical alert is still firing
+    pub fn update(&mut self, payload: &Payload) -> bool {
+        let is_critical = |alert: &&Alert| alert.is_firing() && alert.severity() == CRITICAL;
+        match &payload.group_key {
+            Some(key) => {
+                let firing: HashSet<String> = payload
+                    .alerts
+                    .iter()
+                    .filter(is_critical)
+                    .map(Alert::id)
+                    .collect();
+                if firing.is_empty() {
+                    self.groups.remove(key);
+                } else {
+                    self.groups.insert(key.clone(), firing);
+                }
+            }
+            None => {
+                for alert in &payload.alerts {
+                    if is_critical(&alert) {
+                        self.ungrouped.insert(alert.id());
+                    } else {
+                        self.ungrouped.remove(&alert.id());
+                    }
+                }
+            }
+        }
+        !self.groups.is_empty() || !self.ungrouped.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn payload(alerts: Value) -> Payload {
+        serde_json::from_value(json!({ "status": "firing", "alerts": alerts })).unwrap()
+    }
+
+    fn alert(status: &str, name: &str, severity: &str) -> Value {
+        json!({
+            "status": status,
+            "labels": { "alertname": name, "severity": severity },
+            "annotations": { "summary": "Disk almost full" },
+            "fingerprint": name,
+        })
+    }
+
+    #[test]
+    fn test_severity_styles() {
+        let mut styles = HashMap::new();
+        styles.insert(
+            "warning".to_string(),
+            SeverityStyle {
+                icon: Some(555),
+                sound: Some("beep".to_string()),
+                ..SeverityStyle::default()
+            },
+        );
+
+        let payload = payload(json!([
+            alert("firing", "DiskFull", "critical"),
+            alert("firing", "HighLoad", "warning"),
+            alert("resolved", "DiskFull", "critical"),
+        ]));
+
+        let critical = notification(&payload.alerts[0], &styles, None).unwrap();
+        assert_eq!(critical.text.as_deref(), Some("DiskFull: Disk almost full"));
+        assert_eq!(critical.color, Some(Color::RED));
+        assert_eq!(critical.hold, Some(true));
+
+        let warning = notification(&payload.alerts[1], &styles, None).unwrap();
+        assert_eq!(warning.icon, Some(555));
+        assert_eq!(warning.sound.as_deref(), Some("beep"));
+
+        let resolved = notification(&payload.alerts[2], &styles, None).unwrap();
+        assert_eq!(resolved.text.as_deref(), Some("Resolved: DiskFull"));
+        assert_eq!(resolved.color, Some(Color::GREEN));
+        assert_eq!(resolved.hold, None);
+    }
+
+    #[test]
+    fn test_template_overrides_style() {
+        let payload = payload(json!([alert("firing", "DiskFull", "critical")]));
+        let template = json!({ "text": "{{labels.alertname}} ({{labels.severity}})" });
+
+        let notification =
+            notification(&payload.alerts[0], &HashMap::new(), Some(&template)).unwrap();
+        assert_eq!(notification.text.as_deref(), Some("DiskFull (critical)"));
+        assert_eq!(notification.color, Some(Color::RED));
+    }
+
+    #[test]
+    fn test_firing_critical() {
+        let mut firing = FiringCritical::default();
+
+        let first = payload(json!([
+            alert("firing", "DiskFull", "critical"),
+            alert("firing", "NodeDown", "critical"),
+            alert("firing", "HighLoad", "warning"),
+        ]));
+        assert!(firing.update(&first));
+
+        let partly = payload(json!([alert("resolved", "DiskFull", "critical")]));
+        assert!(firing.update(&partly));
+
+        let cleared = payload(json!([alert("resolved", "NodeDown", "critical")]));
+        assert!(!firing.update(&cleared));
+    }
+
+    #[test]
+    fn test_group_payload_replaces_state() {
+        let grouped = |alerts: Value| {
+            let mut payload = payload(alerts);
+            payload.group_key = Some("{}:{alertname=\"NodeDown\"}".to_string());
+            payload
+        };
+        let mut firing = FiringCritical::default();
+
+        assert!(firing.update(&grouped(json!([alert("firing", "NodeDown", "critical")]))));
+
+        // An alert missing from its group's next payload is no longer firing,
+        // even if its resolution was never seen
+        assert!(!firing.update(&grouped(json!([alert("firing", "NodeDown", "warning")]))));
+    }
+}
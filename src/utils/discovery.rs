@@ -1,7 +1,18 @@
 use anyhow::Result;
 #[cfg(feature = "discovery")]
-use mdns_sd::{ServiceDaemon, ServiceEvent};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Service advertised by AWTRIX3 firmware next to `_http._tcp`
+pub const AWTRIX_SERVICE: &str = "_awtrix._tcp.local.";
+
+/// Generic HTTP service, which AWTRIX3 and many other devices advertise
+pub const HTTP_SERVICE: &str = "_http._tcp.local.";
 
 /// Discovered AWTRIX3 device
 #[derive(Debug, Clone)]
@@ -9,64 +20,492 @@ pub struct DiscoveredDevice {
     pub name: String,
     pub host: String,
     pub port: u16,
-    pub txt_records: std::collections::HashMap<String, String>,
+    pub txt_records: HashMap<String, String>,
+    /// Stable device ID (the firmware `uid`), when known
+    pub id: Option<String>,
+}
+
+impl DiscoveredDevice {
+    /// Host to connect to, including the port when it is not 80
+    pub fn address(&self) -> String {
+        let host = if self.host.contains(':') {
+            // IPv6 literals need brackets in URLs
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+
+        if self.port == 80 {
+            host
+        } else {
+            format!("{}:{}", host, self.port)
+        }
+    }
+
+    /// Config name suggested from the hostname, e.g. `kitchen` for `awtrix-kitchen.local`
+    pub fn suggested_name(&self) -> String {
+        let name = self
+            .name
+            .trim_end_matches('.')
+            .trim_end_matches(".local")
+            .to_lowercase();
+        let stripped = ["awtrix_", "awtrix-", "awtrix3_", "awtrix3-"]
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix))
+            .filter(|rest| !rest.is_empty())
+            .unwrap_or(&name);
+
+        let suggestion: String = stripped
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+
+        if suggestion.is_empty() {
+            "awtrix".to_string()
+        } else {
+            suggestion
+        }
+    }
+
+    /// Whether two results describe the same device
+    pub fn same_device(&self, other: &DiscoveredDevice) -> bool {
+        match (&self.id, &other.id) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+            _ => self.address() == other.address(),
+        }
+    }
+}
+
+/// Add `device` to `devices` unless it is already there, filling in what the
+/// earlier result was missing
+pub fn merge_device(devices: &mut Vec<DiscoveredDevice>, device: DiscoveredDevice) {
+    match devices.iter_mut().find(|known| known.same_device(&device)) {
+        Some(known) => {
+            for (key, value) in device.txt_records {
+                known.txt_records.entry(key).or_insert(value);
+            }
+            if known.id.is_none() {
+                known.id = device.id;
+            }
+        }
+        None => devices.push(device),
+    }
 }
 
 /// Discover AWTRIX3 devices on the network
+///
+/// Browses for the AWTRIX3 service and generic HTTP services, returning
+/// early once `expected` devices have been found.
+#[cfg(feature = "discovery")]
+pub async fn discover_devices(
+    timeout: Duration,
+    expected: Option<usize>,
+) -> Result<Vec<DiscoveredDevice>> {
+    browse(timeout, |devices| {
+        expected.is_some_and(|expected| devices.len() >= expected)
+    })
+    .await
+}
+
+/// Browse mDNS until `timeout` or until `done` returns true for the results so far
 #[cfg(feature = "discovery")]
-pub async fn discover_devices(timeout: Duration) -> Result<Vec<DiscoveredDevice>> {
+async fn browse(
+    timeout: Duration,
+    done: impl Fn(&[DiscoveredDevice]) -> bool,
+) -> Result<Vec<DiscoveredDevice>> {
     let mdns = ServiceDaemon::new()?;
-    let service_type = "_http._tcp.local.";
-    let receiver = mdns.browse(service_type)?;
+    let awtrix = mdns.browse(AWTRIX_SERVICE)?;
+    let http = mdns.browse(HTTP_SERVICE)?;
 
     let mut devices = Vec::new();
-    let start = std::time::Instant::now();
-
-    while start.elapsed() < timeout {
-        if let Ok(event) = receiver.recv_timeout(Duration::from_millis(100)) {
-            if let ServiceEvent::ServiceResolved(info) = event {
-                // Check if this is an AWTRIX device by looking at TXT records or hostname
-                if is_awtrix_device(&info) {
-                    let device = DiscoveredDevice {
-                        name: info.get_hostname().to_string(),
-                        host: info
-                            .get_addresses()
-                            .iter()
-                            .next()
-                            .map(|addr| addr.to_string())
-                            .unwrap_or_default(),
-                        port: info.get_port(),
-                        txt_records: std::collections::HashMap::new(), // TODO: Fix TXT records parsing
-                    };
-                    devices.push(device);
-                }
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    while !done(&devices) {
+        let event = tokio::select! {
+            event = awtrix.recv_async() => event,
+            event = http.recv_async() => event,
+            _ = tokio::time::sleep_until(deadline) => break,
+        };
+
+        if let Ok(ServiceEvent::ServiceResolved(info)) = event {
+            if let Some(device) = device_from_service(&info) {
+                merge_device(&mut devices, device);
             }
         }
     }
 
+    // Wait for the daemon to stop while the receivers are still alive, otherwise
+    // it logs errors about closed channels. Failures here do not affect the results.
+    if let Ok(status) = mdns.shutdown() {
+        let _ = status.recv_async().await;
+    }
+
     Ok(devices)
 }
 
 #[cfg(feature = "discovery")]
-fn is_awtrix_device(info: &mdns_sd::ServiceInfo) -> bool {
-    // Check for AWTRIX-specific indicators
-    let hostname = info.get_hostname().to_lowercase();
+fn device_from_service(info: &ServiceInfo) -> Option<DiscoveredDevice> {
+    let txt_records: HashMap<String, String> = info
+        .get_properties()
+        .iter()
+        .map(|property| {
+            (
+                property.key().to_lowercase(),
+                property.val_str().to_string(),
+            )
+        })
+        .collect();
 
-    // Check hostname patterns
-    if hostname.contains("awtrix") || hostname.contains("ulanzi") {
+    if !is_awtrix_device(info.get_type(), info.get_hostname(), &txt_records) {
+        return None;
+    }
+
+    let host = select_address(info.get_addresses().iter().copied())?;
+    let hostname = info
+        .get_hostname()
+        .trim_end_matches('.')
+        .trim_end_matches(".local")
+        .to_string();
+
+    Some(DiscoveredDevice {
+        name: txt_records.get("name").cloned().unwrap_or(hostname),
+        host: host.to_string(),
+        port: info.get_port(),
+        id: txt_records.get("id").cloned(),
+        txt_records,
+    })
+}
+
+/// Identify AWTRIX3 devices by service and TXT data, falling back to the hostname
+pub fn is_awtrix_device(
+    service_type: &str,
+    hostname: &str,
+    txt_records: &HashMap<String, String>,
+) -> bool {
+    if service_type.eq_ignore_ascii_case(AWTRIX_SERVICE) {
+        return true;
+    }
+
+    let txt = |key: &str| {
+        txt_records
+            .get(key)
+            .map(|value| value.to_lowercase())
+            .unwrap_or_default()
+    };
+    if txt("type").contains("awtrix") || txt("id").starts_with("awtrix") {
         return true;
     }
 
-    // TODO: Check TXT records for AWTRIX-specific keys when API is fixed
-    // For now, just use hostname matching
+    // Other ESP devices often advertise HTTP too, so the hostname is a last resort
+    let hostname = hostname.to_lowercase();
+    hostname.contains("awtrix") || hostname.contains("ulanzi")
+}
+
+/// Pick the address to connect to: IPv4 first, then routable IPv6
+pub fn select_address(addresses: impl IntoIterator<Item = IpAddr>) -> Option<IpAddr> {
+    let rank = |address: &IpAddr| match address {
+        IpAddr::V4(v4) if v4.is_link_local() => 1,
+        IpAddr::V4(_) => 0,
+        // Link-local IPv6 needs a zone ID, which URLs cannot carry portably
+        IpAddr::V6(v6) if (v6.segments()[0] & 0xffc0) == 0xfe80 => 3,
+        IpAddr::V6(_) => 2,
+    };
+
+    addresses
+        .into_iter()
+        .filter(|address| !address.is_loopback() && !address.is_unspecified())
+        .min_by_key(|address| (rank(address), *address))
+}
+
+/// Scan an IPv4 subnet like `192.168.1.0/24` for AWTRIX3 devices
+///
+/// Every host is probed for the `/api/stats` signature, with at most
+/// `concurrency` probes in flight and `timeout` per probe. This finds devices
+/// mDNS cannot, e.g. across VLANs or on networks that block multicast.
+pub async fn scan_subnet(
+    cidr: &str,
+    timeout: Duration,
+    concurrency: usize,
+) -> Result<Vec<DiscoveredDevice>> {
+    let hosts = parse_cidr(cidr)?;
+    let http = reqwest::Client::builder()
+        .timeout(timeout)
+        .connect_timeout(timeout)
+        .build()?;
+    let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+
+    let mut probes = JoinSet::new();
+    for host in hosts {
+        let http = http.clone();
+        let permits = permits.clone();
+        probes.spawn(async move {
+            let _permit = permits.acquire_owned().await.ok()?;
+            probe(&host.to_string(), http).await
+        });
+    }
+
+    let mut devices = Vec::new();
+    while let Some(result) = probes.join_next().await {
+        if let Ok(Some(device)) = result {
+            devices.push(device);
+        }
+    }
 
-    false
+    // Probes finish in any order
+    devices.sort_by_key(|device| device.host.parse::<Ipv4Addr>().ok());
+    Ok(devices)
+}
+
+/// Check whether `host` (with optional port) answers like an AWTRIX3 device
+pub async fn probe(host: &str, http: reqwest::Client) -> Option<DiscoveredDevice> {
+    let client = crate::Client::with_client(host, http).ok()?;
+    let response = client.get("/api/stats").await.ok()?;
+    let stats = crate::Client::get_json_value(response).await.ok()?;
+
+    if !is_awtrix_stats(&stats) {
+        return None;
+    }
+
+    let (host, port) = match host.rsplit_once(':') {
+        Some((host, port)) => (host.to_string(), port.parse().unwrap_or(80)),
+        None => (host.to_string(), 80),
+    };
+
+    let id = stats["uid"].as_str().map(str::to_string);
+    Some(DiscoveredDevice {
+        name: id.clone().unwrap_or_else(|| host.clone()),
+        host,
+        port,
+        txt_records: HashMap::new(),
+        id,
+    })
+}
+
+/// Whether a `/api/stats` response carries the AWTRIX3 fields
+fn is_awtrix_stats(stats: &serde_json::Value) -> bool {
+    let has = |key: &str| stats.get(key).is_some();
+    stats.is_object() && has("matrix") && (has("uid") || has("app"))
+}
+
+/// Expand an IPv4 CIDR into its host addresses
+pub fn parse_cidr(cidr: &str) -> Result<Vec<Ipv4Addr>> {
+    let invalid = || anyhow::anyhow!("Invalid subnet '{}'. Use e.g. 192.168.1.0/24", cidr);
+
+    let (address, prefix) = match cidr.split_once('/') {
+        Some((address, prefix)) => (address, prefix.parse::<u32>().map_err(|_| invalid())?),
+        None => (cidr, 32),
+    };
+    let address: Ipv4Addr = address.parse().map_err(|_| invalid())?;
+
+    if prefix > 32 {
+        return Err(invalid());
+    }
+    if prefix < 16 {
+        return Err(anyhow::anyhow!(
+            "Subnet '{}' is too large to scan. Use a /16 or smaller",
+            cidr
+        ));
+    }
+
+    let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+    let network = u32::from(address) & mask;
+    let broadcast = network | !mask;
+
+    // Skip the network and broadcast addresses where they exist
+    let (first, last) = if prefix >= 31 {
+        (network, broadcast)
+    } else {
+        (network + 1, broadcast - 1)
+    };
+
+    Ok((first..=last).map(Ipv4Addr::from).collect())
+}
+
+/// Find a device by its ID, first with mDNS and then by scanning the /24
+/// around its last known IPv4 address
+pub async fn locate(
+    id: &str,
+    last_host: Option<&str>,
+    timeout: Duration,
+) -> Result<Option<DiscoveredDevice>> {
+    let matches = |device: &DiscoveredDevice| {
+        device
+            .id
+            .as_deref()
+            .is_some_and(|found| found.eq_ignore_ascii_case(id))
+    };
+
+    // mDNS may be unavailable or blocked; the scan below still has a chance
+    #[cfg(feature = "discovery")]
+    if let Ok(devices) = browse(timeout, |devices| devices.iter().any(matches)).await {
+        if let Some(found) = devices.into_iter().find(|device| matches(device)) {
+            return Ok(Some(found));
+        }
+    }
+
+    let last_ip = last_host
+        .and_then(|host| host.split(':').next())
+        .and_then(|host| host.parse::<Ipv4Addr>().ok());
+
+    match last_ip {
+        Some(ip) => {
+            let probe_timeout = timeout.min(Duration::from_secs(1));
+            let devices = scan_subnet(&format!("{}/24", ip), probe_timeout, 64).await?;
+            Ok(devices.into_iter().find(|device| matches(device)))
+        }
+        None => Ok(None),
+    }
 }
 
 /// Discover devices (stub for when discovery feature is disabled)
 #[cfg(not(feature = "discovery"))]
-pub async fn discover_devices(_timeout: Duration) -> Result<Vec<DiscoveredDevice>> {
+pub async fn discover_devices(
+    _timeout: Duration,
+    _expected: Option<usize>,
+) -> Result<Vec<DiscoveredDevice>> {
     Err(anyhow::anyhow!(
         "Device discovery is not available. Compile with --features discovery"
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_parse_cidr() {
+        let hosts = parse_cidr("192.168.1.77/24").unwrap();
+        assert_eq!(hosts.len(), 254);
+        assert_eq!(hosts[0], Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(hosts[253], Ipv4Addr::new(192, 168, 1, 254));
+
+        assert_eq!(
+            parse_cidr("10.0.0.5").unwrap(),
+            [Ipv4Addr::new(10, 0, 0, 5)]
+        );
+        assert_eq!(parse_cidr("10.0.0.4/31").unwrap().len(), 2);
+        assert!(parse_cidr("10.0.0.0/8").is_err());
+        assert!(parse_cidr("10.0.0/24").is_err());
+        assert!(parse_cidr("10.0.0.0/33").is_err());
+    }
+
+    fn txt(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn device(host: &str, id: Option<&str>) -> DiscoveredDevice {
+        DiscoveredDevice {
+            name: host.to_string(),
+            host: host.to_string(),
+            port: 80,
+            txt_records: HashMap::new(),
+            id: id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_is_awtrix_device() {
+        let none = HashMap::new();
+        assert!(is_awtrix_device(AWTRIX_SERVICE, "lobby.local.", &none));
+        assert!(is_awtrix_device(
+            HTTP_SERVICE,
+            "esp-1234.local.",
+            &txt(&[("type", "awtrix3")])
+        ));
+        assert!(is_awtrix_device(HTTP_SERVICE, "awtrix_ab12.local.", &none));
+        // A generic ESP web server is not an AWTRIX
+        assert!(!is_awtrix_device(
+            HTTP_SERVICE,
+            "esp-1234.local.",
+            &txt(&[("board", "esp32")])
+        ));
+    }
+
+    #[test]
+    fn test_select_address() {
+        let v4: IpAddr = "192.168.1.20".parse().unwrap();
+        let v6: IpAddr = "2001:db8::20".parse().unwrap();
+        let link_local: IpAddr = "fe80::1".parse().unwrap();
+
+        assert_eq!(select_address([link_local, v6, v4]), Some(v4));
+        assert_eq!(select_address([link_local, v6]), Some(v6));
+        assert_eq!(select_address([]), None);
+
+        let mut device = device("2001:db8::20", None);
+        assert_eq!(device.address(), "[2001:db8::20]");
+        device.port = 8080;
+        assert_eq!(device.address(), "[2001:db8::20]:8080");
+    }
+
+    #[test]
+    fn test_suggested_name() {
+        let mut found = device("192.168.1.20", None);
+        found.name = "awtrix-kitchen.local.".to_string();
+        assert_eq!(found.suggested_name(), "kitchen");
+
+        found.name = "Lobby Display".to_string();
+        assert_eq!(found.suggested_name(), "lobby_display");
+
+        found.name = "awtrix_".to_string();
+        assert_eq!(found.suggested_name(), "awtrix_");
+    }
+
+    #[test]
+    fn test_merge_by_id() {
+        let mut devices = Vec::new();
+        merge_device(&mut devices, device("192.168.1.20", Some("awtrix_ab12")));
+        // Same device seen over IPv6
+        merge_device(&mut devices, device("2001:db8::20", Some("AWTRIX_AB12")));
+        merge_device(&mut devices, device("192.168.1.21", Some("awtrix_cd34")));
+        // Same address without an ID
+        merge_device(&mut devices, device("192.168.1.21", None));
+
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].host, "192.168.1.20");
+    }
+
+    #[tokio::test]
+    async fn test_probe() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/stats"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "uid": "awtrix_abc123",
+                "matrix": true,
+                "app": "Time"
+            })))
+            .mount(&server)
+            .await;
+
+        let host = server.address().to_string();
+        let device = probe(&host, reqwest::Client::new()).await.unwrap();
+        assert_eq!(device.name, "awtrix_abc123");
+        assert_eq!(device.id.as_deref(), Some("awtrix_abc123"));
+        assert_eq!(device.host, "127.0.0.1");
+        assert_eq!(device.port, server.address().port());
+    }
+
+    #[tokio::test]
+    async fn test_probe_ignores_other_devices() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/stats"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"temp": 21})))
+            .mount(&server)
+            .await;
+
+        let host = server.address().to_string();
+        assert!(probe(&host, reqwest::Client::new()).await.is_none());
+    }
+}
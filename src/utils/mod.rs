@@ -1,4 +1,7 @@
+pub mod alertmanager;
 pub mod discovery;
+pub mod template;
+pub mod webhook;
 
 use anyhow::Result;
 
@@ -9,6 +12,26 @@ pub fn parse_color_placeholder(input: &str) -> Result<String> {
     Ok(input.to_string())
 }
 
+/// Widen an `f32` sensor reading to `f64` without exposing float noise like
+/// `21.700000762939453`
+pub fn decimal(value: f32) -> f64 {
+    value.to_string().parse().unwrap_or(value.into())
+}
+
+/// Seconds as `3d 4h`, `1h 5m`, `5m 3s` or `42s`
+pub fn format_duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
 /// Format output based on CLI preferences
 pub fn format_output<T: serde::Serialize>(data: &T, json: bool) -> Result<String> {
     if json {
@@ -24,6 +47,20 @@ pub fn format_output<T: serde::Serialize>(data: &T, json: bool) -> Result<String
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_decimal() {
+        assert_eq!(decimal(21.7), 21.7);
+        assert_eq!(decimal(-3.25), -3.25);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(42), "42s");
+        assert_eq!(format_duration(65), "1m 5s");
+        assert_eq!(format_duration(4 * 3600 + 12 * 60), "4h 12m");
+        assert_eq!(format_duration(3 * 86400 + 4 * 3600), "3d 4h");
+    }
+
     #[test]
     fn test_format_output() {
         let data = serde_json::json!({"test": "value"});
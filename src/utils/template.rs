@@ -0,0 +1,462 @@
+//! `{{placeholder}}` templates rendered against JSON data
+//!
+//! Placeholders are dotted paths into the data, with numeric segments
+//! indexing arrays: `{{repository.name}}`, `{{alerts.0.labels.severity}}`.
+//! Missing values render as an empty string, since webhook payloads vary.
+//!
+//! Helpers format values: `{{upper name}}`, `{{lower name}}`,
+//! `{{truncate title 20}}`, `{{number load 1}}`, `{{duration seconds}}` and
+//! `{{default branch "main"}}`. Sections render conditionally on a value
+//! being set and not `false`, `0` or empty, or on comparing it:
+//! `{{#if error}}…{{else}}…{{/if}}`, `{{#if status == "success"}}…{{/if}}`.
+
+use crate::error::{AwtrixError, Result};
+use crate::models::Notification;
+use serde_json::Value;
+
+/// Parsed template piece
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    /// A value, optionally passed through a helper
+    Expr {
+        helper: Option<String>,
+        args: Vec<Arg>,
+    },
+    If {
+        condition: Condition,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Arg {
+    Path(String),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Truthy(Arg),
+    Equals(Arg, Arg),
+    NotEquals(Arg, Arg),
+}
+
+const HELPERS: &[&str] = &[
+    "upper", "lower", "truncate", "number", "duration", "default",
+];
+
+fn invalid(template: &str, reason: impl std::fmt::Display) -> AwtrixError {
+    AwtrixError::Validation(format!("Invalid template '{}': {}", template, reason))
+}
+
+/// Split a tag into words, keeping quoted strings together
+fn words(tag: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in tag.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn arg(word: &str) -> Arg {
+    if let Some(text) = word.strip_prefix('"').and_then(|w| w.strip_suffix('"')) {
+        Arg::Literal(Value::String(text.to_string()))
+    } else if let Ok(number) = serde_json::from_str::<serde_json::Number>(word) {
+        Arg::Literal(Value::Number(number))
+    } else if let Ok(boolean) = word.parse::<bool>() {
+        Arg::Literal(Value::Bool(boolean))
+    } else {
+        Arg::Path(word.to_string())
+    }
+}
+
+fn expr(template: &str, tag: &str) -> Result<Node> {
+    let words = words(tag);
+    match words.as_slice() {
+        [] => Err(invalid(template, "empty placeholder")),
+        [single] => Ok(Node::Expr {
+            helper: None,
+            args: vec![arg(single)],
+        }),
+        [helper, args @ ..] if HELPERS.contains(&helper.as_str()) => Ok(Node::Expr {
+            helper: Some(helper.clone()),
+            args: args.iter().map(|word| arg(word)).collect(),
+        }),
+        [helper, ..] => Err(invalid(template, format!("unknown helper '{}'", helper))),
+    }
+}
+
+fn condition(template: &str, tag: &str) -> Result<Condition> {
+    let words = words(tag);
+    match words.as_slice() {
+        [value] => Ok(Condition::Truthy(arg(value))),
+        [left, op, right] if op == "==" => Ok(Condition::Equals(arg(left), arg(right))),
+        [left, op, right] if op == "!=" => Ok(Condition::NotEquals(arg(left), arg(right))),
+        _ => Err(invalid(template, format!("invalid condition '{}'", tag))),
+    }
+}
+
+/// Where a list of nodes being parsed ends
+#[derive(PartialEq)]
+enum Terminator {
+    Eof,
+    Else,
+    EndIf,
+}
+
+fn parse(template: &str) -> Result<Vec<Node>> {
+    let mut rest = template;
+    match parse_nodes(template, &mut rest)? {
+        (nodes, Terminator::Eof) => Ok(nodes),
+        (_, Terminator::Else) => Err(invalid(template, "'{{else}}' outside of '{{#if}}'")),
+        (_, Terminator::EndIf) => Err(invalid(template, "'{{/if}}' without '{{#if}}'")),
+    }
+}
+
+fn parse_nodes(template: &str, rest: &mut &str) -> Result<(Vec<Node>, Terminator)> {
+    let mut nodes = Vec::new();
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            nodes.push(Node::Text(rest[..start].to_string()));
+        }
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| invalid(template, "unclosed '{{'"))?;
+        let tag = rest[start + 2..start + end].trim().to_string();
+        *rest = &rest[start + end + 2..];
+
+        if let Some(tag) = tag.strip_prefix("#if") {
+            let condition = condition(template, tag)?;
+            let (then, end) = parse_nodes(template, rest)?;
+            let otherwise = match end {
+                Terminator::Else => match parse_nodes(template, rest)? {
+                    (otherwise, Terminator::EndIf) => otherwise,
+                    _ => return Err(invalid(template, "missing '{{/if}}'")),
+                },
+                Terminator::EndIf => Vec::new(),
+                Terminator::Eof => return Err(invalid(template, "missing '{{/if}}'")),
+            };
+            nodes.push(Node::If {
+                condition,
+                then,
+                otherwise,
+            });
+        } else if tag == "else" {
+            return Ok((nodes, Terminator::Else));
+        } else if tag == "/if" {
+            return Ok((nodes, Terminator::EndIf));
+        } else {
+            nodes.push(expr(template, &tag)?);
+        }
+    }
+
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest.to_string()));
+        *rest = "";
+    }
+    Ok((nodes, Terminator::Eof))
+}
+
+/// Look up a dotted path like `alerts.0.labels.severity`
+pub fn lookup<'a>(data: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(data, |value, segment| match value {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => value.get(segment),
+        })
+}
+
+/// Text form of a value: strings unquoted, null empty, containers as JSON
+pub fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn resolve(arg: &Arg, data: &Value) -> Value {
+    match arg {
+        Arg::Path(path) => lookup(data, path).cloned().unwrap_or(Value::Null),
+        Arg::Literal(value) => value.clone(),
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+/// Number from a JSON number or numeric string
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn evaluate(helper: Option<&str>, args: &[Arg], data: &Value) -> Value {
+    let values: Vec<Value> = args.iter().map(|arg| resolve(arg, data)).collect();
+    let value = values.first().cloned().unwrap_or(Value::Null);
+    let count = values
+        .get(1)
+        .and_then(as_number)
+        .map(|n| n.max(0.0) as usize);
+
+    match helper {
+        None => value,
+        Some("upper") => Value::String(to_text(&value).to_uppercase()),
+        Some("lower") => Value::String(to_text(&value).to_lowercase()),
+        Some("truncate") => {
+            let text = to_text(&value);
+            let max = count.unwrap_or(usize::MAX);
+            if text.chars().count() > max {
+                let cut: String = text.chars().take(max.saturating_sub(1)).collect();
+                Value::String(format!("{}…", cut))
+            } else {
+                Value::String(text)
+            }
+        }
+        Some("number") => match as_number(&value) {
+            Some(n) => Value::String(format!("{:.*}", count.unwrap_or(0), n)),
+            None => Value::String(to_text(&value)),
+        },
+        Some("duration") => match as_number(&value) {
+            Some(n) => Value::String(super::format_duration(n.max(0.0) as u64)),
+            None => Value::String(to_text(&value)),
+        },
+        Some("default") if truthy(&value) => value,
+        Some("default") => values.get(1).cloned().unwrap_or(Value::Null),
+        Some(_) => Value::Null,
+    }
+}
+
+fn holds(condition: &Condition, data: &Value) -> bool {
+    let same =
+        |left: &Arg, right: &Arg| to_text(&resolve(left, data)) == to_text(&resolve(right, data));
+    match condition {
+        Condition::Truthy(arg) => truthy(&resolve(arg, data)),
+        Condition::Equals(left, right) => same(left, right),
+        Condition::NotEquals(left, right) => !same(left, right),
+    }
+}
+
+fn render_nodes(nodes: &[Node], data: &Value, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Expr { helper, args } => {
+                out.push_str(&to_text(&evaluate(helper.as_deref(), args, data)))
+            }
+            Node::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let branch = if holds(condition, data) {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, data, out);
+            }
+        }
+    }
+}
+
+/// Render a template string
+pub fn render(template: &str, data: &Value) -> Result<String> {
+    let mut out = String::new();
+    render_nodes(&parse(template)?, data, &mut out);
+    Ok(out)
+}
+
+/// Render every string inside a JSON template
+///
+/// A string that is a single placeholder, like `"{{build.number}}"`, takes
+/// the value's own type, so numeric and boolean fields keep their types.
+pub fn render_value(template: &Value, data: &Value) -> Result<Value> {
+    match template {
+        Value::String(s) => {
+            let nodes = parse(s)?;
+            if let [Node::Expr { helper, args }] = nodes.as_slice() {
+                return Ok(evaluate(helper.as_deref(), args, data));
+            }
+            let mut out = String::new();
+            render_nodes(&nodes, data, &mut out);
+            Ok(Value::String(out))
+        }
+        Value::Array(items) => items
+            .iter()
+            .map(|item| render_value(item, data))
+            .collect::<Result<_>>()
+            .map(Value::Array),
+        Value::Object(map) => {
+            let mut rendered = serde_json::Map::new();
+            for (key, value) in map {
+                let value = render_value(value, data)?;
+                // Missing values leave the field unset rather than null
+                if !value.is_null() {
+                    rendered.insert(key.clone(), value);
+                }
+            }
+            Ok(Value::Object(rendered))
+        }
+        other => Ok(other.clone()),
+    }
+}
+
+/// Render a notification-shaped JSON template into a [`Notification`]
+pub fn render_notification(template: &Value, data: &Value) -> Result<Notification> {
+    serde_json::from_value(render_value(template, data)?)
+        .map_err(|e| AwtrixError::Validation(format!("Invalid notification: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render() {
+        let data = json!({
+            "repository": { "name": "awtrix3-rs" },
+            "alerts": [{ "labels": { "severity": "critical" } }],
+            "count": 3,
+        });
+
+        assert_eq!(
+            render("{{ repository.name }}: {{count}} alerts", &data).unwrap(),
+            "awtrix3-rs: 3 alerts"
+        );
+        assert_eq!(
+            render("{{alerts.0.labels.severity}}", &data).unwrap(),
+            "critical"
+        );
+        assert_eq!(render("[{{missing.field}}]", &data).unwrap(), "[]");
+        assert!(render("{{oops", &data).is_err());
+    }
+
+    #[test]
+    fn test_render_value_keeps_types() {
+        let template = json!({
+            "text": "Build #{{build}}",
+            "icon": "{{icon}}",
+            "hold": "{{critical}}",
+            "sound": "{{missing}}",
+        });
+        let data = json!({ "build": 42, "icon": 1234, "critical": true });
+
+        assert_eq!(
+            render_value(&template, &data).unwrap(),
+            json!({ "text": "Build #42", "icon": 1234, "hold": true })
+        );
+    }
+
+    #[test]
+    fn test_helpers() {
+        let data = json!({
+            "name": "Deploy",
+            "title": "A very long release title",
+            "load": 0.4567,
+            "elapsed": 3930,
+            "branch": "",
+        });
+
+        assert_eq!(
+            render("{{upper name}} {{lower name}}", &data).unwrap(),
+            "DEPLOY deploy"
+        );
+        assert_eq!(
+            render("{{truncate title 10}}", &data).unwrap(),
+            "A very lo…"
+        );
+        assert_eq!(render("{{truncate name 10}}", &data).unwrap(), "Deploy");
+        assert_eq!(render("{{number load 2}}", &data).unwrap(), "0.46");
+        assert_eq!(render("{{duration elapsed}}", &data).unwrap(), "1h 5m");
+        assert_eq!(render("{{duration 42}}", &data).unwrap(), "42s");
+        assert_eq!(
+            render("{{default branch \"main\"}}", &data).unwrap(),
+            "main"
+        );
+        assert!(render("{{shout name}}", &data).is_err());
+    }
+
+    #[test]
+    fn test_conditionals() {
+        let template = "{{#if failed}}✗ {{name}}{{else}}✓ {{name}}{{/if}}";
+        assert_eq!(
+            render(template, &json!({ "name": "CI", "failed": true })).unwrap(),
+            "✗ CI"
+        );
+        assert_eq!(
+            render(template, &json!({ "name": "CI", "failed": 0 })).unwrap(),
+            "✓ CI"
+        );
+
+        let template =
+            "{{#if status == \"success\"}}ok{{/if}}{{#if status != \"success\"}}{{status}}{{/if}}";
+        assert_eq!(
+            render(template, &json!({ "status": "success" })).unwrap(),
+            "ok"
+        );
+        assert_eq!(
+            render(template, &json!({ "status": "failed" })).unwrap(),
+            "failed"
+        );
+
+        assert_eq!(
+            render(
+                "{{#if a}}{{#if b}}ab{{else}}a{{/if}}{{/if}}",
+                &json!({ "a": 1 })
+            )
+            .unwrap(),
+            "a"
+        );
+        assert!(render("{{#if a}}unclosed", &json!({})).is_err());
+        assert!(render("stray {{/if}}", &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_render_notification() {
+        let template = json!({
+            "text": "{{service}} {{#if ok}}up{{else}}down{{/if}}",
+            "icon": "{{icon}}",
+            "color": "{{#if ok}}#00FF00{{else}}#FF0000{{/if}}",
+        });
+
+        let notification =
+            render_notification(&template, &json!({ "service": "api", "icon": 7 })).unwrap();
+        assert_eq!(notification.text.as_deref(), Some("api down"));
+        assert_eq!(notification.icon, Some(7));
+        assert_eq!(notification.color, Some(crate::Color::RED));
+    }
+}
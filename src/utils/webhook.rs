@@ -0,0 +1,176 @@
+//! Turning webhook payloads from other tools into notifications
+
+use crate::error::{AwtrixError, Result};
+use crate::models::{Color, Notification};
+use crate::utils::template;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Payload format of a webhook route
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// Any JSON; by default the payload itself is the notification
+    #[default]
+    Generic,
+    GitHub,
+    GitLab,
+    Grafana,
+    Alertmanager,
+}
+
+/// Data a template is rendered against: the payload, plus `event` from the
+/// sender's event header (GitHub, GitLab) and `outcome`, the normalized
+/// result of `success`, `failure` or `other`
+pub fn context(format: WebhookFormat, event: Option<&str>, payload: &Value) -> Value {
+    let mut context = payload.clone();
+    if let Value::Object(map) = &mut context {
+        if let Some(event) = event {
+            map.entry("event").or_insert_with(|| json!(event));
+        }
+        map.insert("outcome".to_string(), json!(outcome(format, payload)));
+    }
+    context
+}
+
+/// Normalized outcome of the event: `success`, `failure` or `other`
+fn outcome(format: WebhookFormat, payload: &Value) -> &'static str {
+    let status = match format {
+        WebhookFormat::GitHub => ["workflow_run.conclusion", "check_run.conclusion", "state"]
+            .iter()
+            .find_map(|path| template::lookup(payload, path)),
+        WebhookFormat::GitLab => template::lookup(payload, "object_attributes.status"),
+        WebhookFormat::Grafana => payload.get("status").or_else(|| payload.get("state")),
+        WebhookFormat::Alertmanager => payload.get("status"),
+        WebhookFormat::Generic => payload.get("status"),
+    };
+
+    match status.and_then(Value::as_str).unwrap_or_default() {
+        "success" | "succeeded" | "passed" | "ok" | "resolved" => "success",
+        "failure" | "failed" | "error" | "alerting" | "firing" | "timed_out" => "failure",
+        _ => "other",
+    }
+}
+
+/// Notification template used when a route does not set one
+///
+/// Generic payloads have no default template; they are used as they are.
+pub fn default_template(format: WebhookFormat, context: &Value) -> Value {
+    let event = context
+        .get("event")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let kind = context
+        .get("object_kind")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    let text = match (format, event, kind) {
+        (WebhookFormat::Generic, ..) => return json!({ "text": "{{text}}" }),
+        (WebhookFormat::GitHub, "workflow_run", _) => {
+            "{{repository.name}}: {{workflow_run.name}} {{workflow_run.conclusion}}"
+        }
+        (WebhookFormat::GitHub, "push", _) => "{{repository.name}}: push by {{pusher.name}}",
+        (WebhookFormat::GitHub, "pull_request", _) => {
+            "{{repository.name}}: PR #{{number}} {{action}}"
+        }
+        (WebhookFormat::GitHub, ..) => "{{repository.name}}: {{event}} {{action}}",
+        (WebhookFormat::GitLab, _, "pipeline") => {
+            "{{project.name}}: pipeline {{object_attributes.status}}"
+        }
+        (WebhookFormat::GitLab, _, "push") => "{{project.name}}: push by {{user_name}}",
+        (WebhookFormat::GitLab, _, "merge_request") => {
+            "{{project.name}}: MR {{object_attributes.action}} {{object_attributes.title}}"
+        }
+        (WebhookFormat::GitLab, ..) => "{{project.name}}: {{object_kind}}",
+        (WebhookFormat::Grafana, ..) => "{{title}}",
+        (WebhookFormat::Alertmanager, ..) => "[{{status}}] {{commonLabels.alertname}}",
+    };
+
+    let mut notification = json!({ "text": text });
+    let color = match context.get("outcome").and_then(Value::as_str) {
+        Some("success") => Some(Color::GREEN),
+        Some("failure") => Some(Color::RED),
+        _ => None,
+    };
+    if let Some(color) = color {
+        notification["color"] = json!(color);
+    }
+    notification
+}
+
+/// Build the notification for a webhook payload
+///
+/// `template` is a notification-shaped JSON template; without one, the
+/// format's default is used.
+pub fn to_notification(
+    format: WebhookFormat,
+    event: Option<&str>,
+    payload: &Value,
+    template: Option<&Value>,
+) -> Result<Notification> {
+    let context = context(format, event, payload);
+    let rendered = match template {
+        Some(template) => template::render_value(template, &context)?,
+        None if format == WebhookFormat::Generic => payload.clone(),
+        None => template::render_value(&default_template(format, &context), &context)?,
+    };
+
+    serde_json::from_value(rendered)
+        .map_err(|e| AwtrixError::Validation(format!("Invalid notification: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_github_workflow_run() {
+        let payload = json!({
+            "repository": { "name": "awtrix3-rs" },
+            "workflow_run": { "name": "CI", "conclusion": "failure" },
+        });
+
+        let notification =
+            to_notification(WebhookFormat::GitHub, Some("workflow_run"), &payload, None).unwrap();
+        assert_eq!(notification.text.as_deref(), Some("awtrix3-rs: CI failure"));
+        assert_eq!(notification.color, Some(Color::RED));
+    }
+
+    #[test]
+    fn test_gitlab_pipeline() {
+        let payload = json!({
+            "object_kind": "pipeline",
+            "project": { "name": "infra" },
+            "object_attributes": { "status": "success" },
+        });
+
+        let notification = to_notification(WebhookFormat::GitLab, None, &payload, None).unwrap();
+        assert_eq!(
+            notification.text.as_deref(),
+            Some("infra: pipeline success")
+        );
+        assert_eq!(notification.color, Some(Color::GREEN));
+    }
+
+    #[test]
+    fn test_generic_payload_is_notification() {
+        let payload = json!({ "text": "Coffee is ready", "icon": 1234 });
+
+        let notification = to_notification(WebhookFormat::Generic, None, &payload, None).unwrap();
+        assert_eq!(notification.text.as_deref(), Some("Coffee is ready"));
+        assert_eq!(notification.icon, Some(1234));
+    }
+
+    #[test]
+    fn test_custom_template() {
+        let payload = json!({ "title": "Disk full", "state": "alerting" });
+        let template = json!({ "text": "⚠ {{title}}", "color": "#FF8800", "hold": true });
+
+        let notification =
+            to_notification(WebhookFormat::Grafana, None, &payload, Some(&template)).unwrap();
+        assert_eq!(notification.text.as_deref(), Some("⚠ Disk full"));
+        assert_eq!(notification.color, Some(Color::new(255, 136, 0)));
+        assert_eq!(notification.hold, Some(true));
+    }
+}
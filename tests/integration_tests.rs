@@ -131,6 +131,72 @@ fn test_client_creation() {
     let client = Client::new("awtrix.local");
     assert!(client.is_ok());
 
+    // IPv6 literals and reverse proxy path prefixes
+    let client = Client::new("fe80::1").unwrap();
+    assert_eq!(client.base_url().as_str(), "http://[fe80::1]/");
+    let client = Client::new("https://proxy/awtrix/lobby").unwrap();
+    assert_eq!(client.base_url().as_str(), "https://proxy/awtrix/lobby/");
+
+    // Invalid addresses are rejected up front
+    assert!(Client::new("ftp://awtrix.local").is_err());
+
     // The client should be created successfully even if host is unreachable
     // since we don't test connectivity during creation
 }
+
+/// Test that the example fleet file parses and resolves
+#[test]
+fn test_fleet_example() {
+    use awtrix3::config::Fleet;
+
+    let fleet = Fleet::load("examples/fleet/office.yaml").expect("Fleet example should parse");
+    let devices = fleet.resolve().expect("Fleet example should resolve");
+
+    assert!(devices.contains_key("lobby"));
+    assert!(devices.contains_key("meeting-room"));
+    assert_eq!(
+        devices["lobby"].settings.as_ref().unwrap().brightness,
+        Some(150)
+    );
+}
+
+/// Test that stats shaped like several firmware versions deserialize
+///
+/// The fixtures are synthetic, see `tests/fixtures/stats/README.md`.
+#[test]
+fn test_stats_fixtures() {
+    use awtrix3::models::Stats;
+
+    let fixtures = [
+        "synthetic-v0.90.json",
+        "synthetic-v0.96.json",
+        "synthetic-v0.98.json",
+    ];
+
+    for fixture in &fixtures {
+        let path = format!("tests/fixtures/stats/{}", fixture);
+        let content = fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("Failed to read stats fixture: {}", path));
+
+        let stats: Result<Stats, _> = serde_json::from_str(&content);
+        assert!(
+            stats.is_ok(),
+            "Stats fixture should parse correctly: {} - Error: {:?}",
+            fixture,
+            stats.err()
+        );
+
+        let stats = stats.unwrap();
+        assert!(
+            stats.uptime.is_some(),
+            "Stats should have uptime: {}",
+            fixture
+        );
+        assert!(
+            stats.version.is_some(),
+            "Stats should have version: {}",
+            fixture
+        );
+        assert!(stats.uid.is_some(), "Stats should have uid: {}", fixture);
+    }
+}